@@ -1,6 +1,6 @@
 //! CLI command definitions and handlers
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
 /// VectDB - Vector Database CLI for Semantic Search
@@ -30,7 +30,10 @@ pub enum Commands {
     },
 
     /// Ingest documents into the vector database
-    Ingest {
+    Ingest(IngestArgs),
+
+    /// Estimate how long ingesting a source would take
+    Estimate {
         /// Source file or directory path
         source: PathBuf,
 
@@ -49,14 +52,47 @@ pub enum Commands {
         /// Process directories recursively
         #[arg(short, long)]
         recursive: bool,
+
+        /// Number of chunks to embed for measuring latency
+        #[arg(long, default_value = "3")]
+        samples: usize,
+    },
+
+    /// Preview how a file would be chunked (no database or Ollama needed)
+    Chunk {
+        /// File to chunk
+        file: PathBuf,
+
+        /// Chunking strategy (fixed, semantic, token, markdown)
+        #[arg(long, default_value = "fixed")]
+        strategy: String,
+
+        /// Chunk size (maximum size for the semantic and markdown strategies)
+        #[arg(short = 's', long, default_value = "512")]
+        size: usize,
+
+        /// Overlap between chunks (fixed strategy only)
+        #[arg(short = 'o', long, default_value = "50")]
+        overlap: usize,
+
+        /// Unit the overlap is counted in (chars, words, sentences)
+        #[arg(long, default_value = "chars")]
+        overlap_unit: String,
+
+        /// Output format (text, json)
+        #[arg(short = 'f', long, default_value = "text")]
+        format: String,
     },
 
     /// Search the vector database
-    Search {
-        /// Search query
-        query: String,
+    Search(SearchArgs),
 
-        /// Number of results to return
+    /// Run a batch of queries from a file
+    SearchBatch {
+        /// File with one query per line (optionally `query_id<TAB>query`)
+        queries: PathBuf,
+
+        /// Number of results to return per query
         #[arg(short = 'k', long, default_value = "10")]
         top_k: usize,
 
@@ -64,13 +100,27 @@ pub enum Commands {
         #[arg(short = 't', long, default_value = "0.0")]
         threshold: f32,
 
-        /// Show detailed similarity scores
-        #[arg(short = 'e', long)]
-        explain: bool,
+        /// Emit results in TREC run format for trec_eval
+        #[arg(long)]
+        trec_run: bool,
 
-        /// Output format (text, json, csv)
-        #[arg(short = 'f', long, default_value = "text")]
-        format: String,
+        /// Run name written in the last TREC column
+        #[arg(long, default_value = "vectdb")]
+        run_name: String,
+
+        /// Output format (text, ndjson); ndjson streams one line per query
+        #[arg(long, default_value = "text")]
+        output: String,
+    },
+
+    /// Check golden queries still retrieve their expected sources (exits non-zero on failure)
+    Regress {
+        /// File of `query -> expected_source` lines
+        golden: PathBuf,
+
+        /// A query passes if its expected source is within the top k results
+        #[arg(short = 'k', long, default_value = "5")]
+        top_k: usize,
     },
 
     /// Start the web server
@@ -87,9 +137,355 @@ pub enum Commands {
     /// Show database statistics
     Stats,
 
+    /// Print the JSON Schema of an API response type (stats, search, documents, ...)
+    #[command(hide = true)]
+    Schema {
+        /// Response type name
+        #[arg(value_name = "TYPE")]
+        type_name: String,
+    },
+
+    /// List documents in the database
+    List {
+        /// Maximum number of documents to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        /// Number of documents to skip
+        #[arg(long, default_value = "0")]
+        offset: usize,
+
+        /// Sort key (date, source, chunks)
+        #[arg(long, default_value = "date")]
+        sort: String,
+
+        /// Sort order (asc, desc)
+        #[arg(long, default_value = "desc")]
+        order: String,
+
+        /// Print the documents as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Delete a document, or every document from a source, with its chunks and embeddings
+    Delete {
+        /// Id of the document to delete
+        #[arg(long, required_unless_present = "source", conflicts_with = "source")]
+        id: Option<i64>,
+
+        /// Delete every document ingested from this source
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Show what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Delete documents left incomplete by interrupted ingests
+    Prune {
+        /// Show what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print random chunks to sanity-check ingestion quality
+    Sample {
+        /// Number of chunks to show
+        #[arg(short = 'n', long, default_value = "5")]
+        n: usize,
+
+        /// Seed for a reproducible sample
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+
+    /// Report documents whose chunks are not fully embedded
+    Coverage {
+        /// Embedding model to check (defaults to the configured model)
+        #[arg(short, long)]
+        model: Option<String>,
+    },
+
+    /// Re-embed all stored chunks with another model (no re-reading or re-chunking)
+    Reindex {
+        /// Embedding model to re-embed with
+        model: String,
+
+        /// Also re-embed chunks already embedded with the model
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Re-embed the chunks of a single document
+    ReindexDoc {
+        /// Document id (see `vectdb list`)
+        id: i64,
+
+        /// Embedding model (defaults to the configured model)
+        #[arg(short, long)]
+        model: Option<String>,
+    },
+
+    /// Export documents and their chunks to a JSONL dump
+    Export {
+        /// JSONL file to write, one document record per line
+        output: PathBuf,
+
+        /// Include embedding vectors (base64-encoded) so the dump can be imported without re-embedding
+        #[arg(long)]
+        include_vectors: bool,
+    },
+
+    /// Import documents from a JSONL dump
+    Import {
+        /// JSONL file with one document record per line
+        input: PathBuf,
+
+        /// Continue an interrupted import from its last checkpoint
+        #[arg(long)]
+        resume: bool,
+
+        /// Number of records committed per transaction
+        #[arg(long, default_value = "500")]
+        batch_size: usize,
+    },
+
+    /// Copy the documents of another database into this one
+    Merge {
+        /// Database file to merge from
+        other: PathBuf,
+    },
+
+    /// View or change configuration settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Re-encode stored embeddings in a different precision (no re-embedding)
+    Recompress {
+        /// Target storage precision (f32, f16)
+        #[arg(long, default_value = "f16")]
+        target_precision: String,
+
+        /// Number of embeddings rewritten per transaction
+        #[arg(long, default_value = "1000")]
+        batch_size: usize,
+    },
+
+    /// Manage the cache of embeddings reused for identical chunk text
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
     /// Optimize database (vacuum and analyze)
-    Optimize,
+    Optimize {
+        /// Reclaim at most N free pages with an incremental vacuum instead of
+        /// a full VACUUM (no value or 0 reclaims all free pages)
+        #[arg(long, value_name = "PAGES", num_args = 0..=1, default_missing_value = "0")]
+        incremental: Option<u32>,
+    },
 
     /// List available Ollama models
-    Models,
+    Models {
+        /// Output format (text, json)
+        #[arg(short = 'f', long, default_value = "text")]
+        format: String,
+    },
+
+    /// Embed a probe text to report a model's dimension, vector norm and latency
+    ModelInfo {
+        /// Model to probe (defaults to the configured model)
+        model: Option<String>,
+    },
+}
+
+/// Operations of the `cache` command
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Remove all cached embeddings
+    Clear,
+}
+
+/// Operations of the `config` command
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the full configuration
+    Show,
+
+    /// Print one setting (e.g. ollama.timeout_seconds)
+    Get {
+        /// Dotted setting key
+        key: String,
+    },
+
+    /// Change one setting and save the configuration file
+    Set {
+        /// Dotted setting key
+        key: String,
+
+        /// New value
+        value: String,
+    },
+}
+
+/// Arguments for the search command
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// Search query
+    pub query: String,
+
+    /// Number of results to return [default: search.default_top_k from the config, 10]
+    #[arg(short = 'k', long)]
+    pub top_k: Option<usize>,
+
+    /// Similarity threshold (0.0-1.0) [default: search.similarity_threshold from the config, 0.0]
+    #[arg(short = 't', long)]
+    pub threshold: Option<f32>,
+
+    /// Keep only results scoring at least this fraction of the best result (0.0-1.0)
+    #[arg(long, value_name = "RATIO", conflicts_with = "threshold")]
+    pub relative_threshold: Option<f32>,
+
+    /// Truncate the query embedding to N dimensions (must match the ingest setting)
+    #[arg(long, value_name = "N")]
+    pub dimensions: Option<usize>,
+
+    /// Preview each result with its sentence closest to the query (one extra embed per sentence)
+    #[arg(long)]
+    pub smart_snippet: bool,
+
+    /// Show detailed similarity scores
+    #[arg(short = 'e', long)]
+    pub explain: bool,
+
+    /// Output format (text, json, csv)
+    #[arg(short = 'f', long, default_value = "text")]
+    pub format: String,
+
+    /// Print the SQL query plan used for the search
+    #[arg(long)]
+    pub explain_sql: bool,
+
+    /// Write results to a file instead of stdout
+    #[arg(long, value_name = "FILE")]
+    pub output_file: Option<PathBuf>,
+
+    /// Only search within these document ids (comma-separated, e.g. 1,2,3)
+    #[arg(long, value_name = "IDS")]
+    pub in_docs: Option<String>,
+
+    /// Re-rank with Maximal Marginal Relevance (0.0 = most diverse, 1.0 = pure relevance)
+    #[arg(long, value_name = "LAMBDA")]
+    pub mmr: Option<f32>,
+
+    /// Blend keyword (BM25) matches into the ranking; ALPHA weights vector vs keyword score
+    #[arg(long, value_name = "ALPHA", num_args = 0..=1, default_missing_value = "0.5")]
+    pub hybrid: Option<f32>,
+
+    /// Only include these fields in JSON output (e.g. source,similarity,content)
+    #[arg(long, value_name = "FIELDS")]
+    pub fields: Option<String>,
+
+    /// Boost results whose metadata matches (key=value:factor, repeatable)
+    #[arg(long, value_name = "KEY=VALUE:FACTOR")]
+    pub boost: Vec<String>,
+
+    /// Demote results that resemble this text ("like the query, but not like this")
+    #[arg(long = "not", value_name = "TEXT")]
+    pub exclude: Option<String>,
+}
+
+/// Arguments for the ingest command
+#[derive(Args, Debug)]
+pub struct IngestArgs {
+    /// Source file or directory path, or `-` to read one document from stdin
+    pub source: PathBuf,
+
+    /// Embedding model to use (defaults to the configured model)
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// Chunking strategy: fixed, semantic, token or markdown (defaults to chunking.strategy)
+    #[arg(long)]
+    pub strategy: Option<String>,
+
+    /// Maximum chunk size (defaults to chunking.max_chunk_size)
+    #[arg(short = 's', long)]
+    pub chunk_size: Option<usize>,
+
+    /// Overlap size between chunks (defaults to chunking.overlap_size)
+    #[arg(short = 'o', long)]
+    pub overlap: Option<usize>,
+
+    /// Unit the overlap is counted in: chars, words, sentences (defaults to chunking.overlap_unit)
+    #[arg(long)]
+    pub overlap_unit: Option<String>,
+
+    /// Process directories recursively
+    #[arg(short, long)]
+    pub recursive: bool,
+
+    /// Follow symbolic links (may reach files outside the source directory)
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// Encoding for files that are not valid UTF-8 (e.g. windows-1252, latin1)
+    #[arg(long)]
+    pub encoding: Option<String>,
+
+    /// Skip files smaller than this many bytes
+    #[arg(long, value_name = "BYTES")]
+    pub min_file_size: Option<u64>,
+
+    /// Skip files larger than this many bytes
+    #[arg(long, value_name = "BYTES")]
+    pub max_file_size: Option<u64>,
+
+    /// Only ingest files under the source directory matching this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Skip files under the source directory matching this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Include dotfiles and dot-directories such as .git (skipped by default)
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// Skip files matched by .gitignore files in and above the source directory
+    #[arg(long)]
+    pub respect_gitignore: bool,
+
+    /// Also ingest source code files found in directories (.rs, .py, ...)
+    #[arg(long)]
+    pub include_code: bool,
+
+    /// Parts of source code files to index (all, code-only, comments-only)
+    #[arg(long, default_value = "all")]
+    pub code_filter: String,
+
+    /// Process files in this order: name, size-asc, size-desc, mtime (oldest first) [default: filesystem order]
+    #[arg(long, value_name = "ORDER")]
+    pub order: Option<String>,
+
+    /// Faster load with durability off and indices rebuilt at the end (unsafe if interrupted)
+    #[arg(long)]
+    pub bulk: bool,
+
+    /// Always call Ollama instead of reusing cached embeddings of identical chunk text
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Truncate embeddings to their first N dimensions, re-normalized (Matryoshka models)
+    #[arg(long, value_name = "N")]
+    pub dimensions: Option<usize>,
+
+    /// Show how files would be chunked without writing to the database or calling Ollama
+    #[arg(long)]
+    pub dry_run: bool,
 }
@@ -1,5 +1,202 @@
 //! External service clients
 
 pub mod ollama;
+pub mod openai;
 
 pub use ollama::OllamaClient;
+pub use openai::OpenAiClient;
+
+use crate::config::{Config, Provider};
+use crate::error::Result;
+use ollama::{ModelInfo, RetryStats};
+use std::future::Future;
+
+/// A service that turns text into embedding vectors
+///
+/// Implemented by [`OllamaClient`] and [`OpenAiClient`], so the ingestion and
+/// search services can run against either backend.
+pub trait EmbeddingProvider: Send + Sync {
+    /// Generate an embedding for a single text
+    fn embed(&self, model: &str, text: &str) -> impl Future<Output = Result<Vec<f32>>> + Send;
+
+    /// Generate embeddings for several texts, in input order
+    fn embed_batch(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> impl Future<Output = Result<Vec<Vec<f32>>>> + Send;
+
+    /// Check whether the service is reachable
+    fn health_check(&self) -> impl Future<Output = Result<bool>> + Send;
+
+    /// List the models the service offers
+    fn list_models(&self) -> impl Future<Output = Result<Vec<ModelInfo>>> + Send;
+
+    /// Generate embeddings, also reporting the retries they needed
+    ///
+    /// Providers that do not retry report no retries.
+    fn embed_batch_with_stats(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> impl Future<Output = Result<(Vec<Vec<f32>>, RetryStats)>> + Send {
+        async move { Ok((self.embed_batch(model, texts).await?, RetryStats::default())) }
+    }
+
+    /// Dimension the provider truncates embeddings to, if any
+    fn truncate_dimension(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl EmbeddingProvider for OllamaClient {
+    fn embed(&self, model: &str, text: &str) -> impl Future<Output = Result<Vec<f32>>> + Send {
+        OllamaClient::embed(self, model, text)
+    }
+
+    fn embed_batch(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> impl Future<Output = Result<Vec<Vec<f32>>>> + Send {
+        OllamaClient::embed_batch(self, model, texts)
+    }
+
+    fn health_check(&self) -> impl Future<Output = Result<bool>> + Send {
+        OllamaClient::health_check(self)
+    }
+
+    fn list_models(&self) -> impl Future<Output = Result<Vec<ModelInfo>>> + Send {
+        OllamaClient::list_models(self)
+    }
+
+    fn embed_batch_with_stats(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> impl Future<Output = Result<(Vec<Vec<f32>>, RetryStats)>> + Send {
+        OllamaClient::embed_batch_with_stats(self, model, texts)
+    }
+
+    fn truncate_dimension(&self) -> Option<usize> {
+        OllamaClient::truncate_dimension(self)
+    }
+}
+
+/// The embedding provider selected by the `provider` setting
+#[derive(Clone)]
+pub enum Embedder {
+    Ollama(OllamaClient),
+    OpenAi(OpenAiClient),
+}
+
+impl Embedder {
+    /// Create the client for the configured provider
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(match config.provider {
+            Provider::Ollama => Embedder::Ollama(OllamaClient::from_config(&config.ollama)?),
+            Provider::OpenAi => Embedder::OpenAi(OpenAiClient::from_config(&config.openai)?),
+        })
+    }
+
+    /// The Ollama client, for checks only Ollama supports (versions, pulls)
+    pub fn as_ollama(&self) -> Option<&OllamaClient> {
+        match self {
+            Embedder::Ollama(client) => Some(client),
+            Embedder::OpenAi(_) => None,
+        }
+    }
+
+    /// Check whether the provider offers a model
+    pub async fn has_model(&self, model: &str) -> Result<bool> {
+        match self {
+            Embedder::Ollama(client) => client.has_model(model).await,
+            Embedder::OpenAi(client) => {
+                Ok(client.list_models().await?.iter().any(|m| m.name == model))
+            }
+        }
+    }
+
+    /// Check that the model really embeds text, not just that it is listed
+    pub async fn embed_health_check(&self, model: &str) -> Result<bool> {
+        match self {
+            Embedder::Ollama(client) => client.embed_health_check(model).await,
+            Embedder::OpenAi(client) => Ok(client
+                .embed(model, ollama::PROBE_TEXT)
+                .await
+                .is_ok_and(|v| !v.is_empty() && v.iter().all(|x| x.is_finite()))),
+        }
+    }
+
+    /// Digest identifying the installed version of a model
+    ///
+    /// Only Ollama reports one; other providers return `None`.
+    pub async fn model_digest(&self, model: &str) -> Result<Option<String>> {
+        match self {
+            Embedder::Ollama(client) => client.model_digest(model).await,
+            Embedder::OpenAi(_) => Ok(None),
+        }
+    }
+}
+
+impl From<OllamaClient> for Embedder {
+    fn from(client: OllamaClient) -> Self {
+        Embedder::Ollama(client)
+    }
+}
+
+impl From<OpenAiClient> for Embedder {
+    fn from(client: OpenAiClient) -> Self {
+        Embedder::OpenAi(client)
+    }
+}
+
+impl EmbeddingProvider for Embedder {
+    async fn embed(&self, model: &str, text: &str) -> Result<Vec<f32>> {
+        match self {
+            Embedder::Ollama(client) => client.embed(model, text).await,
+            Embedder::OpenAi(client) => client.embed(model, text).await,
+        }
+    }
+
+    async fn embed_batch(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        match self {
+            Embedder::Ollama(client) => client.embed_batch(model, texts).await,
+            Embedder::OpenAi(client) => client.embed_batch(model, texts).await,
+        }
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        match self {
+            Embedder::Ollama(client) => client.health_check().await,
+            Embedder::OpenAi(client) => client.health_check().await,
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        match self {
+            Embedder::Ollama(client) => client.list_models().await,
+            Embedder::OpenAi(client) => client.list_models().await,
+        }
+    }
+
+    async fn embed_batch_with_stats(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, RetryStats)> {
+        match self {
+            Embedder::Ollama(client) => client.embed_batch_with_stats(model, texts).await,
+            Embedder::OpenAi(client) => {
+                EmbeddingProvider::embed_batch_with_stats(client, model, texts).await
+            }
+        }
+    }
+
+    fn truncate_dimension(&self) -> Option<usize> {
+        match self {
+            Embedder::Ollama(client) => client.truncate_dimension(),
+            Embedder::OpenAi(_) => None,
+        }
+    }
+}
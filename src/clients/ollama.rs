@@ -3,10 +3,23 @@
 //! Provides a client to interact with a local Ollama instance for generating
 //! text embeddings using various models.
 
+use crate::config::{OllamaConfig, RetryConfig};
 use crate::error::{Result, VectDbError};
+use crate::util::{format_relative, format_utc};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::DateTime;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use futures::stream::{self, StreamExt};
 use reqwest::Client;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
@@ -16,6 +29,17 @@ pub struct OllamaClient {
     base_url: String,
     client: Client,
     timeout: Duration,
+    retry: RetryConfig,
+    retry_jitter: bool,
+    truncate_dimension: Option<usize>,
+    embedding_format: EmbeddingFormat,
+    use_batch_endpoint: bool,
+    compress_requests: bool,
+    max_concurrent_requests: usize,
+    /// Set once the server answers 404 for `/api/embed`, shared across clones
+    batch_endpoint_missing: Arc<AtomicBool>,
+    /// Server version, fetched once and shared across clones
+    version: Arc<OnceCell<Option<OllamaVersion>>>,
 }
 
 impl OllamaClient {
@@ -34,9 +58,86 @@ impl OllamaClient {
             base_url,
             client,
             timeout,
+            retry: RetryConfig::default(),
+            retry_jitter: false,
+            truncate_dimension: None,
+            embedding_format: EmbeddingFormat::Float32,
+            use_batch_endpoint: false,
+            compress_requests: false,
+            max_concurrent_requests: 1,
+            batch_endpoint_missing: Arc::new(AtomicBool::new(false)),
+            version: Arc::new(OnceCell::new()),
         })
     }
 
+    /// Create a client from the `[ollama]` configuration section
+    pub fn from_config(config: &OllamaConfig) -> Result<Self> {
+        Ok(Self::new(config.base_url.clone(), config.timeout_seconds)?
+            .with_retry_policy(config.retry)
+            .with_retry_jitter(config.retry_jitter)
+            .with_truncate_dimension(config.truncate_dimension)
+            .with_batch_endpoint(config.use_batch_endpoint)
+            .with_compressed_requests(config.compress_requests)
+            .with_max_concurrent_requests(config.max_concurrent_requests)
+            .with_embedding_format(if config.binary_embeddings {
+                EmbeddingFormat::Binary
+            } else {
+                EmbeddingFormat::Float32
+            }))
+    }
+
+    /// Set how many times, and how patiently, failed embedding requests are retried
+    pub fn with_retry_policy(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Enable or disable randomized jitter on retry backoff
+    pub fn with_retry_jitter(mut self, enabled: bool) -> Self {
+        self.retry_jitter = enabled;
+        self
+    }
+
+    /// Truncate embeddings to their first `dimension` values and re-normalize
+    ///
+    /// Only meaningful for Matryoshka-trained models such as `nomic-embed-text`,
+    /// where a prefix of the vector is itself a valid embedding.
+    pub fn with_truncate_dimension(mut self, dimension: Option<usize>) -> Self {
+        self.truncate_dimension = dimension;
+        self
+    }
+
+    /// Set how byte-encoded embeddings returned by Ollama are decoded
+    pub fn with_embedding_format(mut self, format: EmbeddingFormat) -> Self {
+        self.embedding_format = format;
+        self
+    }
+
+    /// Send batches to the multi-input `/api/embed` endpoint instead of one request per text
+    pub fn with_batch_endpoint(mut self, enabled: bool) -> Self {
+        self.use_batch_endpoint = enabled;
+        self
+    }
+
+    /// Gzip embedding request bodies of at least [`COMPRESSION_THRESHOLD_BYTES`]
+    pub fn with_compressed_requests(mut self, enabled: bool) -> Self {
+        self.compress_requests = enabled;
+        self
+    }
+
+    /// Keep up to `limit` per-text embedding requests in flight at once
+    ///
+    /// Each request still retries on its own; results keep the input order.
+    pub fn with_max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.max_concurrent_requests = limit.max(1);
+        self
+    }
+
+    /// Dimension embeddings are truncated to, if any
+    pub fn truncate_dimension(&self) -> Option<usize> {
+        self.truncate_dimension
+    }
+
     /// Check if Ollama service is available
     pub async fn health_check(&self) -> Result<bool> {
         debug!("Performing health check on Ollama");
@@ -63,6 +164,58 @@ impl OllamaClient {
         }
     }
 
+    /// Check that Ollama can actually embed with `model`, not just that it is up
+    ///
+    /// `/api/tags` answers even when the embedding endpoint is broken or the
+    /// model fails to load, so this embeds a tiny probe text once, without
+    /// retries, and requires a non-empty, finite vector back.
+    pub async fn embed_health_check(&self, model: &str) -> Result<bool> {
+        debug!("Performing embedding health check with model {}", model);
+
+        let url = format!("{}/api/embeddings", self.base_url);
+        let request = EmbedRequest {
+            model: model.to_string(),
+            prompt: PROBE_TEXT.to_string(),
+        };
+
+        let response = match self.client.post(&url).json(&request).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                warn!(
+                    "Embedding health check failed with status: {}",
+                    response.status()
+                );
+                return Ok(false);
+            }
+            Err(e) => {
+                warn!("Embedding health check failed: {}", e);
+                return Ok(false);
+            }
+        };
+
+        let vector = match response.json::<EmbedResponse>().await {
+            Ok(body) => body.embedding.into_vector(self.embedding_format),
+            Err(e) => Err(VectDbError::EmbeddingFailed(format!(
+                "Failed to parse response: {}",
+                e
+            ))),
+        };
+        match vector {
+            Ok(vector) if !vector.is_empty() && vector.iter().all(|v| v.is_finite()) => {
+                info!("Embedding health check passed");
+                Ok(true)
+            }
+            Ok(_) => {
+                warn!("Embedding health check returned an empty or non-finite vector");
+                Ok(false)
+            }
+            Err(e) => {
+                warn!("Embedding health check failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+
     /// Generate an embedding for a single text
     pub async fn embed(&self, model: &str, text: &str) -> Result<Vec<f32>> {
         let embeddings = self.embed_batch(model, &[text.to_string()]).await?;
@@ -74,8 +227,98 @@ impl OllamaClient {
 
     /// Generate embeddings for a batch of texts with retry logic
     pub async fn embed_batch(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let (embeddings, _) = self.embed_batch_with_stats(model, texts).await?;
+        Ok(embeddings)
+    }
+
+    /// Generate embeddings for a batch of texts, reporting the retries they needed
+    ///
+    /// Uses the multi-input `/api/embed` endpoint when `use_batch_endpoint` is
+    /// enabled, otherwise one `/api/embeddings` request per text.
+    pub async fn embed_batch_with_stats(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, RetryStats)> {
+        if self.use_batch_endpoint {
+            self.embed_many_with_stats(model, texts).await
+        } else {
+            self.embed_each_with_stats(model, texts).await
+        }
+    }
+
+    /// Generate embeddings for a batch of texts in a single `/api/embed` request
+    ///
+    /// Falls back to one request per text when the server predates the endpoint.
+    pub async fn embed_many(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let (embeddings, _) = self.embed_many_with_stats(model, texts).await?;
+        Ok(embeddings)
+    }
+
+    async fn embed_many_with_stats(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, RetryStats)> {
+        if texts.is_empty() {
+            return Ok((Vec::new(), RetryStats::default()));
+        }
+        if self.batch_endpoint_missing.load(Ordering::Relaxed) {
+            return self.embed_each_with_stats(model, texts).await;
+        }
+
+        debug!(
+            "Generating embeddings for {} texts using model {} (batched)",
+            texts.len(),
+            model
+        );
+
+        let url = format!("{}/api/embed", self.base_url);
+        let request = BatchEmbedRequest {
+            model,
+            input: texts,
+        };
+        let input_chars = texts.iter().map(|t| t.chars().count()).sum();
+
+        let (response, stats) = self
+            .post_with_retry(&url, &request, model, input_chars)
+            .await?;
+
+        if response.status().as_u16() == 404 {
+            let error_text = response.text().await.unwrap_or_default();
+            if error_text.to_lowercase().contains("model") {
+                return Err(VectDbError::EmbeddingFailed(format!(
+                    "Model '{}' not found. {}",
+                    model, error_text
+                )));
+            }
+            warn!("Ollama has no /api/embed endpoint; falling back to per-text requests");
+            self.batch_endpoint_missing.store(true, Ordering::Relaxed);
+            return self.embed_each_with_stats(model, texts).await;
+        }
+
+        let batch_response: BatchEmbedResponse = response.json().await.map_err(|e| {
+            VectDbError::EmbeddingFailed(format!("Failed to parse response: {}", e))
+        })?;
+
+        let embeddings = batch_response
+            .embeddings
+            .into_iter()
+            .map(|payload| payload.into_vector(self.embedding_format))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok((self.finish_batch(model, texts.len(), embeddings)?, stats))
+    }
+
+    /// Generate embeddings with one `/api/embeddings` request per text
+    async fn embed_each_with_stats(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> Result<(Vec<Vec<f32>>, RetryStats)> {
+        let mut stats = RetryStats::default();
         if texts.is_empty() {
-            return Ok(Vec::new());
+            return Ok((Vec::new(), stats));
         }
 
         debug!(
@@ -86,55 +329,152 @@ impl OllamaClient {
 
         let url = format!("{}/api/embeddings", self.base_url);
 
-        let mut embeddings = Vec::with_capacity(texts.len());
-
-        // Process texts one at a time (Ollama's embeddings endpoint takes one input at a time)
-        for (idx, text) in texts.iter().enumerate() {
-            let request = EmbedRequest {
-                model: model.to_string(),
-                prompt: text.clone(),
-            };
+        // Ollama's embeddings endpoint takes one input at a time, so keep
+        // several requests in flight; they may complete in any order
+        let requests: Vec<_> = texts
+            .iter()
+            .enumerate()
+            .map(|(idx, text)| self.embed_indexed(&url, model, idx, text))
+            .collect();
+        let mut responses = stream::iter(requests).buffer_unordered(self.max_concurrent_requests);
 
-            // Retry logic with exponential backoff
-            let embedding = self.embed_with_retry(&url, &request).await?;
-            embeddings.push(embedding);
+        let mut slots: Vec<Option<Vec<f32>>> = vec![None; texts.len()];
+        let mut completed = 0;
+        while let Some(response) = responses.next().await {
+            // Dropping the stream on error cancels the requests still in flight
+            let (idx, embedding, retries) = response?;
+            stats.merge(&retries);
+            slots[idx] = Some(embedding);
 
-            if (idx + 1) % 10 == 0 {
-                debug!("Generated {}/{} embeddings", idx + 1, texts.len());
+            completed += 1;
+            if completed % 10 == 0 {
+                debug!("Generated {}/{} embeddings", completed, texts.len());
             }
         }
 
+        let embeddings = slots.into_iter().flatten().collect();
+        Ok((self.finish_batch(model, texts.len(), embeddings)?, stats))
+    }
+
+    /// Embed one text of a batch, tagged with its position in the batch
+    async fn embed_indexed(
+        &self,
+        url: &str,
+        model: &str,
+        idx: usize,
+        text: &str,
+    ) -> Result<(usize, Vec<f32>, RetryStats)> {
+        let request = EmbedRequest {
+            model: model.to_string(),
+            prompt: text.to_string(),
+        };
+
+        // Retry logic with exponential backoff
+        let (embedding, retries) = self.embed_with_retry(url, &request).await?;
+        Ok((idx, embedding, retries))
+    }
+
+    /// Check the embedding count and apply dimension truncation
+    fn finish_batch(
+        &self,
+        model: &str,
+        expected: usize,
+        mut embeddings: Vec<Vec<f32>>,
+    ) -> Result<Vec<Vec<f32>>> {
+        ensure_embedding_count(model, expected, &embeddings)?;
+
+        if let Some(dimension) = self.truncate_dimension {
+            embeddings = embeddings
+                .into_iter()
+                .map(|vector| truncate_embedding(vector, dimension))
+                .collect::<Result<_>>()?;
+        }
+
         info!("Successfully generated {} embeddings", embeddings.len());
 
         Ok(embeddings)
     }
 
     /// Generate a single embedding with retry logic
-    async fn embed_with_retry(&self, url: &str, request: &EmbedRequest) -> Result<Vec<f32>> {
-        const MAX_RETRIES: u32 = 3;
-        const INITIAL_BACKOFF_MS: u64 = 100;
+    ///
+    /// Returns the embedding together with the retries it took.
+    async fn embed_with_retry(
+        &self,
+        url: &str,
+        request: &EmbedRequest,
+    ) -> Result<(Vec<f32>, RetryStats)> {
+        let (response, stats) = self
+            .post_with_retry(url, request, &request.model, request.prompt.chars().count())
+            .await?;
+
+        if response.status().as_u16() == 404 {
+            // Model not found - no point in retrying
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Model not found".to_string());
+            return Err(VectDbError::EmbeddingFailed(format!(
+                "Model '{}' not found. {}",
+                request.model, error_text
+            )));
+        }
+
+        let embed_response: EmbedResponse = response.json().await.map_err(|e| {
+            VectDbError::EmbeddingFailed(format!("Failed to parse response: {}", e))
+        })?;
+        let embedding = embed_response
+            .embedding
+            .into_vector(self.embedding_format)?;
+        Ok((embedding, stats))
+    }
+
+    /// POST an embedding request, retrying transient failures with exponential backoff
+    ///
+    /// Returns the first successful or 404 response together with the retries it took.
+    async fn post_with_retry<R: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        request: &R,
+        model: &str,
+        input_chars: usize,
+    ) -> Result<(reqwest::Response, RetryStats)> {
+        let max_retries = self.retry.max_retries;
+
+        let mut body = serde_json::to_vec(request)?;
+        let compressed = self.compress_requests && body.len() >= COMPRESSION_THRESHOLD_BYTES;
+        if compressed {
+            let original = body.len();
+            body = gzip(&body)?;
+            debug!(
+                "Compressed request body from {} to {} bytes",
+                original,
+                body.len()
+            );
+        }
 
         let mut retries = 0;
-        let mut backoff_ms = INITIAL_BACKOFF_MS;
+        let mut backoff_ms = self.retry.initial_backoff_ms.min(self.retry.max_backoff_ms);
+        let mut backoff = Duration::ZERO;
 
         loop {
-            match self.client.post(url).json(request).send().await {
+            let mut builder = self
+                .client
+                .post(url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if compressed {
+                builder = builder.header(CONTENT_ENCODING, "gzip");
+            }
+
+            match builder.send().await {
                 Ok(response) => {
-                    if response.status().is_success() {
-                        let embed_response: EmbedResponse = response.json().await.map_err(|e| {
-                            VectDbError::EmbeddingFailed(format!("Failed to parse response: {}", e))
-                        })?;
-                        return Ok(embed_response.embedding);
-                    } else if response.status().as_u16() == 404 {
-                        // Model not found - no point in retrying
-                        let error_text = response
-                            .text()
-                            .await
-                            .unwrap_or_else(|_| "Model not found".to_string());
-                        return Err(VectDbError::EmbeddingFailed(format!(
-                            "Model '{}' not found. {}",
-                            request.model, error_text
-                        )));
+                    if response.status().is_success() || response.status().as_u16() == 404 {
+                        let stats = RetryStats {
+                            retries,
+                            retried_inputs: usize::from(retries > 0),
+                            backoff,
+                        };
+                        return Ok((response, stats));
                     } else {
                         // Server error - may be transient
                         let status = response.status();
@@ -143,43 +483,57 @@ impl OllamaClient {
                             .await
                             .unwrap_or_else(|_| "Unknown error".to_string());
 
-                        if retries < MAX_RETRIES {
+                        // Retrying cannot help an input the model refuses to accept
+                        if is_input_length_error(&error_text) {
+                            return Err(VectDbError::InputTooLong(format!(
+                                "Model '{}' rejected input of {} chars ({}): {}",
+                                model, input_chars, status, error_text
+                            )));
+                        }
+
+                        if retries < max_retries {
                             warn!(
                                 "Embedding request failed with status {} (attempt {}/{}): {}",
                                 status,
                                 retries + 1,
-                                MAX_RETRIES + 1,
+                                max_retries + 1,
                                 error_text
                             );
                             retries += 1;
-                            sleep(Duration::from_millis(backoff_ms)).await;
-                            backoff_ms *= 2; // Exponential backoff
+                            let delay = retry_delay(backoff_ms, self.retry_jitter);
+                            backoff += delay;
+                            sleep(delay).await;
+                            // Exponential backoff, capped
+                            backoff_ms =
+                                backoff_ms.saturating_mul(2).min(self.retry.max_backoff_ms);
                             continue;
                         } else {
                             return Err(VectDbError::EmbeddingFailed(format!(
                                 "Ollama API returned error {} after {} retries: {}",
-                                status, MAX_RETRIES, error_text
+                                status, max_retries, error_text
                             )));
                         }
                     }
                 }
                 Err(e) => {
                     // Network error - may be transient
-                    if retries < MAX_RETRIES {
+                    if retries < max_retries {
                         warn!(
                             "Network error during embedding request (attempt {}/{}): {}",
                             retries + 1,
-                            MAX_RETRIES + 1,
+                            max_retries + 1,
                             e
                         );
                         retries += 1;
-                        sleep(Duration::from_millis(backoff_ms)).await;
-                        backoff_ms *= 2;
+                        let delay = retry_delay(backoff_ms, self.retry_jitter);
+                        backoff += delay;
+                        sleep(delay).await;
+                        backoff_ms = backoff_ms.saturating_mul(2).min(self.retry.max_backoff_ms);
                         continue;
                     } else {
                         return Err(VectDbError::OllamaUnavailable(format!(
                             "Failed to connect to Ollama after {} retries: {}",
-                            MAX_RETRIES, e
+                            max_retries, e
                         )));
                     }
                 }
@@ -187,10 +541,80 @@ impl OllamaClient {
         }
     }
 
+    /// Embed a short probe text and report the shape of the returned vector
+    ///
+    /// The latency covers the whole round trip, including any retries.
+    pub async fn probe_embedding(&self, model: &str) -> Result<EmbeddingProbe> {
+        let url = format!("{}/api/embeddings", self.base_url);
+        let request = EmbedRequest {
+            model: model.to_string(),
+            prompt: PROBE_TEXT.to_string(),
+        };
+
+        let started = Instant::now();
+        let (vector, _) = self.embed_with_retry(&url, &request).await?;
+        Ok(EmbeddingProbe {
+            latency: started.elapsed(),
+            ..EmbeddingProbe::from_vector(&vector)
+        })
+    }
+
+    /// Check whether a model looks like an embedding model
+    ///
+    /// Returns a warning message when the probe fails or produces a degenerate
+    /// vector, which usually means a chat model was configured by mistake.
+    pub async fn check_embedding_model(&self, model: &str) -> Option<String> {
+        match self.probe_embedding(model).await {
+            Ok(probe) => probe.warning(model),
+            Err(e) => Some(format!(
+                "Model '{}' failed to produce a probe embedding ({}); it may not be an \
+                 embedding model. Try one of: {}",
+                model,
+                e,
+                RECOMMENDED_EMBEDDING_MODELS.join(", ")
+            )),
+        }
+    }
+
     /// List available models
     pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         debug!("Listing available models from Ollama");
 
+        let tags_response = self.fetch_tags().await?;
+
+        let models: Vec<ModelInfo> = tags_response
+            .models
+            .into_iter()
+            .map(|m| ModelInfo {
+                name: m.name,
+                size: m.size,
+                modified_at: m.modified_at,
+            })
+            .collect();
+
+        info!("Found {} models", models.len());
+
+        Ok(models)
+    }
+
+    /// Digest of the installed weights for `model`, if Ollama has it
+    ///
+    /// The digest changes when a model is re-pulled with new weights under the
+    /// same name, which makes previously stored embeddings stale.
+    pub async fn model_digest(&self, model: &str) -> Result<Option<String>> {
+        let tags_response = self.fetch_tags().await?;
+        let with_latest = format!("{}:latest", model);
+
+        Ok(tags_response
+            .models
+            .into_iter()
+            .find(|m| m.name == model || m.name == with_latest)
+            .map(|m| m.digest)
+            .filter(|digest| !digest.is_empty()))
+    }
+
+    /// Fetch the locally installed models from `/api/tags`
+    async fn fetch_tags(&self) -> Result<TagsResponse> {
         let url = format!("{}/api/tags", self.base_url);
 
         let response = self.client.get(&url).send().await.map_err(|e| {
@@ -204,23 +628,94 @@ impl OllamaClient {
             )));
         }
 
-        let tags_response: TagsResponse = response.json().await.map_err(|e| {
+        response
+            .json()
+            .await
+            .map_err(|e| VectDbError::OllamaUnavailable(format!("Failed to parse response: {}", e)))
+    }
+
+    /// Version of the Ollama server, from `/api/version`
+    ///
+    /// Fetched once and cached. Servers that predate the endpoint, or report a
+    /// version that does not parse, give `None`.
+    pub async fn version(&self) -> Result<Option<OllamaVersion>> {
+        self.version
+            .get_or_try_init(|| self.fetch_version())
+            .await
+            .copied()
+    }
+
+    async fn fetch_version(&self) -> Result<Option<OllamaVersion>> {
+        let url = format!("{}/api/version", self.base_url);
+
+        let response = self.client.get(&url).send().await.map_err(|e| {
+            VectDbError::OllamaUnavailable(format!("Failed to connect to Ollama: {}", e))
+        })?;
+
+        if response.status().as_u16() == 404 {
+            debug!("Ollama has no /api/version endpoint; assuming an old release");
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(VectDbError::OllamaUnavailable(format!(
+                "Ollama API returned error: {}",
+                response.status()
+            )));
+        }
+
+        let body: VersionResponse = response.json().await.map_err(|e| {
             VectDbError::OllamaUnavailable(format!("Failed to parse response: {}", e))
         })?;
+        match body.version.parse() {
+            Ok(version) => {
+                debug!("Ollama version {}", version);
+                Ok(Some(version))
+            }
+            Err(_) => {
+                warn!("Unrecognized Ollama version '{}'", body.version);
+                Ok(None)
+            }
+        }
+    }
 
-        let models: Vec<ModelInfo> = tags_response
-            .models
-            .into_iter()
-            .map(|m| ModelInfo {
-                name: m.name,
-                size: m.size,
-                modified_at: m.modified_at,
-            })
-            .collect();
+    /// Check the server version against `minimum` and what the enabled features need
+    ///
+    /// Returns a warning when the server is older than required, or when its
+    /// version cannot be determined. With `strict`, a server known to be too
+    /// old is an error instead.
+    pub async fn check_version(
+        &self,
+        minimum: Option<OllamaVersion>,
+        strict: bool,
+    ) -> Result<Option<String>> {
+        let mut required = minimum.map(|version| (version, "ollama.min_version"));
+        if self.use_batch_endpoint
+            && required.is_none_or(|(version, _)| version < BATCH_ENDPOINT_MIN_VERSION)
+        {
+            required = Some((BATCH_ENDPOINT_MIN_VERSION, "use_batch_endpoint"));
+        }
+        let Some((required, reason)) = required else {
+            return Ok(None);
+        };
 
-        info!("Found {} models", models.len());
+        let Some(found) = self.version().await? else {
+            return Ok(Some(format!(
+                "Could not determine the Ollama version; {} needs {} or newer",
+                reason, required
+            )));
+        };
+        if found >= required {
+            return Ok(None);
+        }
 
-        Ok(models)
+        let message = format!(
+            "Ollama {} is older than {}, which {} needs. Upgrade Ollama",
+            found, required, reason
+        );
+        if strict {
+            return Err(VectDbError::OllamaUnavailable(message));
+        }
+        Ok(Some(message))
     }
 
     /// Check if a specific model is available
@@ -249,6 +744,94 @@ impl OllamaClient {
         }))
     }
 
+    /// Pull a model into Ollama, waiting for the download to finish
+    pub async fn pull_model(&self, model: &str) -> Result<()> {
+        self.pull_model_with_progress(model, |progress| {
+            debug!("Pull {}: {}", model, progress.status);
+        })
+        .await
+    }
+
+    /// Pull a model, reporting each NDJSON progress event from `/api/pull`
+    pub async fn pull_model_with_progress<F>(&self, model: &str, mut on_progress: F) -> Result<()>
+    where
+        F: FnMut(&PullProgress),
+    {
+        info!("Pulling model {}", model);
+
+        let url = format!("{}/api/pull", self.base_url);
+        let request = PullRequest {
+            model: model.to_string(),
+            stream: true,
+        };
+
+        let mut response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .timeout(PULL_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| {
+                VectDbError::OllamaUnavailable(format!("Failed to connect to Ollama: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(VectDbError::OllamaUnavailable(format!(
+                "Failed to pull model {} ({}): {}",
+                model, status, error_text
+            )));
+        }
+
+        let mut buffer = Vec::new();
+        let mut succeeded = false;
+        loop {
+            let chunk = response.chunk().await.map_err(|e| {
+                VectDbError::OllamaUnavailable(format!("Pull stream interrupted: {}", e))
+            })?;
+            let done = chunk.is_none();
+            if let Some(bytes) = chunk {
+                buffer.extend_from_slice(&bytes);
+            } else if !buffer.is_empty() {
+                // Final event without a trailing newline
+                buffer.push(b'\n');
+            }
+
+            while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=pos).collect();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+
+                let progress: PullProgress = serde_json::from_slice(&line)?;
+                if let Some(error) = &progress.error {
+                    return Err(VectDbError::OllamaUnavailable(format!(
+                        "Failed to pull model {}: {}",
+                        model, error
+                    )));
+                }
+                succeeded |= progress.status == "success";
+                on_progress(&progress);
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        if !succeeded {
+            return Err(VectDbError::OllamaUnavailable(format!(
+                "Pull of model {} ended without a success status",
+                model
+            )));
+        }
+
+        info!("Pulled model {}", model);
+        Ok(())
+    }
+
     /// Get information about the client configuration
     pub fn info(&self) -> ClientInfo {
         ClientInfo {
@@ -258,6 +841,133 @@ impl OllamaClient {
     }
 }
 
+/// Request bodies smaller than this are sent uncompressed even when compression is on
+pub const COMPRESSION_THRESHOLD_BYTES: usize = 1024;
+
+/// Oldest Ollama release with the multi-input `/api/embed` endpoint
+pub const BATCH_ENDPOINT_MIN_VERSION: OllamaVersion = OllamaVersion::new(0, 3, 0);
+
+/// Upper bound on a model download, which can take far longer than a request
+const PULL_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Text embedded when probing a model
+pub(crate) const PROBE_TEXT: &str = "VectDB embedding probe";
+
+/// Smallest dimension considered plausible for a real embedding model
+const MIN_EMBEDDING_DIMENSION: usize = 64;
+
+/// Embedding models suggested when a misconfigured model is detected
+pub const RECOMMENDED_EMBEDDING_MODELS: [&str; 3] =
+    ["nomic-embed-text", "all-minilm", "mxbai-embed-large"];
+
+/// Shape of a probe embedding returned by a model
+#[derive(Debug, Clone)]
+pub struct EmbeddingProbe {
+    /// Number of dimensions in the returned vector
+    pub dimension: usize,
+
+    /// L2 norm of the returned vector
+    pub norm: f32,
+
+    /// Round-trip time of the probe request (zero when built from a vector)
+    pub latency: Duration,
+}
+
+impl EmbeddingProbe {
+    /// Summarize a probe vector
+    pub fn from_vector(vector: &[f32]) -> Self {
+        Self {
+            dimension: vector.len(),
+            norm: vector.iter().map(|x| x * x).sum::<f32>().sqrt(),
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// Whether the vector already has unit length
+    pub fn is_normalized(&self) -> bool {
+        (self.norm - 1.0).abs() < 1e-3
+    }
+
+    /// Explain why the probe looks wrong, if it does
+    pub fn warning(&self, model: &str) -> Option<String> {
+        let problem = if self.dimension == 0 {
+            "returned an empty vector".to_string()
+        } else if self.dimension < MIN_EMBEDDING_DIMENSION {
+            format!("returned a {}-dimensional vector", self.dimension)
+        } else if !self.norm.is_finite() || self.norm == 0.0 {
+            "returned a degenerate (zero or non-finite) vector".to_string()
+        } else {
+            return None;
+        };
+
+        Some(format!(
+            "Model '{}' {}; it may not be an embedding model. Try one of: {}",
+            model,
+            problem,
+            RECOMMENDED_EMBEDDING_MODELS.join(", ")
+        ))
+    }
+}
+
+/// Verify that Ollama returned one usable embedding per input
+///
+/// Empty vectors are not counted, since Ollama answers with an empty
+/// `embedding` array when a model silently fails to embed an input.
+fn ensure_embedding_count(model: &str, expected: usize, embeddings: &[Vec<f32>]) -> Result<()> {
+    let returned = embeddings.iter().filter(|e| !e.is_empty()).count();
+
+    if returned != expected {
+        return Err(VectDbError::EmbeddingFailed(format!(
+            "Model '{}' returned {} embedding(s) for {} input(s)",
+            model, returned, expected
+        )));
+    }
+
+    Ok(())
+}
+
+/// Keep the first `dimension` values of an embedding and re-normalize to unit length
+pub fn truncate_embedding(mut vector: Vec<f32>, dimension: usize) -> Result<Vec<f32>> {
+    if dimension == 0 || dimension > vector.len() {
+        return Err(VectDbError::InvalidInput(format!(
+            "Cannot truncate a {}-dimensional embedding to {} dimensions",
+            vector.len(),
+            dimension
+        )));
+    }
+
+    vector.truncate(dimension);
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+
+    Ok(vector)
+}
+
+/// Whether an Ollama error message reports that the input exceeds the model's length limit
+fn is_input_length_error(error_text: &str) -> bool {
+    let text = error_text.to_lowercase();
+    [
+        "context length",
+        "too long",
+        "input length",
+        "maximum context",
+        "too many tokens",
+    ]
+    .iter()
+    .any(|pattern| text.contains(pattern))
+}
+
+/// Delay before the next retry, optionally scaled by a random factor in [0.5, 1.5)
+fn retry_delay(backoff_ms: u64, jitter: bool) -> Duration {
+    if jitter {
+        Duration::from_secs_f64(backoff_ms as f64 * (0.5 + fastrand::f64()) / 1000.0)
+    } else {
+        Duration::from_millis(backoff_ms)
+    }
+}
+
 // ============================================================================
 // Request/Response Types
 // ============================================================================
@@ -270,7 +980,103 @@ struct EmbedRequest {
 
 #[derive(Debug, Deserialize)]
 struct EmbedResponse {
-    embedding: Vec<f32>,
+    embedding: EmbeddingPayload,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchEmbedResponse {
+    embeddings: Vec<EmbeddingPayload>,
+}
+
+/// An embedding as returned by Ollama: a float array, or base64-encoded bytes
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EmbeddingPayload {
+    Floats(Vec<f32>),
+    Encoded(String),
+}
+
+impl EmbeddingPayload {
+    fn into_vector(self, format: EmbeddingFormat) -> Result<Vec<f32>> {
+        match self {
+            EmbeddingPayload::Floats(vector) => Ok(vector),
+            EmbeddingPayload::Encoded(encoded) => {
+                let bytes = BASE64.decode(encoded.trim()).map_err(|e| {
+                    VectDbError::EmbeddingFailed(format!("Invalid base64 embedding: {}", e))
+                })?;
+                decode_embedding_bytes(&bytes, format)
+            }
+        }
+    }
+}
+
+/// How byte-encoded embeddings are interpreted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmbeddingFormat {
+    /// Little-endian f32 values
+    #[default]
+    Float32,
+
+    /// Packed sign bits (most significant bit first), decoded to +1.0 / -1.0
+    Binary,
+}
+
+/// Convert a byte-encoded embedding to floats
+pub fn decode_embedding_bytes(bytes: &[u8], format: EmbeddingFormat) -> Result<Vec<f32>> {
+    match format {
+        EmbeddingFormat::Float32 => {
+            if !bytes.len().is_multiple_of(4) {
+                return Err(VectDbError::EmbeddingFailed(format!(
+                    "Encoded f32 embedding has {} bytes, not a multiple of 4",
+                    bytes.len()
+                )));
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect())
+        }
+        EmbeddingFormat::Binary => Ok(bytes
+            .iter()
+            .flat_map(|byte| {
+                (0..8)
+                    .rev()
+                    .map(move |bit| if byte >> bit & 1 == 1 { 1.0 } else { -1.0 })
+            })
+            .collect()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PullRequest {
+    model: String,
+    stream: bool,
+}
+
+/// A progress event streamed while pulling a model
+#[derive(Debug, Clone, Deserialize)]
+pub struct PullProgress {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionResponse {
+    version: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -284,16 +1090,134 @@ struct ModelDetail {
     #[serde(default)]
     size: u64,
     modified_at: String,
+    #[serde(default)]
+    digest: String,
+}
+
+/// Retries spent on embedding requests
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RetryStats {
+    /// Total retry attempts
+    pub retries: u32,
+
+    /// Number of inputs that needed at least one retry
+    pub retried_inputs: usize,
+
+    /// Total time spent waiting between attempts
+    pub backoff: Duration,
+}
+
+impl RetryStats {
+    /// Add another set of retry counts to this one
+    pub fn merge(&mut self, other: &RetryStats) {
+        self.retries += other.retries;
+        self.retried_inputs += other.retried_inputs;
+        self.backoff += other.backoff;
+    }
 }
 
 /// Information about an available model
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ModelInfo {
     pub name: String,
     pub size: u64,
+    /// Raw timestamp as reported by the server (RFC 3339 for Ollama)
     pub modified_at: String,
 }
 
+impl ModelInfo {
+    /// Render `modified_at` for display, falling back to the raw string if it cannot be parsed
+    pub fn modified_display(&self, format: TimestampFormat, now_unix: i64) -> String {
+        let Ok(modified) = DateTime::parse_from_rfc3339(self.modified_at.trim()) else {
+            return self.modified_at.clone();
+        };
+        let timestamp = modified.timestamp();
+        match format {
+            TimestampFormat::Relative => format_relative(now_unix - timestamp),
+            TimestampFormat::Utc => format_utc(timestamp),
+            TimestampFormat::Raw => self.modified_at.clone(),
+        }
+    }
+}
+
+/// How model modification times are displayed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// "2 days ago"
+    #[default]
+    Relative,
+    /// "2024-01-15 18:30 UTC"
+    Utc,
+    /// The server's string, unchanged
+    Raw,
+}
+
+impl std::str::FromStr for TimestampFormat {
+    type Err = VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "relative" => Ok(TimestampFormat::Relative),
+            "utc" => Ok(TimestampFormat::Utc),
+            "raw" => Ok(TimestampFormat::Raw),
+            other => Err(VectDbError::InvalidInput(format!(
+                "Unknown timestamp format: {} (expected relative, utc or raw)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Release of an Ollama server, e.g. `0.3.12`
+///
+/// Pre-release and build suffixes (`0.5.0-rc1`) are ignored when comparing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OllamaVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl OllamaVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl std::fmt::Display for OllamaVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl std::str::FromStr for OllamaVersion {
+    type Err = VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || VectDbError::InvalidInput(format!("Invalid Ollama version: {}", s));
+
+        let core = s
+            .trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default();
+        let mut parts = core.split('.').map(|part| part.parse::<u32>());
+        let major = parts.next().ok_or_else(invalid)?.map_err(|_| invalid())?;
+        let minor = parts.next().unwrap_or(Ok(0)).map_err(|_| invalid())?;
+        let patch = parts.next().unwrap_or(Ok(0)).map_err(|_| invalid())?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(Self::new(major, minor, patch))
+    }
+}
+
 /// Information about the Ollama client configuration
 #[derive(Debug, Clone)]
 pub struct ClientInfo {
@@ -301,6 +1225,13 @@ pub struct ClientInfo {
     pub timeout_seconds: u64,
 }
 
+/// Compress a request body into a gzip member
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -314,6 +1245,33 @@ mod tests {
         assert_eq!(info.timeout_seconds, 30);
     }
 
+    #[test]
+    fn test_is_input_length_error() {
+        assert!(is_input_length_error(
+            "the input length exceeds the context length"
+        ));
+        assert!(is_input_length_error("Input is too long"));
+        assert!(!is_input_length_error("model is loading"));
+    }
+
+    #[test]
+    fn test_retry_delay_jitter() {
+        assert_eq!(retry_delay(100, false), Duration::from_millis(100));
+
+        // Two clients retrying at the same backoff step should not sleep in lockstep
+        let pairs: Vec<(Duration, Duration)> = (0..20)
+            .map(|_| (retry_delay(100, true), retry_delay(100, true)))
+            .collect();
+        assert!(pairs.iter().any(|(a, b)| a != b));
+
+        for (a, b) in pairs {
+            for delay in [a, b] {
+                assert!(delay >= Duration::from_millis(50));
+                assert!(delay < Duration::from_millis(150));
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_health_check() {
         let client = OllamaClient::new("http://localhost:11434".to_string(), 5).unwrap();
@@ -343,6 +1301,77 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_concurrent_embeddings_keep_input_order() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        /// Embeds "text N" as [N], answering later inputs sooner
+        struct Deterministic;
+
+        impl Respond for Deterministic {
+            fn respond(&self, request: &Request) -> ResponseTemplate {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let prompt = body["prompt"].as_str().unwrap();
+                if prompt == "missing" {
+                    return ResponseTemplate::new(404).set_body_string("model not found");
+                }
+                let n: u64 = prompt.trim_start_matches("text ").parse().unwrap();
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "embedding": [n as f32] }))
+                    .set_delay(Duration::from_millis((8 - n) * 25))
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(Deterministic)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5)
+            .unwrap()
+            .with_max_concurrent_requests(4);
+        let texts: Vec<String> = (0..8).map(|n| format!("text {}", n)).collect();
+        let embeddings = client.embed_batch("model", &texts).await.unwrap();
+        let expected: Vec<Vec<f32>> = (0..8).map(|n| vec![n as f32]).collect();
+        assert_eq!(embeddings, expected);
+
+        // A failure in any in-flight request fails the whole batch
+        let mut texts = texts;
+        texts[5] = "missing".to_string();
+        let err = client.embed_batch("model", &texts).await.unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_model_digest() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{
+                    "name": "nomic-embed-text:latest",
+                    "size": 1,
+                    "modified_at": "2024-01-15T10:30:00Z",
+                    "digest": "sha256:0a109f422b47"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        assert_eq!(
+            client.model_digest("nomic-embed-text").await.unwrap(),
+            Some("sha256:0a109f422b47".to_string())
+        );
+        assert_eq!(client.model_digest("other").await.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_embed_batch_empty() {
         let client = OllamaClient::new("http://localhost:11434".to_string(), 5).unwrap();
@@ -350,4 +1379,576 @@ mod tests {
         let result = client.embed_batch("test-model", &[]).await.unwrap();
         assert_eq!(result.len(), 0);
     }
+
+    #[test]
+    fn test_ensure_embedding_count() {
+        assert!(ensure_embedding_count("m", 2, &[vec![1.0], vec![2.0]]).is_ok());
+
+        let err = ensure_embedding_count("m", 3, &[vec![1.0], vec![2.0]]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("returned 2 embedding(s) for 3 input(s)")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_embed_batch_fewer_embeddings_than_inputs() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": []
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        let texts = vec!["one".to_string(), "two".to_string()];
+        let err = client.embed_batch("mock-model", &texts).await.unwrap_err();
+
+        assert!(matches!(err, VectDbError::EmbeddingFailed(_)));
+        let message = err.to_string();
+        assert!(message.contains("mock-model"));
+        assert!(message.contains("returned 0 embedding(s) for 2 input(s)"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_embedding_reports_dimension_and_norm() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "probe-model",
+                "prompt": PROBE_TEXT
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [3.0, 0.0, 4.0, 0.0]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        let probe = client.probe_embedding("probe-model").await.unwrap();
+
+        assert_eq!(probe.dimension, 4);
+        assert!((probe.norm - 5.0).abs() < 1e-6);
+        assert!(!probe.is_normalized());
+        assert!(probe.latency > Duration::ZERO);
+        assert!(EmbeddingProbe::from_vector(&[0.6, 0.8]).is_normalized());
+    }
+
+    #[test]
+    fn test_embedding_payload_formats() {
+        let floats = [0.25f32, -1.5, 3.0];
+        let bytes: Vec<u8> = floats.iter().flat_map(|f| f.to_le_bytes()).collect();
+
+        let from_array: EmbedResponse =
+            serde_json::from_value(serde_json::json!({ "embedding": floats })).unwrap();
+        let from_base64: EmbedResponse =
+            serde_json::from_value(serde_json::json!({ "embedding": BASE64.encode(&bytes) }))
+                .unwrap();
+
+        let expected = floats.to_vec();
+        assert_eq!(
+            from_array
+                .embedding
+                .into_vector(EmbeddingFormat::Float32)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            from_base64
+                .embedding
+                .into_vector(EmbeddingFormat::Float32)
+                .unwrap(),
+            expected
+        );
+
+        assert_eq!(
+            decode_embedding_bytes(&[0b1010_0001], EmbeddingFormat::Binary).unwrap(),
+            vec![1.0, -1.0, 1.0, -1.0, -1.0, -1.0, -1.0, 1.0]
+        );
+        assert!(decode_embedding_bytes(&[0, 1, 2], EmbeddingFormat::Float32).is_err());
+    }
+
+    #[test]
+    fn test_truncate_embedding_renormalizes_to_unit_length() {
+        let full: Vec<f32> = (0..768)
+            .map(|i| ((i % 7) as f32 - 3.0) / 10.0 + 0.01)
+            .collect();
+        let truncated = truncate_embedding(full.clone(), 256).unwrap();
+
+        assert_eq!(truncated.len(), 256);
+        let norm = truncated.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5, "norm {}", norm);
+
+        // Same direction as the 256-value prefix of the original
+        let prefix_norm = full[..256].iter().map(|x| x * x).sum::<f32>().sqrt();
+        for (value, original) in truncated.iter().zip(&full) {
+            assert!((value - original / prefix_norm).abs() < 1e-6);
+        }
+
+        assert!(truncate_embedding(full, 1024).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_truncate_dimension() {
+        use crate::repositories::vector_store::cosine_similarity;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [3.0, 4.0, 0.5, 0.5]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5)
+            .unwrap()
+            .with_truncate_dimension(Some(2));
+        let vector = client.embed("model", "text").await.unwrap();
+        assert_eq!(vector, vec![0.6, 0.8]);
+
+        let too_long = client.with_truncate_dimension(Some(8));
+        assert!(too_long.embed("model", "text").await.is_err());
+
+        // The prefix still ranks documents the same way as the full vectors
+        let query = truncate_embedding(vec![0.9, 0.1, 0.3, 0.2], 2).unwrap();
+        let related = truncate_embedding(vec![0.8, 0.2, 0.9, 0.1], 2).unwrap();
+        let unrelated = truncate_embedding(vec![0.1, 0.9, 0.3, 0.2], 2).unwrap();
+        assert!(cosine_similarity(&query, &related) > cosine_similarity(&query, &unrelated));
+    }
+
+    #[test]
+    fn test_modified_at_formatting() {
+        let timestamp = DateTime::parse_from_rfc3339("2024-01-15T18:30:00Z")
+            .unwrap()
+            .timestamp();
+        assert_eq!(format_utc(timestamp), "2024-01-15 18:30 UTC");
+
+        let model = ModelInfo {
+            name: "nomic-embed-text".to_string(),
+            size: 0,
+            modified_at: "2024-01-15T10:30:00.123456-08:00".to_string(),
+        };
+        let two_days_later = timestamp + 2 * 86_400 + 600;
+        assert_eq!(
+            model.modified_display(TimestampFormat::Relative, two_days_later),
+            "2 days ago"
+        );
+        assert_eq!(
+            model.modified_display(TimestampFormat::Utc, two_days_later),
+            "2024-01-15 18:30 UTC"
+        );
+
+        let unparseable = ModelInfo {
+            modified_at: "last tuesday".to_string(),
+            ..model
+        };
+        assert_eq!(
+            unparseable.modified_display(TimestampFormat::Relative, two_days_later),
+            "last tuesday"
+        );
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        use std::io::Read;
+
+        let text = "the quick brown fox jumps over the lazy dog. ".repeat(200);
+        let compressed = gzip(text.as_bytes()).unwrap();
+        assert!(compressed.len() < text.len() / 4);
+
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, text.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_compressed_request_body() {
+        use std::io::Read;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+        struct GzipJsonBody(serde_json::Value);
+
+        impl wiremock::Match for GzipJsonBody {
+            fn matches(&self, request: &Request) -> bool {
+                let mut body = Vec::new();
+                flate2::read::GzDecoder::new(request.body.as_slice())
+                    .read_to_end(&mut body)
+                    .ok()
+                    .and_then(|_| serde_json::from_slice::<serde_json::Value>(&body).ok())
+                    .is_some_and(|body| body == self.0)
+            }
+        }
+
+        let long_text = "lorem ipsum dolor sit amet ".repeat(100);
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(header("content-encoding", "gzip"))
+            .and(GzipJsonBody(serde_json::json!({
+                "model": "model",
+                "prompt": long_text,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(wiremock::matchers::body_partial_json(
+                serde_json::json!({ "prompt": "short" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.0, 1.0]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5)
+            .unwrap()
+            .with_compressed_requests(true);
+        assert_eq!(
+            client.embed("model", &long_text).await.unwrap(),
+            vec![1.0, 0.0]
+        );
+        // Below the threshold the body goes out as plain JSON
+        assert_eq!(
+            client.embed("model", "short").await.unwrap(),
+            vec![0.0, 1.0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_embed_many_single_request() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .and(body_partial_json(
+                serde_json::json!({ "input": ["a", "b"] }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embeddings": [[1.0, 0.0], [0.0, 1.0]]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5)
+            .unwrap()
+            .with_batch_endpoint(true);
+        let texts = vec!["a".to_string(), "b".to_string()];
+        let embeddings = client.embed_batch("model", &texts).await.unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_many_falls_back_without_batch_endpoint() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("404 page not found"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        let texts = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(client.embed_many("model", &texts).await.unwrap().len(), 2);
+        // The missing endpoint is remembered, so the second batch skips it
+        assert_eq!(client.embed_many("model", &texts).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_sets_retry_count() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("overloaded"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Two retries are enough to reach the third, successful attempt
+        let policy = RetryConfig {
+            max_retries: 2,
+            initial_backoff_ms: 2,
+            max_backoff_ms: 3,
+        };
+        let client = OllamaClient::new(server.uri(), 5)
+            .unwrap()
+            .with_retry_policy(policy);
+        let (embeddings, stats) = client
+            .embed_batch_with_stats("model", &["text".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0]]);
+        assert_eq!(stats.retries, 2);
+        // 2ms then 3ms: the doubled delay is capped at max_backoff_ms
+        assert_eq!(stats.backoff, Duration::from_millis(5));
+
+        // One retry is not
+        server.reset().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("overloaded"))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let client = client.with_retry_policy(RetryConfig {
+            max_retries: 1,
+            ..policy
+        });
+        let err = client.embed("model", "text").await.unwrap_err();
+        assert!(err.to_string().contains("after 1 retries"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_version_parsed_cached_and_checked() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "version": "0.2.1"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        assert_eq!(
+            client.version().await.unwrap(),
+            Some(OllamaVersion::new(0, 2, 1))
+        );
+
+        // Recent enough, and nothing required: no warning (and no second request)
+        let minimum = "0.1.40".parse().ok();
+        assert_eq!(client.check_version(minimum, true).await.unwrap(), None);
+        assert_eq!(client.check_version(None, false).await.unwrap(), None);
+
+        // The batch endpoint needs a newer server
+        let batching = client.clone().with_batch_endpoint(true);
+        let warning = batching.check_version(None, false).await.unwrap().unwrap();
+        assert!(warning.contains("0.2.1"), "{}", warning);
+        assert!(warning.contains("0.3.0"), "{}", warning);
+        assert!(batching.check_version(None, true).await.is_err());
+
+        let minimum = OllamaVersion::new(0, 4, 0);
+        let warning = client.check_version(Some(minimum), false).await.unwrap();
+        assert!(warning.unwrap().contains("0.4.0"));
+    }
+
+    #[tokio::test]
+    async fn test_version_missing_endpoint() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/version"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("404 page not found"))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        assert_eq!(client.version().await.unwrap(), None);
+
+        // An unknown version only warns, even in strict mode
+        let minimum = Some(OllamaVersion::new(0, 3, 0));
+        let warning = client.check_version(minimum, true).await.unwrap().unwrap();
+        assert!(warning.contains("Could not determine"), "{}", warning);
+    }
+
+    #[test]
+    fn test_parse_ollama_version() {
+        let parse = |s: &str| s.parse::<OllamaVersion>().ok();
+
+        assert_eq!(parse("0.3.12"), Some(OllamaVersion::new(0, 3, 12)));
+        assert_eq!(parse("v0.5.0-rc1"), Some(OllamaVersion::new(0, 5, 0)));
+        assert_eq!(parse("1.2"), Some(OllamaVersion::new(1, 2, 0)));
+        assert_eq!(parse("0.0.0+dev"), Some(OllamaVersion::new(0, 0, 0)));
+        assert_eq!(parse("unknown"), None);
+        assert_eq!(parse("1.2.3.4"), None);
+        assert!(OllamaVersion::new(0, 10, 0) > OllamaVersion::new(0, 9, 9));
+    }
+
+    #[tokio::test]
+    async fn test_pull_model_consumes_progress() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let body = [
+            r#"{"status":"pulling manifest"}"#,
+            r#"{"status":"pulling abc","digest":"sha256:abc","total":100,"completed":40}"#,
+            r#"{"status":"pulling abc","digest":"sha256:abc","total":100,"completed":100}"#,
+            r#"{"status":"success"}"#,
+        ]
+        .join("\n");
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/pull"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/x-ndjson"))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut events = Vec::new();
+        client
+            .pull_model_with_progress("nomic-embed-text", |p| events.push(p.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[2].completed, Some(100));
+        assert_eq!(events[3].status, "success");
+
+        client.pull_model("nomic-embed-text").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pull_model_reports_stream_error() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/pull"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "{\"status\":\"pulling manifest\"}\n{\"error\":\"file does not exist\"}\n",
+                "application/x-ndjson",
+            ))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        let err = client.pull_model("missing-model").await.unwrap_err();
+        assert!(err.to_string().contains("file does not exist"));
+    }
+
+    #[tokio::test]
+    async fn test_check_embedding_model_degenerate_vectors() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        for embedding in [serde_json::json!([0.5]), serde_json::json!([])] {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/api/embeddings"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "embedding": embedding })),
+                )
+                .mount(&server)
+                .await;
+
+            let client = OllamaClient::new(server.uri(), 5).unwrap();
+            let warning = client.check_embedding_model("llama3").await.unwrap();
+
+            assert!(warning.contains("llama3"));
+            assert!(warning.contains("may not be an embedding model"));
+            assert!(warning.contains("nomic-embed-text"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_health_check_catches_broken_embedding_endpoint() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{ "name": "nomic-embed-text", "size": 1, "modified_at": "" }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("model failed to load"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri(), 5).unwrap();
+        assert!(client.health_check().await.unwrap());
+        assert!(!client.embed_health_check("nomic-embed-text").await.unwrap());
+
+        // An empty vector is no better than an error
+        server.reset().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "embedding": [] })),
+            )
+            .mount(&server)
+            .await;
+        assert!(!client.embed_health_check("nomic-embed-text").await.unwrap());
+
+        server.reset().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "embedding": [0.6, 0.8] })),
+            )
+            .mount(&server)
+            .await;
+        assert!(client.embed_health_check("nomic-embed-text").await.unwrap());
+    }
+
+    #[test]
+    fn test_embedding_probe_plausible_vector() {
+        let probe = EmbeddingProbe::from_vector(&vec![0.1; 768]);
+        assert_eq!(probe.dimension, 768);
+        assert!(probe.warning("nomic-embed-text").is_none());
+    }
 }
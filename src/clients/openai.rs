@@ -0,0 +1,326 @@
+//! OpenAI-compatible API client for embedding generation
+//!
+//! Talks to the `/v1/embeddings` endpoint offered by OpenAI, Azure OpenAI
+//! gateways and other compatible servers, authenticating with a bearer token.
+
+use crate::clients::EmbeddingProvider;
+use crate::clients::ollama::ModelInfo;
+use crate::config::OpenAiConfig;
+use crate::error::{Result, VectDbError};
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Most inputs OpenAI accepts in a single embeddings request
+const MAX_INPUTS_PER_REQUEST: usize = 2048;
+
+/// OpenAI-compatible API client
+#[derive(Clone)]
+pub struct OpenAiClient {
+    base_url: String,
+    api_key: Option<String>,
+    client: Client,
+}
+
+impl OpenAiClient {
+    /// Create a new OpenAI-compatible client
+    pub fn new(base_url: String, api_key: Option<String>, timeout_seconds: u64) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(timeout_seconds))
+            .build()
+            .map_err(VectDbError::Http)?;
+
+        info!("Created OpenAI client with base URL: {}", base_url);
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            client,
+        })
+    }
+
+    /// Create a client from the `[openai]` configuration section
+    pub fn from_config(config: &OpenAiConfig) -> Result<Self> {
+        Self::new(
+            config.base_url.clone(),
+            config.api_key.clone(),
+            config.timeout_seconds,
+        )
+    }
+
+    /// Attach the bearer token, if one is configured
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// Check if the API is reachable and the credentials are accepted
+    pub async fn health_check(&self) -> Result<bool> {
+        debug!("Performing health check on OpenAI API");
+
+        let url = format!("{}/v1/models", self.base_url);
+
+        match self.authorize(self.client.get(&url)).send().await {
+            Ok(response) => {
+                let is_ok = response.status().is_success();
+                if is_ok {
+                    info!("OpenAI health check passed");
+                } else {
+                    warn!(
+                        "OpenAI health check failed with status: {}",
+                        response.status()
+                    );
+                }
+                Ok(is_ok)
+            }
+            Err(e) => {
+                warn!("OpenAI health check failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Generate embedding for a single text
+    pub async fn embed(&self, model: &str, text: &str) -> Result<Vec<f32>> {
+        let embeddings = self.embed_batch(model, &[text.to_string()]).await?;
+        embeddings
+            .into_iter()
+            .next()
+            .ok_or_else(|| VectDbError::EmbeddingFailed("No embedding returned".to_string()))
+    }
+
+    /// Generate embeddings for a batch of texts
+    ///
+    /// Texts are sent as a single array input per request, split only when the
+    /// batch exceeds the API's per-request limit.
+    pub async fn embed_batch(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        debug!(
+            "Generating embeddings for {} texts using model {}",
+            texts.len(),
+            model
+        );
+
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(MAX_INPUTS_PER_REQUEST) {
+            embeddings.extend(self.embed_request(model, batch).await?);
+        }
+
+        info!("Successfully generated {} embeddings", embeddings.len());
+
+        Ok(embeddings)
+    }
+
+    /// Send one embeddings request and return the vectors in input order
+    async fn embed_request(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/v1/embeddings", self.base_url);
+        let request = EmbeddingsRequest {
+            model,
+            input: texts,
+        };
+
+        let response = self
+            .authorize(self.client.post(&url).json(&request))
+            .send()
+            .await
+            .map_err(|e| {
+                VectDbError::EmbeddingFailed(format!("Failed to connect to OpenAI API: {}", e))
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(VectDbError::EmbeddingFailed(format!(
+                "OpenAI API returned error {}: {}",
+                status, body
+            )));
+        }
+
+        let mut parsed: EmbeddingsResponse = response.json().await.map_err(|e| {
+            VectDbError::EmbeddingFailed(format!("Failed to parse response: {}", e))
+        })?;
+
+        if parsed.data.len() != texts.len() {
+            return Err(VectDbError::EmbeddingFailed(format!(
+                "Model {} returned {} embeddings for {} inputs",
+                model,
+                parsed.data.len(),
+                texts.len()
+            )));
+        }
+
+        parsed.data.sort_by_key(|d| d.index);
+        Ok(parsed.data.into_iter().map(|d| d.embedding).collect())
+    }
+
+    /// List available models
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        debug!("Listing available models from OpenAI API");
+
+        let url = format!("{}/v1/models", self.base_url);
+
+        let response = self
+            .authorize(self.client.get(&url))
+            .send()
+            .await
+            .map_err(|e| {
+                VectDbError::OllamaUnavailable(format!("Failed to connect to OpenAI API: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            return Err(VectDbError::OllamaUnavailable(format!(
+                "OpenAI API returned error: {}",
+                response.status()
+            )));
+        }
+
+        let models_response: ModelsResponse = response.json().await.map_err(|e| {
+            VectDbError::OllamaUnavailable(format!("Failed to parse response: {}", e))
+        })?;
+
+        let models: Vec<ModelInfo> = models_response
+            .data
+            .into_iter()
+            .map(|m| ModelInfo {
+                name: m.id,
+                size: 0,
+                modified_at: m.created.map(|c| c.to_string()).unwrap_or_default(),
+            })
+            .collect();
+
+        info!("Found {} models", models.len());
+
+        Ok(models)
+    }
+}
+
+impl EmbeddingProvider for OpenAiClient {
+    fn embed(&self, model: &str, text: &str) -> impl Future<Output = Result<Vec<f32>>> + Send {
+        OpenAiClient::embed(self, model, text)
+    }
+
+    fn embed_batch(
+        &self,
+        model: &str,
+        texts: &[String],
+    ) -> impl Future<Output = Result<Vec<Vec<f32>>>> + Send {
+        OpenAiClient::embed_batch(self, model, texts)
+    }
+
+    fn health_check(&self) -> impl Future<Output = Result<bool>> + Send {
+        OpenAiClient::health_check(self)
+    }
+
+    fn list_models(&self) -> impl Future<Output = Result<Vec<ModelInfo>>> + Send {
+        OpenAiClient::list_models(self)
+    }
+}
+
+#[derive(Serialize)]
+struct EmbeddingsRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct ModelsResponse {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    id: String,
+    #[serde(default)]
+    created: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_embed_batch_sends_array_input_with_bearer_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .and(header("authorization", "Bearer sk-test"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "text-embedding-3-small",
+                "input": ["first", "second"]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [
+                    {"index": 1, "embedding": [0.0, 1.0]},
+                    {"index": 0, "embedding": [1.0, 0.0]}
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenAiClient::new(server.uri(), Some("sk-test".to_string()), 5).unwrap();
+        let texts = vec!["first".to_string(), "second".to_string()];
+        let embeddings = client
+            .embed_batch("text-embedding-3-small", &texts)
+            .await
+            .unwrap();
+
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    }
+
+    #[tokio::test]
+    async fn test_list_models_and_health_check() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"id": "text-embedding-3-small", "created": 1705948997}]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OpenAiClient::new(server.uri(), None, 5).unwrap();
+        assert!(client.health_check().await.unwrap());
+
+        let models = client.list_models().await.unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].name, "text-embedding-3-small");
+    }
+
+    #[tokio::test]
+    async fn test_embed_reports_api_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid api key"))
+            .mount(&server)
+            .await;
+
+        let client = OpenAiClient::new(server.uri(), Some("bad".to_string()), 5).unwrap();
+        let err = client
+            .embed("text-embedding-3-small", "hi")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid api key"));
+    }
+}
@@ -1,6 +1,7 @@
 //! Configuration management for VectDB
 
-use crate::domain::ChunkStrategy;
+use crate::clients::ollama::OllamaVersion;
+use crate::domain::{ChunkStrategy, NonFiniteEmbeddings, OverlapUnit};
 use crate::error::{Result, VectDbError};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
@@ -9,20 +10,65 @@ use std::path::PathBuf;
 /// Main application configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
+    /// Service that generates embeddings: ollama or openai
+    #[serde(default)]
+    pub provider: Provider,
     #[serde(default)]
     pub database: DatabaseConfig,
     #[serde(default)]
     pub ollama: OllamaConfig,
     #[serde(default)]
+    pub openai: OpenAiConfig,
+    #[serde(default)]
     pub chunking: ChunkingConfig,
     #[serde(default)]
     pub search: SearchConfig,
+    #[serde(default)]
+    pub ingestion: IngestionConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+}
+
+/// Service that generates embeddings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    /// An Ollama server, configured in `[ollama]`
+    #[default]
+    Ollama,
+    /// An OpenAI-compatible API, configured in `[openai]`
+    OpenAi,
+}
+
+impl std::fmt::Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Provider::Ollama => write!(f, "Ollama"),
+            Provider::OpenAi => write!(f, "the OpenAI API"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
     /// Path to the SQLite database file
     pub path: PathBuf,
+
+    /// Store embeddings scaled to unit length, so search scores them with a dot product
+    #[serde(default)]
+    pub normalize_embeddings: bool,
+
+    /// Embeddings containing NaN or infinite values: reject, or zero the bad values
+    #[serde(default)]
+    pub non_finite_embeddings: NonFiniteEmbeddings,
+
+    /// Read-only copy of the database that the server searches (unset = the primary)
+    #[serde(default)]
+    pub read_path: Option<PathBuf>,
+
+    /// Find search candidates with the sqlite-vec index instead of scanning in Rust
+    #[serde(default = "default_true")]
+    pub use_ann: bool,
 }
 
 impl Default for DatabaseConfig {
@@ -31,7 +77,13 @@ impl Default for DatabaseConfig {
             .map(|d| d.join("vectors.db"))
             .unwrap_or_else(|| PathBuf::from("vectors.db"));
 
-        Self { path }
+        Self {
+            path,
+            normalize_embeddings: false,
+            non_finite_embeddings: NonFiniteEmbeddings::default(),
+            read_path: None,
+            use_ann: true,
+        }
     }
 }
 
@@ -45,6 +97,55 @@ pub struct OllamaConfig {
 
     /// Request timeout in seconds
     pub timeout_seconds: u64,
+
+    /// Warn if the model does not look like an embedding model (probed before ingest,
+    /// checked against the query vector on search)
+    #[serde(default = "default_true")]
+    pub check_embedding_model: bool,
+
+    /// Warn when a model's weights changed since its embeddings were stored
+    #[serde(default = "default_true")]
+    pub check_model_version: bool,
+
+    /// Randomize embedding retry backoff so concurrent retries do not fire in lockstep
+    #[serde(default = "default_true")]
+    pub retry_jitter: bool,
+
+    /// Keep only the first N dimensions of each embedding (Matryoshka models), re-normalized
+    #[serde(default)]
+    pub truncate_dimension: Option<usize>,
+
+    /// Decode byte-encoded (base64) embeddings as packed sign bits instead of f32 values
+    #[serde(default)]
+    pub binary_embeddings: bool,
+
+    /// Embed batches with one `/api/embed` request (falls back if the server lacks it)
+    #[serde(default)]
+    pub use_batch_endpoint: bool,
+
+    /// Gzip large embedding request bodies (for remote Ollama behind a proxy that accepts it)
+    #[serde(default)]
+    pub compress_requests: bool,
+
+    /// Per-text embedding requests kept in flight at once
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+
+    /// How `vectdb models` shows modification times: relative, utc or raw
+    #[serde(default = "default_timestamp_format")]
+    pub timestamp_format: String,
+
+    /// Oldest Ollama release to accept without a warning (e.g. "0.3.0")
+    #[serde(default)]
+    pub min_version: Option<String>,
+
+    /// Refuse to start instead of warning when Ollama is older than required
+    #[serde(default)]
+    pub strict_version_check: bool,
+
+    /// Retry policy for failed embedding requests
+    #[serde(default)]
+    pub retry: RetryConfig,
 }
 
 impl Default for OllamaConfig {
@@ -53,21 +154,102 @@ impl Default for OllamaConfig {
             base_url: "http://localhost:11434".to_string(),
             default_model: "nomic-embed-text".to_string(),
             timeout_seconds: 30,
+            check_embedding_model: true,
+            check_model_version: true,
+            retry_jitter: true,
+            truncate_dimension: None,
+            binary_embeddings: false,
+            use_batch_endpoint: false,
+            compress_requests: false,
+            max_concurrent_requests: default_max_concurrent_requests(),
+            timestamp_format: default_timestamp_format(),
+            min_version: None,
+            strict_version_check: false,
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+impl OllamaConfig {
+    /// The configured minimum Ollama version, if set and valid
+    pub fn min_version(&self) -> Option<OllamaVersion> {
+        self.min_version.as_deref()?.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Retries after the first failed attempt (0 disables retrying)
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled on each further retry
+    pub initial_backoff_ms: u64,
+
+    /// Upper bound on the delay between retries
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAiConfig {
+    /// Base URL of an OpenAI-compatible API (without the `/v1` suffix)
+    pub base_url: String,
+
+    /// API key sent as a bearer token
+    pub api_key: Option<String>,
+
+    /// Default embedding model
+    pub default_model: String,
+
+    /// Request timeout in seconds
+    pub timeout_seconds: u64,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.openai.com".to_string(),
+            api_key: None,
+            default_model: "text-embedding-3-small".to_string(),
+            timeout_seconds: 30,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkingConfig {
-    /// Maximum chunk size in characters
+    /// Maximum chunk size in characters (tokens for the token strategy)
     pub max_chunk_size: usize,
 
     /// Overlap size between chunks
     pub overlap_size: usize,
 
-    /// Chunking strategy
+    /// Chunking strategy (fixed, semantic, token or markdown)
     #[serde(default)]
     pub strategy: String,
+
+    /// Unit the fixed strategy's overlap is counted in (chars, words or sentences)
+    #[serde(default)]
+    pub overlap_unit: OverlapUnit,
+
+    /// Convert CRLF/CR line endings to LF before hashing and chunking
+    #[serde(default = "default_true")]
+    pub normalize_newlines: bool,
+
+    /// Normalize text to Unicode NFC before hashing and chunking
+    #[serde(default)]
+    pub normalize_unicode: bool,
 }
 
 impl Default for ChunkingConfig {
@@ -76,6 +258,9 @@ impl Default for ChunkingConfig {
             max_chunk_size: 512,
             overlap_size: 50,
             strategy: "fixed".to_string(),
+            overlap_unit: OverlapUnit::Chars,
+            normalize_newlines: true,
+            normalize_unicode: false,
         }
     }
 }
@@ -87,9 +272,17 @@ impl ChunkingConfig {
             "semantic" => ChunkStrategy::Semantic {
                 max_size: self.max_chunk_size,
             },
+            "token" => ChunkStrategy::TokenBased {
+                max_tokens: self.max_chunk_size,
+                overlap_tokens: self.overlap_size,
+            },
+            "markdown" => ChunkStrategy::Markdown {
+                max_size: self.max_chunk_size,
+            },
             _ => ChunkStrategy::FixedSize {
                 size: self.max_chunk_size,
                 overlap: self.overlap_size,
+                overlap_unit: self.overlap_unit,
             },
         }
     }
@@ -102,6 +295,14 @@ pub struct SearchConfig {
 
     /// Minimum similarity threshold
     pub similarity_threshold: f32,
+
+    /// Accumulate similarity scores in f64 (slower, more accurate near ties)
+    #[serde(default)]
+    pub high_precision_scoring: bool,
+
+    /// Fail searches that take longer than this many milliseconds, interrupting the scan
+    #[serde(default)]
+    pub deadline_ms: Option<u64>,
 }
 
 impl Default for SearchConfig {
@@ -109,10 +310,146 @@ impl Default for SearchConfig {
         Self {
             default_top_k: 10,
             similarity_threshold: 0.0,
+            high_precision_scoring: false,
+            deadline_ms: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestionConfig {
+    /// Reuse cached embeddings for chunk text that was embedded before
+    #[serde(default = "default_true")]
+    pub embedding_cache: bool,
+
+    /// Encoding used to decode files that are not valid UTF-8 (e.g. "windows-1252")
+    #[serde(default)]
+    pub fallback_encoding: Option<String>,
+
+    /// Split chunks the model rejects as too long into smaller pieces and retry
+    #[serde(default = "default_true")]
+    pub split_oversized_chunks: bool,
+
+    /// Rough cap on memory held by chunks and their embeddings before they are stored, in megabytes
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: usize,
+
+    /// Flag documents whose mean embedding is at least this similar to an existing one
+    #[serde(default)]
+    pub near_dup_threshold: Option<f32>,
+
+    /// Skip flagged near-duplicates (false = ingest them with a warning)
+    #[serde(default = "default_true")]
+    pub skip_near_duplicates: bool,
+
+    /// Collect source code files when ingesting directories
+    #[serde(default)]
+    pub include_code: bool,
+
+    /// Commit chunks and embeddings every this many rows within a file
+    #[serde(default = "default_commit_batch_size")]
+    pub commit_batch_size: usize,
+}
+
+impl Default for IngestionConfig {
+    fn default() -> Self {
+        Self {
+            embedding_cache: true,
+            fallback_encoding: None,
+            split_oversized_chunks: true,
+            max_memory_mb: default_max_memory_mb(),
+            near_dup_threshold: None,
+            skip_near_duplicates: true,
+            include_code: false,
+            commit_batch_size: default_commit_batch_size(),
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Bearer token required by the ingest, export, history and admin endpoints
+    /// (unset = open, unless `api_key` is set)
+    #[serde(default)]
+    pub api_token: Option<String>,
+
+    /// Bearer token required by every `/api/*` endpoint except the health probes (unset = open)
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Enable `POST /api/ingest` (off by default)
+    #[serde(default)]
+    pub allow_ingest: bool,
+
+    /// Directory that `POST /api/ingest` may read `path` requests from (unset = no path ingest)
+    #[serde(default)]
+    pub ingest_root: Option<PathBuf>,
+
+    /// Record API searches for `GET /api/history` (off by default)
+    #[serde(default)]
+    pub record_history: bool,
+
+    /// Most searches kept in the history; older ones are pruned
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+
+    /// Header carrying the request id, read from requests and echoed in responses
+    #[serde(default = "default_request_id_header")]
+    pub request_id_header: String,
+
+    /// Reuse `/api/stats` responses until another connection writes to the database
+    #[serde(default)]
+    pub cache_stats: bool,
+
+    /// Origins allowed to call the API from a browser ("*" = any; empty = same-origin only)
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            api_token: None,
+            api_key: None,
+            allow_ingest: false,
+            ingest_root: None,
+            record_history: false,
+            history_limit: default_history_limit(),
+            request_id_header: default_request_id_header(),
+            cache_stats: false,
+            cors_origins: Vec::new(),
+        }
+    }
+}
+
+fn default_history_limit() -> usize {
+    1000
+}
+
+fn default_request_id_header() -> String {
+    "x-request-id".to_string()
+}
+
+fn default_max_memory_mb() -> usize {
+    256
+}
+
+fn default_commit_batch_size() -> usize {
+    500
+}
+
+fn default_max_concurrent_requests() -> usize {
+    4
+}
+
+fn default_timestamp_format() -> String {
+    "relative".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
 impl Config {
     /// Load configuration from a file
     pub fn from_file(path: &PathBuf) -> Result<Self> {
@@ -129,21 +466,191 @@ impl Config {
     /// 1. Provided config file path
     /// 2. Default config location (~/.config/vectdb/config.toml)
     /// 3. Built-in defaults
+    ///
+    /// The loaded settings are validated before they are returned.
     pub fn load(config_path: Option<PathBuf>) -> Result<Self> {
-        // If explicit path provided, try to load it
-        if let Some(path) = config_path {
-            return Self::from_file(&path);
+        let config = match config_path {
+            // If explicit path provided, try to load it
+            Some(path) => Self::from_file(&path)?,
+            // Try default location, then fall back to defaults
+            None => match get_default_config_path() {
+                Some(default_path) if default_path.exists() => Self::from_file(&default_path)?,
+                _ => Config::default(),
+            },
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Embedding model used when a command doesn't name one
+    pub fn default_model(&self) -> &str {
+        match self.provider {
+            Provider::Ollama => &self.ollama.default_model,
+            Provider::OpenAi => &self.openai.default_model,
+        }
+    }
+
+    /// Base URL of the configured embedding provider
+    pub fn provider_url(&self) -> &str {
+        match self.provider {
+            Provider::Ollama => &self.ollama.base_url,
+            Provider::OpenAi => &self.openai.base_url,
         }
+    }
+
+    /// Check that the settings can work together
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(VectDbError::Config(message.to_string()));
 
-        // Try default location
-        if let Some(default_path) = get_default_config_path()
-            && default_path.exists()
+        if !self.ollama.base_url.starts_with("http://")
+            && !self.ollama.base_url.starts_with("https://")
         {
-            return Self::from_file(&default_path);
+            return invalid("ollama.base_url must start with http:// or https://");
+        }
+        if self.ollama.default_model.trim().is_empty() {
+            return invalid("ollama.default_model must not be empty");
+        }
+        if self.provider == Provider::OpenAi {
+            if !self.openai.base_url.starts_with("http://")
+                && !self.openai.base_url.starts_with("https://")
+            {
+                return invalid("openai.base_url must start with http:// or https://");
+            }
+            if self.openai.default_model.trim().is_empty() {
+                return invalid("openai.default_model must not be empty");
+            }
+        }
+        if self.ollama.timeout_seconds == 0 {
+            return invalid("ollama.timeout_seconds must be greater than 0");
+        }
+        if self.ollama.max_concurrent_requests == 0 {
+            return invalid("ollama.max_concurrent_requests must be greater than 0");
+        }
+        if let Some(version) = &self.ollama.min_version
+            && version.parse::<OllamaVersion>().is_err()
+        {
+            return invalid("ollama.min_version must be a version such as '0.3.0'");
+        }
+        if self.ollama.retry.initial_backoff_ms > self.ollama.retry.max_backoff_ms {
+            return invalid("ollama.retry.initial_backoff_ms must not exceed max_backoff_ms");
+        }
+        if self.ollama.truncate_dimension == Some(0) {
+            return invalid("ollama.truncate_dimension must be greater than 0");
+        }
+        if !matches!(
+            self.ollama.timestamp_format.as_str(),
+            "relative" | "utc" | "raw"
+        ) {
+            return invalid("ollama.timestamp_format must be 'relative', 'utc' or 'raw'");
         }
+        if self.chunking.max_chunk_size == 0 {
+            return invalid("chunking.max_chunk_size must be greater than 0");
+        }
+        if self.chunking.overlap_size >= self.chunking.max_chunk_size {
+            return invalid("chunking.overlap_size must be smaller than chunking.max_chunk_size");
+        }
+        if !matches!(
+            self.chunking.strategy.as_str(),
+            "" | "fixed" | "semantic" | "token" | "markdown"
+        ) {
+            return invalid("chunking.strategy must be 'fixed', 'semantic', 'token' or 'markdown'");
+        }
+        if self.search.default_top_k == 0 {
+            return invalid("search.default_top_k must be greater than 0");
+        }
+        if !(0.0..=1.0).contains(&self.search.similarity_threshold) {
+            return invalid("search.similarity_threshold must be between 0.0 and 1.0");
+        }
+        if self.ingestion.max_memory_mb == 0 {
+            return invalid("ingestion.max_memory_mb must be greater than 0");
+        }
+
+        if self.ingestion.commit_batch_size == 0 {
+            return invalid("ingestion.commit_batch_size must be greater than 0");
+        }
+        if let Some(threshold) = self.ingestion.near_dup_threshold
+            && !(0.0..=1.0).contains(&threshold)
+        {
+            return invalid("ingestion.near_dup_threshold must be between 0.0 and 1.0");
+        }
+        if let Some(label) = &self.ingestion.fallback_encoding
+            && encoding_rs::Encoding::for_label(label.trim().as_bytes()).is_none()
+        {
+            return invalid("ingestion.fallback_encoding is not a known encoding label");
+        }
+        let header = &self.server.request_id_header;
+        if header.is_empty()
+            || !header
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return invalid("server.request_id_header must be a valid HTTP header name");
+        }
+        for origin in &self.server.cors_origins {
+            let is_url = (origin.starts_with("http://") || origin.starts_with("https://"))
+                && !origin.ends_with('/');
+            if origin != "*" && !is_url {
+                return invalid(
+                    "server.cors_origins entries must be \"*\" or an origin like https://example.com (no trailing slash)",
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read a setting by dotted key (e.g. "ollama.timeout_seconds")
+    ///
+    /// Unset optional settings are reported as unknown.
+    pub fn get_value(&self, key: &str) -> Result<toml::Value> {
+        let root = self.to_toml()?;
+        let mut value = &root;
+        for part in key.split('.') {
+            value = value.get(part).ok_or_else(|| {
+                VectDbError::Config(format!("Unknown or unset config key: {}", key))
+            })?;
+        }
+
+        Ok(value.clone())
+    }
+
+    /// Change a setting by dotted key, keeping the current settings if the result is invalid
+    ///
+    /// The value is parsed as the type of the current setting; for unset
+    /// optional settings it is parsed as a TOML literal, falling back to a string.
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<()> {
+        let unknown = || VectDbError::Config(format!("Unknown config key: {}", key));
+
+        // Top-level settings such as `provider` have no section
+        let (section_path, field) = key.rsplit_once('.').unwrap_or(("", key));
+        let mut root = self.to_toml()?;
+        let mut section = &mut root;
+        for part in section_path.split('.').filter(|part| !part.is_empty()) {
+            section = section.get_mut(part).ok_or_else(unknown)?;
+        }
+        let table = section.as_table_mut().ok_or_else(unknown)?;
 
-        // Fall back to defaults
-        Ok(Config::default())
+        let parsed = parse_setting(key, value, table.get(field))?;
+        table.insert(field.to_string(), parsed);
+
+        let updated: Config = root
+            .try_into()
+            .map_err(|e| VectDbError::Config(format!("Invalid value for {}: {}", key, e)))?;
+
+        // Serde ignores fields it does not know, so check the key survived
+        if updated.get_value(key).is_err() {
+            return Err(unknown());
+        }
+
+        updated.validate()?;
+        *self = updated;
+        Ok(())
+    }
+
+    fn to_toml(&self) -> Result<toml::Value> {
+        toml::Value::try_from(self)
+            .map_err(|e| VectDbError::Config(format!("Failed to serialize config: {}", e)))
     }
 
     /// Save configuration to a file
@@ -165,6 +672,38 @@ impl Config {
     }
 }
 
+/// Parse a CLI value as the TOML type of the setting it replaces
+fn parse_setting(key: &str, raw: &str, current: Option<&toml::Value>) -> Result<toml::Value> {
+    let mismatch = |expected: &str| {
+        VectDbError::Config(format!(
+            "Invalid value for {}: expected {}, got '{}'",
+            key, expected, raw
+        ))
+    };
+
+    match current {
+        Some(toml::Value::String(_)) => Ok(toml::Value::String(raw.to_string())),
+        Some(toml::Value::Integer(_)) => raw
+            .parse()
+            .map(toml::Value::Integer)
+            .map_err(|_| mismatch("an integer")),
+        Some(toml::Value::Float(_)) => raw
+            .parse()
+            .map(toml::Value::Float)
+            .map_err(|_| mismatch("a number")),
+        Some(toml::Value::Boolean(_)) => raw
+            .parse()
+            .map(toml::Value::Boolean)
+            .map_err(|_| mismatch("true or false")),
+        Some(_) => Err(mismatch("a section name, not a value")),
+        None => Ok(format!("value = {}", raw)
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(raw.to_string()))),
+    }
+}
+
 /// Get the default configuration directory path
 pub fn get_default_config_path() -> Option<PathBuf> {
     ProjectDirs::from("com", "vectdb", "vectdb").map(|dirs| dirs.config_dir().join("config.toml"))
@@ -186,13 +725,98 @@ mod tests {
         assert_eq!(config.search.default_top_k, 10);
     }
 
+    #[test]
+    fn test_get_value() {
+        let config = Config::default();
+        assert_eq!(
+            config.get_value("ollama.timeout_seconds").unwrap(),
+            toml::Value::Integer(30)
+        );
+        assert_eq!(
+            config.get_value("ollama.default_model").unwrap().as_str(),
+            Some("nomic-embed-text")
+        );
+        assert!(config.get_value("ollama.no_such_key").is_err());
+    }
+
+    #[test]
+    fn test_set_value_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let mut config = Config::default();
+        config.set_value("ollama.timeout_seconds", "90").unwrap();
+        config
+            .set_value("search.similarity_threshold", "0.25")
+            .unwrap();
+        config
+            .set_value("ingestion.near_dup_threshold", "0.98")
+            .unwrap();
+        config.save(&path).unwrap();
+
+        let loaded = Config::from_file(&path).unwrap();
+        assert_eq!(loaded.ollama.timeout_seconds, 90);
+        assert_eq!(loaded.search.similarity_threshold, 0.25);
+        assert_eq!(loaded.ingestion.near_dup_threshold, Some(0.98));
+    }
+
+    #[test]
+    fn test_set_value_rejects_invalid() {
+        let mut config = Config::default();
+
+        assert!(config.set_value("ollama.timeout_seconds", "0").is_err());
+        assert!(config.set_value("ollama.timeout_seconds", "soon").is_err());
+        assert!(config.set_value("chunking.overlap_size", "512").is_err());
+        assert!(config.set_value("ollama.no_such_key", "1").is_err());
+        assert!(config.set_value("nosection.key", "1").is_err());
+
+        // Rejected changes leave the settings untouched
+        assert_eq!(config.ollama.timeout_seconds, 30);
+        assert_eq!(config.chunking.overlap_size, 50);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_load_validates_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = Config::default();
+        config.chunking.overlap_size = config.chunking.max_chunk_size;
+        std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+
+        assert!(Config::load(Some(path)).is_err());
+    }
+
+    #[test]
+    fn test_provider_selects_default_model() {
+        let mut config = Config::default();
+        assert_eq!(config.default_model(), "nomic-embed-text");
+
+        config.set_value("provider", "openai").unwrap();
+        assert_eq!(config.provider, Provider::OpenAi);
+        assert_eq!(config.default_model(), "text-embedding-3-small");
+        assert_eq!(config.provider_url(), "https://api.openai.com");
+
+        assert!(config.set_value("provider", "bedrock").is_err());
+        assert!(
+            config
+                .set_value("openai.base_url", "api.openai.com")
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_chunking_strategy() {
         let config = ChunkingConfig::default();
         match config.to_strategy() {
-            ChunkStrategy::FixedSize { size, overlap } => {
+            ChunkStrategy::FixedSize {
+                size,
+                overlap,
+                overlap_unit,
+            } => {
                 assert_eq!(size, 512);
                 assert_eq!(overlap, 50);
+                assert_eq!(overlap_unit, OverlapUnit::Chars);
             }
             _ => panic!("Expected FixedSize strategy"),
         }
@@ -1,7 +1,10 @@
 //! Core domain types for the vector database
 
+use crate::error::{Result, VectDbError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
 /// A document that has been ingested into the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,19 +73,30 @@ pub struct Chunk {
 }
 
 impl Chunk {
-    /// Create a new chunk
+    /// Create a new chunk without a token count
     pub fn new(document_id: i64, chunk_index: usize, content: String) -> Self {
-        // Rough estimate: ~4 characters per token
-        let token_count = Some(content.len() / 4);
-
         Self {
             id: None,
             document_id,
             chunk_index,
             content,
-            token_count,
+            token_count: None,
         }
     }
+
+    /// Set the number of tokens in the chunk
+    pub fn with_token_count(mut self, token_count: usize) -> Self {
+        self.token_count = Some(token_count);
+        self
+    }
+}
+
+/// Text of a chunk to be stored, with its token count and embedding
+#[derive(Debug, Clone)]
+pub struct EmbeddedChunk {
+    pub content: String,
+    pub token_count: usize,
+    pub vector: Vec<f32>,
 }
 
 /// An embedding vector for a chunk
@@ -114,6 +128,97 @@ impl Embedding {
     }
 }
 
+/// A document with its chunks and embeddings, as one line of a JSONL dump
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentRecord {
+    /// Source path or URL
+    pub source: String,
+
+    /// SHA-256 hash of the content for deduplication
+    pub content_hash: String,
+
+    /// Arbitrary metadata
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+
+    /// Unix timestamp of creation
+    pub created_at: i64,
+
+    /// Chunks in document order
+    #[serde(default)]
+    pub chunks: Vec<ChunkRecord>,
+}
+
+/// A chunk within a [`DocumentRecord`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRecord {
+    /// Index of this chunk within the document (0-based)
+    pub chunk_index: usize,
+
+    /// The actual text content
+    pub content: String,
+
+    /// Approximate token count
+    #[serde(default)]
+    pub token_count: Option<usize>,
+
+    /// Stored embedding, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<EmbeddingRecord>,
+}
+
+/// An embedding within a [`ChunkRecord`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingRecord {
+    /// Model used to generate this embedding
+    pub model: String,
+
+    /// The embedding vector, written as base64 little-endian f32 bytes
+    ///
+    /// Plain float arrays are accepted when reading, for older dumps.
+    #[serde(with = "vector_base64")]
+    pub vector: Vec<f32>,
+}
+
+/// Lossless, compact JSON encoding of embedding vectors
+mod vector_base64 {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(vector: &[f32], serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+        serializer.serialize_str(&BASE64.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f32>, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Encoded {
+            Floats(Vec<f32>),
+            Base64(String),
+        }
+
+        match Encoded::deserialize(deserializer)? {
+            Encoded::Floats(vector) => Ok(vector),
+            Encoded::Base64(encoded) => {
+                let bytes = BASE64.decode(encoded.trim()).map_err(D::Error::custom)?;
+                if !bytes.len().is_multiple_of(4) {
+                    return Err(D::Error::custom(format!(
+                        "encoded vector has {} bytes, not a multiple of 4",
+                        bytes.len()
+                    )));
+                }
+                Ok(bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect())
+            }
+        }
+    }
+}
+
 /// Result from a semantic search
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -125,16 +230,33 @@ pub struct SearchResult {
 
     /// Similarity score (0.0-1.0, higher is better)
     pub similarity: f32,
+
+    /// Sentences of the chunk closest to the query, when smart snippets are on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
 }
 
 /// Chunking strategy configuration
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum ChunkStrategy {
     /// Fixed size with overlap
-    FixedSize { size: usize, overlap: usize },
+    FixedSize {
+        size: usize,
+        overlap: usize,
+        overlap_unit: OverlapUnit,
+    },
 
     /// Semantic boundaries (sentences, paragraphs)
     Semantic { max_size: usize },
+
+    /// Windows of BPE tokens with overlap, sized for the model's context
+    TokenBased {
+        max_tokens: usize,
+        overlap_tokens: usize,
+    },
+
+    /// Markdown sections, keeping code blocks and tables whole
+    Markdown { max_size: usize },
 }
 
 impl Default for ChunkStrategy {
@@ -142,6 +264,70 @@ impl Default for ChunkStrategy {
         ChunkStrategy::FixedSize {
             size: 512,
             overlap: 50,
+            overlap_unit: OverlapUnit::Chars,
+        }
+    }
+}
+
+/// What happens to NaN or infinite values in an embedding being stored
+///
+/// A single NaN makes every similarity computed against the vector NaN,
+/// which breaks the ordering of search results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NonFiniteEmbeddings {
+    /// Refuse to store the embedding
+    #[default]
+    Reject,
+    /// Store it with each non-finite value replaced by 0.0
+    Zero,
+}
+
+/// What the overlap of the fixed-size strategy is counted in
+///
+/// With words or sentences, chunks also end on a boundary of that unit, so
+/// no word is cut in half.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlapUnit {
+    /// Graphemes (user-perceived characters)
+    #[default]
+    Chars,
+    /// Words, as delimited by Unicode word boundaries
+    Words,
+    /// Sentences, as delimited by Unicode sentence boundaries
+    Sentences,
+}
+
+impl OverlapUnit {
+    /// Name used on the command line and in the config file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverlapUnit::Chars => "chars",
+            OverlapUnit::Words => "words",
+            OverlapUnit::Sentences => "sentences",
+        }
+    }
+}
+
+impl fmt::Display for OverlapUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OverlapUnit {
+    type Err = VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "chars" | "characters" => Ok(OverlapUnit::Chars),
+            "words" => Ok(OverlapUnit::Words),
+            "sentences" => Ok(OverlapUnit::Sentences),
+            other => Err(VectDbError::InvalidInput(format!(
+                "Invalid overlap unit: {}. Use chars, words or sentences",
+                other
+            ))),
         }
     }
 }
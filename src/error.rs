@@ -29,11 +29,58 @@ pub enum VectDbError {
     #[error("Embedding generation failed: {0}")]
     EmbeddingFailed(String),
 
+    #[error("Input too long for model: {0}")]
+    InputTooLong(String),
+
     #[error("Search failed: {0}")]
     SearchFailed(String),
 
     #[error("{0}")]
     Other(String),
+
+    #[error("{context}: {source}")]
+    WithContext {
+        context: String,
+        #[source]
+        source: Box<VectDbError>,
+    },
+}
+
+impl VectDbError {
+    /// Wrap this error with a description of what was being done
+    pub fn context(self, context: impl Into<String>) -> Self {
+        VectDbError::WithContext {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The innermost error, with all context layers removed
+    pub fn root_cause(&self) -> &VectDbError {
+        match self {
+            VectDbError::WithContext { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
+}
+
+/// Attach context to the error of a [`Result`]
+pub trait ResultExt<T> {
+    /// Wrap the error, if any, with lazily built context
+    fn with_context<F, C>(self, context: F) -> Result<T>
+    where
+        F: FnOnce() -> C,
+        C: Into<String>;
+}
+
+impl<T, E: Into<VectDbError>> ResultExt<T> for std::result::Result<T, E> {
+    fn with_context<F, C>(self, context: F) -> Result<T>
+    where
+        F: FnOnce() -> C,
+        C: Into<String>,
+    {
+        self.map_err(|e| e.into().context(context()))
+    }
 }
 
 /// Result type alias for VectDB operations
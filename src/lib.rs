@@ -14,9 +14,10 @@ pub mod error;
 pub mod repositories;
 pub mod server;
 pub mod services;
+pub mod util;
 
 // Re-export commonly used types
-pub use clients::OllamaClient;
+pub use clients::{Embedder, OllamaClient};
 pub use error::{Result, VectDbError};
 pub use repositories::VectorStore;
 pub use services::{IngestionService, SearchService};
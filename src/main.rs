@@ -4,12 +4,14 @@
 // Licensed under the MIT License (see LICENSE file)
 
 use clap::Parser;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
+use std::io::{BufRead, IsTerminal, Read, Write};
 use vectdb::Result;
-use vectdb::cli::{Cli, Commands};
-use vectdb::config::{Config, get_default_config_path};
+use vectdb::cli::{CacheAction, Cli, Commands, ConfigAction, IngestArgs, SearchArgs};
+use vectdb::clients::EmbeddingProvider;
+use vectdb::config::{Config, Provider, get_default_config_path};
 
 #[tokio::main]
 async fn main() {
@@ -37,7 +39,7 @@ async fn main() {
     };
 
     // Execute the command
-    if let Err(e) = execute_command(cli.command, config).await {
+    if let Err(e) = execute_command(cli.command, config, cli.config).await {
         error!("Command failed: {}", e);
         std::process::exit(1);
     }
@@ -60,31 +62,64 @@ fn init_logging(log_level: &str) -> Result<()> {
 }
 
 /// Execute the appropriate command
-async fn execute_command(command: Commands, config: Config) -> Result<()> {
+async fn execute_command(
+    command: Commands,
+    config: Config,
+    config_path: Option<std::path::PathBuf>,
+) -> Result<()> {
     match command {
         Commands::Init { force } => {
             info!("Initializing VectDB configuration");
             handle_init(force, config).await
         }
-        Commands::Ingest {
+        Commands::Ingest(args) => {
+            info!("Starting ingestion from: {:?}", args.source);
+            handle_ingest(args, config).await
+        }
+        Commands::Estimate {
             source,
             model,
             chunk_size,
             overlap,
             recursive,
+            samples,
         } => {
-            info!("Starting ingestion from: {:?}", source);
-            handle_ingest(source, model, chunk_size, overlap, recursive, config).await
+            info!("Estimating ingestion of: {:?}", source);
+            handle_estimate(
+                source, model, chunk_size, overlap, recursive, samples, config,
+            )
+            .await
+        }
+        Commands::Config { action } => handle_config(action, config, config_path),
+        Commands::Chunk {
+            file,
+            strategy,
+            size,
+            overlap,
+            overlap_unit,
+            format,
+        } => handle_chunk(file, strategy, size, overlap, overlap_unit, format),
+        Commands::Search(args) => {
+            info!("Searching for: {}", args.query);
+            handle_search(args, config).await
         }
-        Commands::Search {
-            query,
+        Commands::SearchBatch {
+            queries,
             top_k,
             threshold,
-            explain,
-            format,
+            trec_run,
+            run_name,
+            output,
         } => {
-            info!("Searching for: {}", query);
-            handle_search(query, top_k, threshold, explain, format, config).await
+            info!("Running batch search from: {:?}", queries);
+            handle_search_batch(
+                queries, top_k, threshold, trec_run, run_name, output, config,
+            )
+            .await
+        }
+        Commands::Regress { golden, top_k } => {
+            info!("Running golden queries from: {:?}", golden);
+            handle_regress(golden, top_k, config).await
         }
         Commands::Serve { port, host } => {
             info!("Starting web server on {}:{}", host, port);
@@ -94,13 +129,80 @@ async fn execute_command(command: Commands, config: Config) -> Result<()> {
             info!("Displaying database statistics");
             handle_stats(config).await
         }
-        Commands::Optimize => {
+        Commands::Schema { type_name } => handle_schema(&type_name),
+        Commands::List {
+            limit,
+            offset,
+            sort,
+            order,
+            json,
+        } => {
+            info!("Listing documents");
+            handle_list(limit, offset, sort, order, json, config)
+        }
+        Commands::Delete {
+            id,
+            source,
+            dry_run,
+        } => {
+            info!("Deleting documents");
+            handle_delete(id, source, dry_run, config)
+        }
+        Commands::Prune { dry_run } => {
+            info!("Pruning incomplete documents");
+            handle_prune(dry_run, config)
+        }
+        Commands::Sample { n, seed } => handle_sample(n, seed, config),
+        Commands::Coverage { model } => {
+            info!("Checking embedding coverage");
+            handle_coverage(model, config).await
+        }
+        Commands::Reindex { model, force } => {
+            info!("Reindexing chunks with model {}", model);
+            handle_reindex(model, force, config).await
+        }
+        Commands::ReindexDoc { id, model } => {
+            info!("Re-embedding document {}", id);
+            handle_reindex_doc(id, model, config).await
+        }
+        Commands::Export {
+            output,
+            include_vectors,
+        } => {
+            info!("Exporting to: {:?}", output);
+            handle_export(output, include_vectors, config)
+        }
+        Commands::Import {
+            input,
+            resume,
+            batch_size,
+        } => {
+            info!("Importing from: {:?}", input);
+            handle_import(input, resume, batch_size, config).await
+        }
+        Commands::Merge { other } => {
+            info!("Merging from: {:?}", other);
+            handle_merge(other, config).await
+        }
+        Commands::Recompress {
+            target_precision,
+            batch_size,
+        } => {
+            info!("Recompressing embeddings as {}", target_precision);
+            handle_recompress(target_precision, batch_size, config).await
+        }
+        Commands::Cache { action } => handle_cache(action, config),
+        Commands::Optimize { incremental } => {
             info!("Optimizing database");
-            handle_optimize(config).await
+            handle_optimize(incremental, config).await
         }
-        Commands::Models => {
+        Commands::Models { format } => {
             info!("Listing available Ollama models");
-            handle_models(config).await
+            handle_models(config, &format).await
+        }
+        Commands::ModelInfo { model } => {
+            info!("Probing embedding model");
+            handle_model_info(model, config).await
         }
     }
 }
@@ -126,56 +228,171 @@ async fn handle_init(force: bool, config: Config) -> Result<()> {
     Ok(())
 }
 
-/// Handle the ingest command
-async fn handle_ingest(
-    source: std::path::PathBuf,
-    model: String,
-    chunk_size: usize,
-    overlap: usize,
-    recursive: bool,
-    config: Config,
+/// Handle the config command
+fn handle_config(
+    action: ConfigAction,
+    mut config: Config,
+    config_path: Option<std::path::PathBuf>,
 ) -> Result<()> {
-    use vectdb::domain::ChunkStrategy;
-    use vectdb::{IngestionService, OllamaClient, VectorStore};
+    match action {
+        ConfigAction::Show => {
+            let contents = toml::to_string_pretty(&config).map_err(|e| {
+                vectdb::VectDbError::Config(format!("Failed to serialize config: {}", e))
+            })?;
+            print!("{}", contents);
+        }
+        ConfigAction::Get { key } => match config.get_value(&key)? {
+            toml::Value::String(value) => println!("{}", value),
+            toml::Value::Table(table) => print!("{}", table),
+            value => println!("{}", value),
+        },
+        ConfigAction::Set { key, value } => {
+            let path = config_path
+                .or_else(get_default_config_path)
+                .ok_or_else(|| {
+                    vectdb::VectDbError::Config("Could not determine config directory".to_string())
+                })?;
+
+            config.set_value(&key, &value)?;
+            config.save(&path)?;
+            println!("Set {} = {} in {:?}", key, config.get_value(&key)?, path);
+        }
+    }
 
-    println!("Starting ingestion from: {:?}\n", source);
+    Ok(())
+}
 
-    // Initialize services
-    let store = VectorStore::new(&config.database.path)?;
-    let ollama = OllamaClient::new(
-        config.ollama.base_url.clone(),
-        config.ollama.timeout_seconds,
-    )?;
-
-    // Check Ollama connection
-    if !ollama.health_check().await? {
-        println!("❌ Cannot connect to Ollama at {}", config.ollama.base_url);
-        println!("\nMake sure Ollama is running:");
-        println!("  ollama serve");
-        return Ok(());
+/// Explain that the configured embedding provider can't be reached
+fn print_provider_unreachable(config: &Config) {
+    match config.provider {
+        Provider::Ollama => {
+            println!("❌ Cannot connect to Ollama at {}", config.ollama.base_url);
+            println!("\nMake sure Ollama is running:");
+            println!("  ollama serve");
+        }
+        Provider::OpenAi => {
+            println!(
+                "❌ Cannot reach the OpenAI API at {}",
+                config.openai.base_url
+            );
+            println!("\nCheck openai.base_url and openai.api_key in the configuration");
+        }
     }
+}
 
-    // Check if model exists
-    if !ollama.has_model(&model).await? {
-        println!("❌ Model '{}' not found in Ollama", model);
-        println!("\nPull the model first:");
-        println!("  ollama pull {}", model);
-        return Ok(());
+/// Warn when the Ollama server is older than required. An unreachable
+/// server is only fatal in strict mode; otherwise the command carries on.
+async fn check_ollama_version(ollama: &vectdb::OllamaClient, config: &Config) -> Result<()> {
+    match ollama
+        .check_version(
+            config.ollama.min_version(),
+            config.ollama.strict_version_check,
+        )
+        .await
+    {
+        Ok(None) => {}
+        Ok(Some(warning)) => println!("⚠ {}\n", warning),
+        Err(e) if config.ollama.strict_version_check => return Err(e),
+        Err(e) => warn!("Could not check the Ollama version: {}", e),
     }
+    Ok(())
+}
 
-    println!("✓ Connected to Ollama");
-    println!("✓ Model '{}' available\n", model);
+/// Chunk strategy for an ingest or its dry run
+///
+/// Chunking options given on the command line override the configured ones.
+fn ingest_strategy(args: &IngestArgs, config: &Config) -> Result<vectdb::domain::ChunkStrategy> {
+    use vectdb::config::ChunkingConfig;
+    use vectdb::services::chunking::strategy_from_name;
+
+    let chunk_size = args.chunk_size.unwrap_or(config.chunking.max_chunk_size);
+    let overlap = args.overlap.unwrap_or(config.chunking.overlap_size);
+    let overlap_unit = match &args.overlap_unit {
+        Some(unit) => unit.parse()?,
+        None => config.chunking.overlap_unit,
+    };
+    match &args.strategy {
+        Some(name) => strategy_from_name(name, chunk_size, overlap, overlap_unit),
+        None => Ok(ChunkingConfig {
+            max_chunk_size: chunk_size,
+            overlap_size: overlap,
+            overlap_unit,
+            ..config.chunking.clone()
+        }
+        .to_strategy()),
+    }
+}
 
-    let mut service = IngestionService::new(store, ollama);
+/// Source name recorded for a document read from stdin (`vectdb ingest -`)
+const STDIN_SOURCE: &str = "stdin";
 
-    // Determine chunk strategy
-    let strategy = ChunkStrategy::FixedSize {
-        size: chunk_size,
-        overlap,
-    };
+/// The whole `stdin` stream when the ingest source is `-`, or None for a path
+///
+/// The text is ingested with `IngestionService::ingest_text`, the entry point
+/// for content that does not come from a file.
+fn read_stdin_source(source: &std::path::Path, mut stdin: impl Read) -> Result<Option<String>> {
+    if source.as_os_str() != "-" {
+        return Ok(None);
+    }
+    let mut content = String::new();
+    stdin.read_to_string(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Handle the ingest command
+async fn handle_ingest(args: IngestArgs, mut config: Config) -> Result<()> {
+    use vectdb::services::code_filter::CodeFilter;
+    use vectdb::services::ingestion::{IngestionOptions, parse_encoding};
+    use vectdb::{Embedder, IngestionService, VectorStore};
+
+    let model = args
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model().to_string());
+    if let Some(dimensions) = args.dimensions {
+        config.ollama.truncate_dimension = Some(check_dimensions(dimensions)?);
+    }
 
-    // Collect files to ingest
-    let files = collect_files(&source, recursive)?;
+    println!("Starting ingestion from: {:?}\n", args.source);
+
+    let mut options = IngestionOptions::from_config(&config)?;
+    if let Some(label) = &args.encoding {
+        options.fallback_encoding = Some(parse_encoding(label)?);
+    }
+    options.code_filter = args.code_filter.parse()?;
+    if args.no_cache {
+        options.use_embedding_cache = false;
+    }
+    if options.code_filter != CodeFilter::All {
+        println!("Code filter: {}", options.code_filter);
+    }
+
+    let strategy = ingest_strategy(&args, &config)?;
+
+    // `-` reads a single document from stdin; otherwise collect files to ingest
+    let stdin_content = read_stdin_source(&args.source, std::io::stdin())?;
+    let collected = match &stdin_content {
+        Some(_) => CollectedFiles {
+            files: vec![std::path::PathBuf::from(STDIN_SOURCE)],
+            skipped_by_size: 0,
+        },
+        None => collect_files(
+            &args.source,
+            &CollectOptions {
+                recursive: args.recursive,
+                follow_symlinks: args.follow_symlinks,
+                min_file_size: args.min_file_size,
+                max_file_size: args.max_file_size,
+                order: args.order.as_deref().map(str::parse).transpose()?,
+                include: build_glob_set(&args.include)?,
+                exclude: build_glob_set(&args.exclude)?,
+                hidden: args.hidden,
+                respect_gitignore: args.respect_gitignore,
+                include_code: args.include_code || config.ingestion.include_code,
+            },
+        )?,
+    };
+    let files = collected.files;
 
     if files.is_empty() {
         println!("No files found to ingest.");
@@ -184,16 +401,89 @@ async fn handle_ingest(
 
     println!("Found {} file(s) to process\n", files.len());
 
+    if args.dry_run {
+        // Chunk in memory only: the database file is never opened and Ollama is never contacted
+        let service =
+            IngestionService::new(VectorStore::in_memory()?, Embedder::from_config(&config)?)
+                .with_options(options);
+        return print_ingest_dry_run(&service, &files, stdin_content.as_deref(), strategy);
+    }
+
+    // Initialize services
+    let mut store = VectorStore::from_config(&config)?;
+    let embedder = Embedder::from_config(&config)?;
+
+    // Check the provider connection
+    if !embedder.health_check().await? {
+        print_provider_unreachable(&config);
+        return Ok(());
+    }
+
+    // Check if model exists
+    if !embedder.has_model(&model).await? {
+        println!("❌ Model '{}' not found in {}", model, config.provider);
+        let Some(ollama) = embedder.as_ollama() else {
+            return Ok(());
+        };
+        if !is_interactive() || !confirm(&format!("Pull '{}' now?", model))? {
+            println!("\nPull the model first:");
+            println!("  ollama pull {}", model);
+            return Ok(());
+        }
+        pull_model_interactive(ollama, &model).await?;
+    }
+
+    println!("✓ Connected to {}", config.provider);
+    println!("✓ Model '{}' available\n", model);
+
+    if let Some(ollama) = embedder.as_ollama() {
+        check_ollama_version(ollama, &config).await?;
+        if config.ollama.check_embedding_model
+            && let Some(warning) = ollama.check_embedding_model(&model).await
+        {
+            println!("⚠ {}\n", warning);
+        }
+    }
+    if config.ollama.check_model_version
+        && let Some(warning) = check_model_version(&mut store, &embedder, &model).await?
+    {
+        println!("⚠ {}\n", warning);
+    }
+
+    let mut service = IngestionService::new(store, embedder).with_options(options);
+
+    if args.bulk {
+        println!("⚠ Bulk mode: a crash before ingestion finishes may corrupt the database.\n");
+        service.store().begin_bulk_mode()?;
+    }
+
     // Process files
     let mut total_chunks = 0;
     let mut total_embeddings = 0;
     let mut skipped = 0;
+    let mut failures = Vec::new();
+    let mut retries = vectdb::clients::ollama::RetryStats::default();
 
     for (idx, file) in files.iter().enumerate() {
         println!("[{}/{}] Processing: {:?}", idx + 1, files.len(), file);
 
-        match service.ingest_file(file, &model, strategy).await {
+        let ingestion = match &stdin_content {
+            Some(content) => {
+                service
+                    .ingest_text(STDIN_SOURCE, content, &model, strategy)
+                    .await
+            }
+            None => service.ingest_file(file, &model, strategy).await,
+        };
+        match ingestion {
             Ok(result) => {
+                retries.merge(&result.retries);
+                if let Some(duplicate) = &result.near_duplicate {
+                    println!(
+                        "  ⚠ Near-duplicate of [{}] {} (similarity {:.4})",
+                        duplicate.document_id, duplicate.source, duplicate.similarity
+                    );
+                }
                 if result.skipped {
                     println!("  ⊘ Skipped (duplicate or empty)");
                     skipped += 1;
@@ -208,49 +498,453 @@ async fn handle_ingest(
             }
             Err(e) => {
                 println!("  ❌ Error: {}", e);
+                failures.push(e);
                 skipped += 1;
             }
         }
         println!();
     }
 
+    if args.bulk {
+        println!("Rebuilding indices...\n");
+        service.store().end_bulk_mode()?;
+    }
+
     // Summary
     println!("=== Ingestion Complete ===");
     println!("Files processed: {}", files.len());
     println!("Files skipped:   {}", skipped);
+    if collected.skipped_by_size > 0 {
+        println!("Skipped by size: {}", collected.skipped_by_size);
+    }
     println!("Chunks created:  {}", total_chunks);
     println!("Embeddings:      {}", total_embeddings);
+    println!(
+        "Retries:         {} across {} chunks ({:.1}s backoff)",
+        retries.retries,
+        retries.retried_inputs,
+        retries.backoff.as_secs_f64()
+    );
+
+    if !failures.is_empty() {
+        println!("\nFailed files ({}):", failures.len());
+        for failure in &failures {
+            println!("  • {}", failure);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the per-file chunking an ingest would produce, without ingesting
+///
+/// `stdin_content` is the document read from stdin when the source is `-`.
+fn print_ingest_dry_run(
+    service: &vectdb::IngestionService<vectdb::Embedder>,
+    files: &[std::path::PathBuf],
+    stdin_content: Option<&str>,
+    strategy: vectdb::domain::ChunkStrategy,
+) -> Result<()> {
+    let mut total_chunks = 0;
+    let mut total_tokens = 0;
+    let mut failed = 0;
+
+    for file in files {
+        let plan = match stdin_content {
+            Some(content) => Ok(service.plan_text(file, content, strategy)),
+            None => service.plan_file(file, strategy),
+        };
+        match plan {
+            Ok(plan) => {
+                println!(
+                    "  {:?}: {} chunks, ~{} tokens",
+                    file,
+                    plan.chunk_count(),
+                    plan.token_count()
+                );
+                total_chunks += plan.chunk_count();
+                total_tokens += plan.token_count();
+            }
+            Err(e) => {
+                println!("  {:?}: ❌ {}", file, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n=== Dry Run (nothing written) ===");
+    println!("Files:               {}", files.len());
+    if failed > 0 {
+        println!("Unreadable files:    {}", failed);
+    }
+    println!("Chunks:              {}", total_chunks);
+    println!("Embeddings (est.):   {}", total_chunks);
+    println!("Tokens (approx):     {}", total_tokens);
+
+    Ok(())
+}
+
+/// Handle the chunk command
+fn handle_chunk(
+    file: std::path::PathBuf,
+    strategy: String,
+    size: usize,
+    overlap: usize,
+    overlap_unit: String,
+    format: String,
+) -> Result<()> {
+    use vectdb::services::chunking::{preview_chunks, strategy_from_name, write_chunk_preview};
+    use vectdb::services::code_filter::CodeFilter;
+    use vectdb::services::loaders::LoaderRegistry;
+
+    let strategy = strategy_from_name(&strategy, size, overlap, overlap_unit.parse()?)?;
+    let content = LoaderRegistry::new(None, CodeFilter::All).load(&file)?;
+    let chunks = preview_chunks(&content, strategy);
+
+    let stdout = std::io::stdout();
+    let mut writer = stdout.lock();
+    write_chunk_preview(&mut writer, &chunks, &format)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Whether both stdin and stdout are attached to a terminal
+fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Compare the installed model's digest with the one its embeddings were made with
+async fn check_model_version(
+    store: &mut vectdb::VectorStore,
+    embedder: &vectdb::Embedder,
+    model: &str,
+) -> Result<Option<String>> {
+    match embedder.model_digest(model).await? {
+        Some(digest) => store.check_model_version(model, &digest),
+        None => Ok(None),
+    }
+}
+
+/// Pull a model through Ollama, printing download progress
+async fn pull_model_interactive(ollama: &vectdb::OllamaClient, model: &str) -> Result<()> {
+    let mut last_status = String::new();
+    ollama
+        .pull_model_with_progress(model, |progress| {
+            match (progress.completed, progress.total) {
+                (Some(completed), Some(total)) if total > 0 => {
+                    print!("\r  {} {:>3}%", progress.status, completed * 100 / total);
+                }
+                _ if progress.status != last_status => {
+                    if !last_status.is_empty() {
+                        println!();
+                    }
+                    print!("  {}", progress.status);
+                }
+                _ => {}
+            }
+            last_status = progress.status.clone();
+            let _ = std::io::stdout().flush();
+        })
+        .await?;
+    println!("\n✓ Pulled model '{}'\n", model);
+    Ok(())
+}
+
+/// Handle the estimate command
+async fn handle_estimate(
+    source: std::path::PathBuf,
+    model: String,
+    chunk_size: usize,
+    overlap: usize,
+    recursive: bool,
+    samples: usize,
+    config: Config,
+) -> Result<()> {
+    use vectdb::domain::ChunkStrategy;
+    use vectdb::services::ingestion::IngestionOptions;
+    use vectdb::{Embedder, IngestionService, VectorStore};
+
+    let files = collect_files(
+        &source,
+        &CollectOptions {
+            recursive,
+            include_code: config.ingestion.include_code,
+            ..Default::default()
+        },
+    )?
+    .files;
+
+    if files.is_empty() {
+        println!("No files found to ingest.");
+        return Ok(());
+    }
+
+    let store = VectorStore::in_memory()?;
+    let embedder = Embedder::from_config(&config)?;
+
+    // Check the provider connection (needed to measure embedding latency)
+    if !embedder.health_check().await? {
+        print_provider_unreachable(&config);
+        return Ok(());
+    }
+
+    let service = IngestionService::new(store, embedder)
+        .with_options(IngestionOptions::from_config(&config)?);
+    let strategy = ChunkStrategy::FixedSize {
+        size: chunk_size,
+        overlap,
+        overlap_unit: config.chunking.overlap_unit,
+    };
+
+    println!(
+        "Estimating ingestion of {} file(s) with '{}'...\n",
+        files.len(),
+        model
+    );
+    let estimate = service
+        .estimate_files(&files, &model, strategy, samples)
+        .await?;
+
+    println!("=== Ingestion Estimate ===");
+    println!("Files:              {}", estimate.file_count);
+    println!("Chunks:             {}", estimate.chunk_count);
+    println!("Tokens (approx):    {}", estimate.token_count);
+    println!(
+        "Latency per chunk:  {:.1} ms",
+        estimate.per_chunk_latency.as_secs_f64() * 1000.0
+    );
+    println!(
+        "Estimated duration: {:.1} s",
+        estimate.estimated_duration.as_secs_f64()
+    );
 
     Ok(())
 }
 
+/// Options controlling which files are collected for ingestion
+#[derive(Debug, Default)]
+struct CollectOptions {
+    recursive: bool,
+    follow_symlinks: bool,
+    min_file_size: Option<u64>,
+    max_file_size: Option<u64>,
+    /// Sort the files; `None` keeps filesystem order
+    order: Option<FileOrder>,
+    /// Only keep directory entries whose path relative to the source matches
+    include: Option<globset::GlobSet>,
+    /// Drop directory entries whose path relative to the source matches
+    exclude: Option<globset::GlobSet>,
+    /// Include dotfiles and files inside dot-directories such as `.git`
+    hidden: bool,
+    /// Skip files ignored by `.gitignore` files in and above the source directory
+    respect_gitignore: bool,
+    /// Also collect source code files found in directories
+    include_code: bool,
+}
+
+impl CollectOptions {
+    /// Whether a file found under the source directory passes the include/exclude patterns
+    fn matches_patterns(&self, source: &std::path::Path, path: &std::path::Path) -> bool {
+        let relative = path.strip_prefix(source).unwrap_or(path);
+        self.include
+            .as_ref()
+            .is_none_or(|set| set.is_match(relative))
+            && !self
+                .exclude
+                .as_ref()
+                .is_some_and(|set| set.is_match(relative))
+    }
+}
+
+/// Compile `--include`/`--exclude` globs; no patterns means no filter
+///
+/// `*` does not cross directory separators, so `**/` is needed to match at any depth.
+fn build_glob_set(patterns: &[String]) -> Result<Option<globset::GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = globset::GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = globset::GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| {
+                vectdb::VectDbError::InvalidInput(format!("Invalid glob {:?}: {}", pattern, e))
+            })?;
+        builder.add(glob);
+    }
+    let set = builder
+        .build()
+        .map_err(|e| vectdb::VectDbError::InvalidInput(format!("Invalid glob set: {}", e)))?;
+    Ok(Some(set))
+}
+
+/// Order in which collected files are ingested
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileOrder {
+    /// By path
+    Name,
+    /// Smallest first
+    SizeAsc,
+    /// Largest first
+    SizeDesc,
+    /// Least recently modified first
+    Mtime,
+}
+
+impl std::str::FromStr for FileOrder {
+    type Err = vectdb::VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(FileOrder::Name),
+            "size-asc" => Ok(FileOrder::SizeAsc),
+            "size-desc" => Ok(FileOrder::SizeDesc),
+            "mtime" => Ok(FileOrder::Mtime),
+            other => Err(vectdb::VectDbError::InvalidInput(format!(
+                "Invalid file order: {}. Use name, size-asc, size-desc or mtime",
+                other
+            ))),
+        }
+    }
+}
+
+/// Sort files in place, breaking ties by path so the order is deterministic
+fn sort_files(files: &mut [std::path::PathBuf], order: FileOrder) {
+    match order {
+        FileOrder::Name => files.sort(),
+        FileOrder::SizeAsc | FileOrder::SizeDesc | FileOrder::Mtime => {
+            files.sort_by_cached_key(|path| {
+                let metadata = std::fs::metadata(path).ok();
+                let size = metadata.as_ref().map_or(0, |m| m.len());
+                let key = match order {
+                    FileOrder::SizeAsc => size as i128,
+                    FileOrder::SizeDesc => -(size as i128),
+                    _ => metadata
+                        .and_then(|m| m.modified().ok())
+                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map_or(0, |d| d.as_nanos() as i128),
+                };
+                (key, path.clone())
+            });
+        }
+    }
+}
+
+/// Files collected for ingestion, along with how many were filtered out
+#[derive(Debug, Default)]
+struct CollectedFiles {
+    files: Vec<std::path::PathBuf>,
+    skipped_by_size: usize,
+}
+
+impl CollectedFiles {
+    /// Add a candidate file, applying the size filters
+    fn push(&mut self, path: std::path::PathBuf, options: &CollectOptions) -> Result<()> {
+        let size = std::fs::metadata(&path)?.len();
+
+        if let Some(min) = options.min_file_size
+            && size < min
+        {
+            info!(
+                "Skipping {:?}: {} bytes is below --min-file-size {}",
+                path, size, min
+            );
+            self.skipped_by_size += 1;
+            return Ok(());
+        }
+
+        if let Some(max) = options.max_file_size
+            && size > max
+        {
+            info!(
+                "Skipping {:?}: {} bytes exceeds --max-file-size {}",
+                path, size, max
+            );
+            self.skipped_by_size += 1;
+            return Ok(());
+        }
+
+        self.files.push(path);
+        Ok(())
+    }
+}
+
 /// Collect files to ingest
-fn collect_files(source: &std::path::Path, recursive: bool) -> Result<Vec<std::path::PathBuf>> {
-    let mut files = Vec::new();
+fn collect_files(source: &std::path::Path, options: &CollectOptions) -> Result<CollectedFiles> {
+    let mut collected = CollectedFiles::default();
 
     if source.is_file() {
-        files.push(source.to_path_buf());
+        collected.push(source.to_path_buf(), options)?;
     } else if source.is_dir() {
-        if recursive {
+        if options.respect_gitignore {
+            let walker = ignore::WalkBuilder::new(source)
+                .standard_filters(false)
+                .hidden(!options.hidden)
+                .git_ignore(true)
+                .git_exclude(true)
+                .parents(true)
+                .require_git(false)
+                .follow_links(options.follow_symlinks)
+                .max_depth(if options.recursive { None } else { Some(1) })
+                .build();
+            for entry in walker.filter_map(|e| e.ok()) {
+                if entry.file_type().is_some_and(|t| t.is_file()) {
+                    let path = entry.path();
+                    if is_supported_file(path, options.include_code)
+                        && options.matches_patterns(source, path)
+                    {
+                        collected.push(path.to_path_buf(), options)?;
+                    }
+                }
+            }
+        } else if options.recursive {
             for entry in walkdir::WalkDir::new(source)
-                .follow_links(true)
+                .follow_links(options.follow_symlinks)
                 .into_iter()
+                // Depth 0 is the source itself, which is walked even if its name starts with a dot
+                .filter_entry(|e| options.hidden || e.depth() == 0 || !is_hidden(e.file_name()))
                 .filter_map(|e| e.ok())
             {
                 if entry.file_type().is_file() {
                     let path = entry.path();
-                    if is_supported_file(path) {
-                        files.push(path.to_path_buf());
+                    if is_supported_file(path, options.include_code)
+                        && options.matches_patterns(source, path)
+                    {
+                        collected.push(path.to_path_buf(), options)?;
                     }
                 }
             }
         } else {
             for entry in std::fs::read_dir(source)? {
                 let entry = entry?;
-                if entry.file_type()?.is_file() {
+                if !options.hidden && is_hidden(&entry.file_name()) {
+                    continue;
+                }
+                let file_type = entry.file_type()?;
+                let is_file = if file_type.is_symlink() {
+                    options.follow_symlinks && entry.path().is_file()
+                } else {
+                    file_type.is_file()
+                };
+                if is_file {
                     let path = entry.path();
-                    if is_supported_file(&path) {
-                        files.push(path);
+                    if is_supported_file(&path, options.include_code)
+                        && options.matches_patterns(source, &path)
+                    {
+                        collected.push(path, options)?;
                     }
                 }
             }
@@ -262,43 +956,118 @@ fn collect_files(source: &std::path::Path, recursive: bool) -> Result<Vec<std::p
         )));
     }
 
-    Ok(files)
+    if let Some(order) = options.order {
+        sort_files(&mut collected.files, order);
+    }
+
+    Ok(collected)
 }
 
-/// Check if file is supported
-fn is_supported_file(path: &std::path::Path) -> bool {
+/// Whether a file or directory name is a dotfile such as `.git` or `.env`
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_string_lossy().starts_with('.')
+}
+
+/// Check if file is supported; source code files only count when `include_code` is set
+fn is_supported_file(path: &std::path::Path, include_code: bool) -> bool {
     if let Some(ext) = path.extension() {
         let ext = ext.to_string_lossy().to_lowercase();
-        matches!(ext.as_str(), "txt" | "md" | "markdown")
+        matches!(
+            ext.as_str(),
+            "txt" | "md" | "markdown" | "html" | "htm" | "pdf"
+        ) || (include_code
+            && vectdb::services::code_filter::CODE_EXTENSIONS.contains(&ext.as_str()))
     } else {
         false
     }
 }
 
+/// Result count and absolute similarity threshold for a search
+///
+/// Flags take precedence over the config file's search defaults. A
+/// `--relative-threshold` replaces the absolute cutoff, so the configured
+/// `similarity_threshold` is not applied on top of it.
+fn search_limits(args: &SearchArgs, config: &Config) -> (usize, f32) {
+    let top_k = args.top_k.unwrap_or(config.search.default_top_k);
+    let threshold = if args.relative_threshold.is_some() {
+        0.0
+    } else {
+        args.threshold.unwrap_or(config.search.similarity_threshold)
+    };
+    (top_k, threshold)
+}
+
 /// Handle the search command
-async fn handle_search(
-    query: String,
-    top_k: usize,
-    threshold: f32,
-    explain: bool,
-    format: String,
-    config: Config,
-) -> Result<()> {
-    use vectdb::services::search::{format_results_csv, format_results_json, format_results_text};
-    use vectdb::{OllamaClient, SearchService, VectorStore};
+async fn handle_search(args: SearchArgs, mut config: Config) -> Result<()> {
+    use vectdb::services::search::{
+        MetadataBoosts, format_results_text, parse_boost, parse_document_ids, parse_fields,
+        validate_query, write_projected_json, write_results_csv, write_results_json,
+        write_results_json_with_stats,
+    };
+    use vectdb::{Embedder, SearchService, VectorStore};
+
+    // Reject empty queries before touching Ollama
+    validate_query(&args.query)?;
+
+    let document_ids = args
+        .in_docs
+        .as_deref()
+        .map(parse_document_ids)
+        .transpose()?;
+    let boosts = args
+        .boost
+        .iter()
+        .map(|spec| parse_boost(spec))
+        .collect::<Result<MetadataBoosts>>()?;
+    let fields = args.fields.as_deref().map(parse_fields).transpose()?;
+
+    let (top_k, threshold) = search_limits(&args, &config);
+    if top_k == 0 {
+        return Err(vectdb::VectDbError::InvalidInput(
+            "--top-k must be greater than 0".to_string(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(vectdb::VectDbError::InvalidInput(format!(
+            "Similarity threshold must be between 0.0 and 1.0, got {}",
+            threshold
+        )));
+    }
+    if let Some(lambda) = args.mmr
+        && !(0.0..=1.0).contains(&lambda)
+    {
+        return Err(vectdb::VectDbError::InvalidInput(format!(
+            "MMR lambda must be between 0.0 and 1.0, got {}",
+            lambda
+        )));
+    }
+    if let Some(alpha) = args.hybrid
+        && !(0.0..=1.0).contains(&alpha)
+    {
+        return Err(vectdb::VectDbError::InvalidInput(format!(
+            "Hybrid alpha must be between 0.0 and 1.0, got {}",
+            alpha
+        )));
+    }
+    if let Some(ratio) = args.relative_threshold
+        && !(0.0..=1.0).contains(&ratio)
+    {
+        return Err(vectdb::VectDbError::InvalidInput(format!(
+            "Relative threshold must be between 0.0 and 1.0, got {}",
+            ratio
+        )));
+    }
+    if let Some(dimensions) = args.dimensions {
+        config.ollama.truncate_dimension = Some(check_dimensions(dimensions)?);
+    }
 
     // Initialize services
-    let store = VectorStore::new(&config.database.path)?;
-    let ollama = OllamaClient::new(
-        config.ollama.base_url.clone(),
-        config.ollama.timeout_seconds,
-    )?;
-
-    // Check Ollama connection
-    if !ollama.health_check().await? {
-        println!("❌ Cannot connect to Ollama at {}", config.ollama.base_url);
-        println!("\nMake sure Ollama is running:");
-        println!("  ollama serve");
+    let mut store = VectorStore::from_config(&config)?;
+    let embedder = Embedder::from_config(&config)?;
+
+    // Check the provider connection
+    if !embedder.health_check().await? {
+        print_provider_unreachable(&config);
         return Ok(());
     }
 
@@ -311,25 +1080,196 @@ async fn handle_search(
         return Ok(());
     }
 
-    let service = SearchService::new(store, ollama);
+    let model = config.default_model();
 
-    // Perform search
-    let model = &config.ollama.default_model;
-    let results = service.search(&query, model, top_k, threshold).await?;
-
-    // Format and display results
-    let output = match format.as_str() {
-        "json" => format_results_json(&results)?,
-        "csv" => format_results_csv(&results),
-        _ => format_results_text(&results, explain),
-    };
+    // A single query embedding doesn't need the batch endpoint, so search only
+    // checks the version when a minimum was asked for
+    if config.ollama.min_version.is_some()
+        && let Some(ollama) = embedder.as_ollama()
+    {
+        check_ollama_version(ollama, &config).await?;
+    }
+    if config.ollama.check_model_version
+        && let Some(warning) = check_model_version(&mut store, &embedder, model).await?
+    {
+        println!("⚠ {}\n", warning);
+    }
 
-    println!("{}", output);
+    if args.explain_sql {
+        println!("=== Query Plan ===");
+        for line in store.explain_search_plan(model)? {
+            println!("{}", line);
+        }
+        println!();
+    }
 
-    Ok(())
-}
+    let mut service = SearchService::new(store, embedder)
+        .with_boosts(boosts)
+        .with_deadline(search_deadline(&config))
+        .with_smart_snippets(args.smart_snippet)
+        .with_embedding_model_check(config.ollama.check_embedding_model);
+    if let Some(alpha) = args.hybrid {
+        service = service.with_hybrid(alpha);
+    }
+    if let Some(lambda) = args.mmr {
+        service = service.with_mmr(lambda);
+    }
+    if let Some(ratio) = args.relative_threshold {
+        service = service.with_relative_threshold(ratio);
+    }
 
-/// Handle the serve command
+    // Perform search
+    let (results, stats) = match &document_ids {
+        Some(ids) => {
+            service
+                .search_within_documents_with_stats(
+                    &args.query,
+                    model,
+                    top_k,
+                    threshold,
+                    args.exclude.as_deref(),
+                    ids,
+                )
+                .await?
+        }
+        None => {
+            service
+                .search_with_stats(
+                    &args.query,
+                    model,
+                    top_k,
+                    0,
+                    threshold,
+                    args.exclude.as_deref(),
+                )
+                .await?
+        }
+    };
+
+    // Format and write results, streaming json/csv straight to the destination
+    let mut writer: Box<dyn Write> = match &args.output_file {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    match args.format.as_str() {
+        "json" => {
+            match &fields {
+                _ if args.explain => {
+                    write_results_json_with_stats(&mut writer, &results, fields.as_deref(), &stats)?
+                }
+                Some(fields) => write_projected_json(&mut writer, &results, fields)?,
+                None => write_results_json(&mut writer, &results)?,
+            }
+            writeln!(writer)?;
+        }
+        "csv" => write_results_csv(&mut writer, &results)?,
+        _ => {
+            writeln!(
+                writer,
+                "{}",
+                format_results_text(&results, args.explain.then_some(args.query.as_str()))
+            )?;
+            if args.explain {
+                writeln!(writer, "{}", stats.summary())?;
+            }
+        }
+    }
+    writer.flush()?;
+
+    if let Some(path) = &args.output_file {
+        println!("Wrote {} result(s) to {:?}", results.len(), path);
+    }
+
+    Ok(())
+}
+
+/// Configured per-search deadline
+fn search_deadline(config: &Config) -> Option<std::time::Duration> {
+    config
+        .search
+        .deadline_ms
+        .map(std::time::Duration::from_millis)
+}
+
+/// Handle the search-batch command
+async fn handle_search_batch(
+    queries_path: std::path::PathBuf,
+    top_k: usize,
+    threshold: f32,
+    trec_run: bool,
+    run_name: String,
+    output: String,
+    config: Config,
+) -> Result<()> {
+    use vectdb::services::search::{
+        format_results_text, format_results_trec, parse_batch_queries, write_batch_ndjson,
+    };
+    use vectdb::{Embedder, SearchService, VectorStore};
+
+    let ndjson = match output.as_str() {
+        "text" => false,
+        "ndjson" => true,
+        other => {
+            return Err(vectdb::VectDbError::InvalidInput(format!(
+                "Unknown batch output format: {}. Available: text, ndjson",
+                other
+            )));
+        }
+    };
+    if ndjson && trec_run {
+        return Err(vectdb::VectDbError::InvalidInput(
+            "--trec-run cannot be combined with --output ndjson".to_string(),
+        ));
+    }
+
+    let contents = std::fs::read_to_string(&queries_path)?;
+    let queries = parse_batch_queries(&contents);
+
+    if queries.is_empty() {
+        println!("No queries found in {:?}", queries_path);
+        return Ok(());
+    }
+
+    // Initialize services
+    let store = VectorStore::from_config(&config)?;
+    let embedder = Embedder::from_config(&config)?;
+
+    // Check the provider connection
+    if !embedder.health_check().await? {
+        print_provider_unreachable(&config);
+        return Ok(());
+    }
+
+    let service = SearchService::new(store, embedder).with_deadline(search_deadline(&config));
+    let model = config.default_model();
+
+    let mut writer = std::io::BufWriter::new(std::io::stdout());
+
+    for query in &queries {
+        let results = service
+            .search(&query.text, model, top_k, 0, threshold, None)
+            .await?;
+
+        if ndjson {
+            write_batch_ndjson(&mut writer, query, &results)?;
+        } else if trec_run {
+            write!(
+                writer,
+                "{}",
+                format_results_trec(&query.id, &results, &run_name)
+            )?;
+        } else {
+            writeln!(writer, "### Query {}: {}\n", query.id, query.text)?;
+            writeln!(writer, "{}", format_results_text(&results, None))?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Handle the serve command
 async fn handle_serve(host: String, port: u16, config: Config) -> Result<()> {
     println!("Starting VectDB web server...");
     println!("Web UI: http://{}:{}", host, port);
@@ -342,8 +1282,9 @@ async fn handle_serve(host: String, port: u16, config: Config) -> Result<()> {
 /// Handle the stats command
 async fn handle_stats(config: Config) -> Result<()> {
     use vectdb::VectorStore;
+    use vectdb::repositories::vector_store::coverage_percent;
 
-    let store = VectorStore::new(&config.database.path)?;
+    let store = VectorStore::from_config(&config)?;
     let stats = store.get_stats()?;
 
     println!("=== VectDB Statistics ===\n");
@@ -367,25 +1308,511 @@ async fn handle_stats(config: Config) -> Result<()> {
         println!("  Chunks per document: {:.2}", avg_chunks);
 
         if stats.embedding_count > 0 {
-            let coverage = (stats.embedding_count as f64 / stats.chunk_count as f64) * 100.0;
-            println!("  Embedding coverage: {:.1}%", coverage);
+            println!("  Embedding coverage: {:.1}%", stats.embedding_coverage());
+        }
+    }
+
+    let by_model = store.count_chunks_by_model()?;
+    if !by_model.is_empty() {
+        println!();
+        println!("Coverage by model:");
+        for (model, count) in &by_model {
+            println!(
+                "  {}: {} chunks ({:.1}%)",
+                model,
+                count,
+                coverage_percent(*count, stats.chunk_count)
+            );
         }
     }
 
     Ok(())
 }
 
-/// Handle the optimize command
-async fn handle_optimize(config: Config) -> Result<()> {
+/// Handle the list command
+fn handle_list(
+    limit: usize,
+    offset: usize,
+    sort: String,
+    order: String,
+    json: bool,
+    config: Config,
+) -> Result<()> {
     use vectdb::VectorStore;
+    use vectdb::repositories::{DocumentSort, SortOrder};
 
-    println!("Optimizing database...");
+    let sort: DocumentSort = sort.parse()?;
+    let order: SortOrder = order.parse()?;
+
+    let store = VectorStore::from_config(&config)?;
+    let documents = store.list_documents(limit, offset, sort, order)?;
+
+    write_document_list(&mut std::io::stdout().lock(), &documents, json)
+}
+
+/// Write listed documents as text (one line per document, then its metadata) or JSON
+fn write_document_list<W: Write>(
+    writer: &mut W,
+    documents: &[vectdb::repositories::DocumentSummary],
+    json: bool,
+) -> Result<()> {
+    use vectdb::util::format_utc;
+
+    if json {
+        let records: Vec<_> = documents
+            .iter()
+            .map(|summary| {
+                serde_json::json!({
+                    "id": summary.document.id,
+                    "source": summary.document.source,
+                    "created_at": summary.document.created_at,
+                    "chunk_count": summary.chunk_count,
+                    "metadata": summary.document.metadata,
+                })
+            })
+            .collect();
+        writeln!(writer, "{}", serde_json::to_string_pretty(&records)?)?;
+        return Ok(());
+    }
+
+    if documents.is_empty() {
+        writeln!(writer, "No documents found.")?;
+        return Ok(());
+    }
+
+    for summary in documents {
+        writeln!(
+            writer,
+            "[{}] {} ({} chunks, {})",
+            summary.document.id.unwrap_or(0),
+            summary.document.source,
+            summary.chunk_count,
+            format_utc(summary.document.created_at)
+        )?;
+
+        let mut metadata: Vec<_> = summary.document.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            writeln!(writer, "    {}: {}", key, value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the schema command
+fn handle_schema(type_name: &str) -> Result<()> {
+    use vectdb::server::{SCHEMA_TYPES, response_schema};
+
+    let schema = response_schema(type_name).ok_or_else(|| {
+        vectdb::VectDbError::InvalidInput(format!(
+            "Unknown response type: {}. Use one of: {}",
+            type_name,
+            SCHEMA_TYPES.join(", ")
+        ))
+    })?;
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+
+    Ok(())
+}
+
+/// Handle the delete command
+fn handle_delete(
+    id: Option<i64>,
+    source: Option<String>,
+    dry_run: bool,
+    config: Config,
+) -> Result<()> {
+    use vectdb::VectorStore;
+
+    let mut store = VectorStore::from_config(&config)?;
+    let preview = match (id, &source) {
+        (Some(id), _) => store.preview_delete_document(id)?,
+        (None, Some(source)) => store.preview_delete_document_by_source(source)?,
+        (None, None) => {
+            return Err(vectdb::VectDbError::InvalidInput(
+                "Pass --id or --source".to_string(),
+            ));
+        }
+    };
+
+    if preview.documents.is_empty() {
+        println!("No matching documents.");
+        return Ok(());
+    }
+
+    delete_previewed(&mut store, &preview, dry_run)
+}
+
+/// Handle the prune command
+fn handle_prune(dry_run: bool, config: Config) -> Result<()> {
+    use vectdb::VectorStore;
 
-    let store = VectorStore::new(&config.database.path)?;
+    let mut store = VectorStore::from_config(&config)?;
+    let preview = store.preview_prune()?;
+
+    if preview.documents.is_empty() {
+        println!("No incomplete documents.");
+        return Ok(());
+    }
+
+    delete_previewed(&mut store, &preview, dry_run)
+}
+
+/// Print the documents of a deletion preview, then delete them unless `dry_run`
+fn delete_previewed(
+    store: &mut vectdb::VectorStore,
+    preview: &vectdb::repositories::DeletionPreview,
+    dry_run: bool,
+) -> Result<()> {
+    for (document_id, source) in &preview.documents {
+        println!("  [{}] {}", document_id, source);
+    }
+    println!(
+        "{} document(s), {} chunk(s), {} embedding(s)",
+        preview.documents.len(),
+        preview.chunk_count,
+        preview.embedding_count
+    );
+
+    if dry_run {
+        println!("\nDry run: nothing deleted.");
+        return Ok(());
+    }
+
+    store.in_transaction(|store| {
+        for (document_id, _) in &preview.documents {
+            store.delete_document(*document_id)?;
+        }
+        Ok(())
+    })?;
+    println!("\n✓ Deleted");
+
+    Ok(())
+}
+
+/// Handle the sample command
+fn handle_sample(n: usize, seed: Option<u64>, config: Config) -> Result<()> {
+    use vectdb::VectorStore;
+
+    /// Characters of each chunk shown in the preview
+    const PREVIEW_CHARS: usize = 200;
+
+    let store = VectorStore::from_config(&config)?;
+    let samples = match seed {
+        Some(seed) => store.sample_chunks_seeded(n, seed)?,
+        None => store.sample_chunks(n)?,
+    };
+
+    if samples.is_empty() {
+        println!("No chunks in the database.");
+        return Ok(());
+    }
+
+    for (chunk, document) in &samples {
+        println!(
+            "=== {} (chunk {}) ===",
+            document.source,
+            chunk.chunk_index + 1
+        );
+        let preview: String = chunk.content.chars().take(PREVIEW_CHARS).collect();
+        if preview.len() < chunk.content.len() {
+            println!("{}...\n", preview);
+        } else {
+            println!("{}\n", preview);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the coverage command
+async fn handle_coverage(model: Option<String>, config: Config) -> Result<()> {
+    use vectdb::VectorStore;
+
+    let model = model.unwrap_or_else(|| config.default_model().to_string());
+    let store = VectorStore::from_config(&config)?;
+    let incomplete = store.documents_with_incomplete_embeddings(&model)?;
+
+    if incomplete.is_empty() {
+        println!("✓ All documents are fully embedded with '{}'", model);
+        return Ok(());
+    }
+
+    println!(
+        "Found {} document(s) with incomplete '{}' embeddings:\n",
+        incomplete.len(),
+        model
+    );
+
+    for (document, embedded, total) in &incomplete {
+        println!(
+            "  [{}] {} ({}/{} chunks embedded)",
+            document.id.unwrap_or(0),
+            document.source,
+            embedded,
+            total
+        );
+    }
+
+    Ok(())
+}
+
+/// Handle the reindex command
+async fn handle_reindex(model: String, force: bool, config: Config) -> Result<()> {
+    use vectdb::services::ingestion::IngestionOptions;
+    use vectdb::{Embedder, IngestionService, VectorStore};
+
+    let store = VectorStore::from_config(&config)?;
+    let embedder = Embedder::from_config(&config)?;
+
+    if !embedder.health_check().await? {
+        print_provider_unreachable(&config);
+        return Ok(());
+    }
+
+    if !embedder.has_model(&model).await? {
+        println!("❌ Model '{}' not found in {}", model, config.provider);
+        if config.provider == Provider::Ollama {
+            println!("\nPull the model first:");
+            println!("  ollama pull {}", model);
+        }
+        return Ok(());
+    }
+
+    let digest = embedder.model_digest(&model).await?;
+    let options = IngestionOptions::from_config(&config)?;
+    let mut service = IngestionService::new(store, embedder).with_options(options);
+
+    println!("Re-embedding chunks with '{}'...", model);
+    let result = service
+        .reindex(&model, force, |done, total| {
+            print!("\r  Reindexed {}/{} chunks", done, total);
+            let _ = std::io::stdout().flush();
+        })
+        .await?;
+    if result.reindexed > 0 {
+        println!();
+    }
+
+    // Only a run that re-embedded every chunk makes the stored embeddings match
+    // the installed weights; skipped chunks keep the staleness warning alive
+    if let Some(digest) = digest
+        && (force || (result.reindexed > 0 && result.skipped == 0))
+    {
+        VectorStore::from_config(&config)?.record_model_version(&model, &digest)?;
+    }
+
+    println!("✓ Reindexed {} chunks", result.reindexed);
+    if result.skipped > 0 {
+        println!(
+            "  Skipped {} chunks already embedded with '{}' (use --force to redo them)",
+            result.skipped, model
+        );
+    }
+    if result.retries.retries > 0 {
+        println!(
+            "  Embedding retries: {} (total backoff {:.1}s)",
+            result.retries.retries,
+            result.retries.backoff.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
+
+/// Handle the reindex-doc command
+async fn handle_reindex_doc(id: i64, model: Option<String>, config: Config) -> Result<()> {
+    use vectdb::services::ingestion::IngestionOptions;
+    use vectdb::{Embedder, IngestionService, VectorStore};
+
+    let model = model.unwrap_or_else(|| config.default_model().to_string());
+    let store = VectorStore::from_config(&config)?;
+    let embedder = Embedder::from_config(&config)?;
+
+    if !embedder.health_check().await? {
+        print_provider_unreachable(&config);
+        return Ok(());
+    }
+
+    let options = IngestionOptions::from_config(&config)?;
+    let mut service = IngestionService::new(store, embedder).with_options(options);
+    let count = service.reembed_document(id, &model).await?;
+
+    println!(
+        "✓ Re-embedded {} chunks of document {} with '{}'",
+        count, id, model
+    );
+
+    Ok(())
+}
+
+/// Handle the export command
+fn handle_export(output: std::path::PathBuf, include_vectors: bool, config: Config) -> Result<()> {
+    use vectdb::VectorStore;
+
+    let store = VectorStore::from_config(&config)?;
+    let writer = std::io::BufWriter::new(std::fs::File::create(&output)?);
+    let written = store.export_jsonl(writer, include_vectors)?;
+
+    println!(
+        "✓ Exported {} document(s) to {:?}{}",
+        written,
+        output,
+        if include_vectors {
+            " with embeddings"
+        } else {
+            ""
+        }
+    );
+
+    Ok(())
+}
+
+/// Handle the import command
+async fn handle_import(
+    input: std::path::PathBuf,
+    resume: bool,
+    batch_size: usize,
+    config: Config,
+) -> Result<()> {
+    use vectdb::VectorStore;
+    use vectdb::services::import::{ImportOptions, import_jsonl};
+
+    let mut store = VectorStore::from_config(&config)?;
+
+    if resume
+        && let Some(line) = store.get_import_checkpoint(&input.canonicalize()?.to_string_lossy())?
+    {
+        println!("Resuming from line {}...", line + 1);
+    }
+
+    let summary = import_jsonl(&mut store, &input, &ImportOptions { resume, batch_size })?;
+
+    println!("✓ Import complete");
+    println!("  Documents imported: {}", summary.imported);
+    println!("  Duplicates skipped: {}", summary.skipped);
+
+    Ok(())
+}
+
+/// Handle the merge command
+async fn handle_merge(other: std::path::PathBuf, config: Config) -> Result<()> {
+    use vectdb::VectorStore;
+    use vectdb::services::import::merge_store;
+
+    if !other.is_file() {
+        return Err(vectdb::VectDbError::InvalidInput(format!(
+            "Database file does not exist: {:?}",
+            other
+        )));
+    }
+    if config.database.path.exists()
+        && other.canonicalize()? == config.database.path.canonicalize()?
+    {
+        return Err(vectdb::VectDbError::InvalidInput(
+            "Cannot merge a database into itself".to_string(),
+        ));
+    }
+
+    let mut store = VectorStore::from_config(&config)?;
+    let other_store = VectorStore::open_read_only(&other)?;
+
+    let summary = merge_store(&mut store, &other_store)?;
+
+    println!("✓ Merge complete");
+    println!("  Documents added:    {}", summary.added);
+    println!("  Duplicates skipped: {}", summary.skipped);
+
+    Ok(())
+}
+
+/// Handle the recompress command
+async fn handle_recompress(
+    target_precision: String,
+    batch_size: usize,
+    config: Config,
+) -> Result<()> {
+    use vectdb::VectorStore;
+    use vectdb::repositories::StoragePrecision;
+
+    let precision: StoragePrecision = target_precision.parse()?;
+    let mut store = VectorStore::from_config(&config)?;
+
+    let size_before = store.get_stats()?.db_size_bytes;
+
+    println!("Re-encoding embeddings as {}...", target_precision);
+    let rewritten = store.recompress_embeddings(precision, batch_size)?;
 
     println!("  Running VACUUM...");
     store.vacuum()?;
 
+    let size_after = store.get_stats()?.db_size_bytes;
+
+    println!("✓ Re-encoded {} embeddings", rewritten);
+    println!(
+        "  Database size: {} KB -> {} KB",
+        size_before / 1024,
+        size_after / 1024
+    );
+
+    Ok(())
+}
+
+/// Validate a `--dimensions` truncation target
+fn check_dimensions(dimensions: usize) -> Result<usize> {
+    if dimensions == 0 {
+        return Err(vectdb::VectDbError::InvalidInput(
+            "--dimensions must be greater than 0".to_string(),
+        ));
+    }
+    Ok(dimensions)
+}
+
+/// Handle the cache command
+fn handle_cache(action: CacheAction, config: Config) -> Result<()> {
+    use vectdb::VectorStore;
+
+    let mut store = VectorStore::from_config(&config)?;
+    match action {
+        CacheAction::Clear => {
+            let removed = store.clear_embedding_cache()?;
+            println!("Removed {} cached embedding(s)", removed);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the optimize command
+async fn handle_optimize(incremental: Option<u32>, config: Config) -> Result<()> {
+    use vectdb::VectorStore;
+
+    println!("Optimizing database...");
+
+    let store = VectorStore::from_config(&config)?;
+
+    match incremental {
+        Some(pages) => {
+            if !store.is_incremental_vacuum_enabled()? {
+                println!("  Enabling incremental auto_vacuum (one-time full VACUUM)...");
+                store.enable_incremental_vacuum()?;
+            }
+
+            println!("  Running incremental vacuum...");
+            let reclaimed = store.incremental_vacuum(pages)?;
+            println!(
+                "  Reclaimed {} pages ({} free pages left)",
+                reclaimed,
+                store.freelist_count()?
+            );
+        }
+        None => {
+            println!("  Running VACUUM...");
+            store.vacuum()?;
+        }
+    }
+
     println!("  Running ANALYZE...");
     store.analyze()?;
 
@@ -394,16 +1821,98 @@ async fn handle_optimize(config: Config) -> Result<()> {
     Ok(())
 }
 
+/// Handle the regress command
+async fn handle_regress(
+    golden_path: std::path::PathBuf,
+    top_k: usize,
+    config: Config,
+) -> Result<()> {
+    use vectdb::services::search::parse_golden_queries;
+    use vectdb::{Embedder, SearchService, VectorStore};
+
+    let contents = std::fs::read_to_string(&golden_path)?;
+    let golden = parse_golden_queries(&contents)?;
+
+    if golden.is_empty() {
+        println!("No golden queries found in {:?}", golden_path);
+        return Ok(());
+    }
+
+    let store = VectorStore::from_config(&config)?;
+    let embedder = Embedder::from_config(&config)?;
+
+    if !embedder.health_check().await? {
+        return Err(vectdb::VectDbError::OllamaUnavailable(format!(
+            "Cannot connect to {} at {}",
+            config.provider,
+            config.provider_url()
+        )));
+    }
+
+    let service = SearchService::new(store, embedder).with_deadline(search_deadline(&config));
+    let outcomes = service
+        .run_regression(&golden, config.default_model(), top_k)
+        .await?;
+
+    for outcome in &outcomes {
+        match outcome.rank {
+            Some(rank) => println!(
+                "✓ PASS  {} -> {} (rank {})",
+                outcome.query, outcome.expected_source, rank
+            ),
+            None => println!(
+                "✗ FAIL  {} -> {} (not in top {})",
+                outcome.query, outcome.expected_source, top_k
+            ),
+        }
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    println!(
+        "\n{} passed, {} failed ({} queries)",
+        outcomes.len() - failed,
+        failed,
+        outcomes.len()
+    );
+
+    if failed > 0 {
+        return Err(vectdb::VectDbError::SearchFailed(format!(
+            "{} of {} golden queries regressed",
+            failed,
+            outcomes.len()
+        )));
+    }
+
+    Ok(())
+}
+
 /// Handle the models command
-async fn handle_models(config: Config) -> Result<()> {
+async fn handle_models(config: Config, format: &str) -> Result<()> {
     use vectdb::OllamaClient;
+    use vectdb::clients::ollama::TimestampFormat;
+
+    let json = match format {
+        "text" => false,
+        "json" => true,
+        other => {
+            return Err(vectdb::VectDbError::InvalidInput(format!(
+                "Unknown output format: {}. Available: text, json",
+                other
+            )));
+        }
+    };
+    let timestamp_format: TimestampFormat = config.ollama.timestamp_format.parse()?;
 
-    println!("Connecting to Ollama at {}...\n", config.ollama.base_url);
+    let client = OllamaClient::from_config(&config.ollama)?;
 
-    let client = OllamaClient::new(
-        config.ollama.base_url.clone(),
-        config.ollama.timeout_seconds,
-    )?;
+    if json {
+        // Raw timestamps, nothing but the JSON document on stdout
+        let models = client.list_models().await?;
+        println!("{}", serde_json::to_string_pretty(&models)?);
+        return Ok(());
+    }
+
+    println!("Connecting to Ollama at {}...\n", config.ollama.base_url);
 
     // Check if Ollama is available
     if !client.health_check().await? {
@@ -431,16 +1940,23 @@ async fn handle_models(config: Config) -> Result<()> {
 
     println!("Available Models ({}):\n", models.len());
 
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
     for model in &models {
         let size_mb = model.size as f64 / (1024.0 * 1024.0);
         println!("  • {}", model.name);
         println!("    Size: {:.1} MB", size_mb);
-        println!("    Modified: {}", model.modified_at);
+        println!(
+            "    Modified: {}",
+            model.modified_display(timestamp_format, now)
+        );
         println!();
     }
 
     // Show recommended models
-    let recommended = vec!["nomic-embed-text", "all-minilm", "mxbai-embed-large"];
+    let recommended = vectdb::clients::ollama::RECOMMENDED_EMBEDDING_MODELS;
     let has_recommended: Vec<_> = models
         .iter()
         .filter(|m| recommended.iter().any(|r| m.name.contains(r)))
@@ -457,3 +1973,499 @@ async fn handle_models(config: Config) -> Result<()> {
 
     Ok(())
 }
+
+/// Handle the model-info command
+async fn handle_model_info(model: Option<String>, config: Config) -> Result<()> {
+    use vectdb::OllamaClient;
+
+    let model = model.unwrap_or_else(|| config.ollama.default_model.clone());
+    let client = OllamaClient::from_config(&config.ollama)?;
+
+    if !client.health_check().await? {
+        println!("❌ Cannot connect to Ollama at {}", config.ollama.base_url);
+        println!("\nMake sure Ollama is running:");
+        println!("  ollama serve");
+        return Ok(());
+    }
+
+    let probe = client.probe_embedding(&model).await?;
+
+    println!("Model:     {}", model);
+    println!("Dimension: {}", probe.dimension);
+    println!(
+        "L2 norm:   {:.4}{}",
+        probe.norm,
+        if probe.is_normalized() {
+            " (normalized)"
+        } else {
+            " (not normalized)"
+        }
+    );
+    println!("Latency:   {} ms", probe.latency.as_millis());
+
+    if let Some(warning) = probe.warning(&model) {
+        println!("\n⚠ {}", warning);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_files_size_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("empty.txt"), "").unwrap();
+        std::fs::write(dir.path().join("small.txt"), "a".repeat(10)).unwrap();
+        std::fs::write(dir.path().join("medium.txt"), "a".repeat(100)).unwrap();
+        std::fs::write(dir.path().join("large.txt"), "a".repeat(1000)).unwrap();
+
+        let collected = collect_files(
+            dir.path(),
+            &CollectOptions {
+                min_file_size: Some(1),
+                max_file_size: Some(100),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let mut names: Vec<String> = collected
+            .files
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+
+        assert_eq!(names, vec!["medium.txt", "small.txt"]);
+        assert_eq!(collected.skipped_by_size, 2);
+    }
+
+    #[test]
+    fn test_collect_files_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        // (name, bytes, minutes after base): sizes and modification times disagree with the names
+        for (name, size, minutes) in [("b.txt", 300, 0), ("a.txt", 200, 20), ("c.txt", 100, 10)] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, "a".repeat(size)).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(base + std::time::Duration::from_secs(minutes * 60))
+                .unwrap();
+        }
+
+        let names = |order: &str| -> Vec<String> {
+            collect_files(
+                dir.path(),
+                &CollectOptions {
+                    order: Some(order.parse().unwrap()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .files
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+        };
+
+        assert_eq!(names("name"), ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(names("size-asc"), ["c.txt", "a.txt", "b.txt"]);
+        assert_eq!(names("size-desc"), ["b.txt", "a.txt", "c.txt"]);
+        assert_eq!(names("mtime"), ["b.txt", "c.txt", "a.txt"]);
+        assert!("newest".parse::<FileOrder>().is_err());
+    }
+
+    #[test]
+    fn test_collect_files_code_is_opt_in() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "notes").unwrap();
+        std::fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+
+        let names = |include_code: bool| -> Vec<String> {
+            let mut names: Vec<String> = collect_files(
+                dir.path(),
+                &CollectOptions {
+                    include_code,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .files
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(false), ["notes.md"]);
+        assert_eq!(names(true), ["main.rs", "notes.md"]);
+
+        // A code file named directly is always collected
+        let single =
+            collect_files(&dir.path().join("main.rs"), &CollectOptions::default()).unwrap();
+        assert_eq!(single.files.len(), 1);
+    }
+
+    /// A directory tree for the glob tests
+    fn glob_fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "README.md",
+            "notes.txt",
+            "docs/guide.md",
+            "docs/api/index.md",
+            "docs/node_modules/pkg/readme.md",
+        ] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "content").unwrap();
+        }
+        dir
+    }
+
+    /// Sorted paths, relative to `dir`, collected with the given globs
+    fn collect_with_globs(
+        dir: &std::path::Path,
+        include: &[&str],
+        exclude: &[&str],
+    ) -> Vec<String> {
+        let globs = |patterns: &[&str]| {
+            let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+            build_glob_set(&patterns).unwrap()
+        };
+        let mut names: Vec<String> = collect_files(
+            dir,
+            &CollectOptions {
+                recursive: true,
+                include: globs(include),
+                exclude: globs(exclude),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .files
+        .iter()
+        .map(|p| {
+            let relative = p.strip_prefix(dir).unwrap();
+            relative.to_string_lossy().replace('\\', "/")
+        })
+        .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_collect_files_include_globs() {
+        let dir = glob_fixture();
+
+        // `*` does not cross directories, `**` does
+        assert_eq!(
+            collect_with_globs(dir.path(), &["*.md"], &[]),
+            ["README.md"]
+        );
+        assert_eq!(
+            collect_with_globs(dir.path(), &["docs/**/*.md", "*.txt"], &[]),
+            [
+                "docs/api/index.md",
+                "docs/guide.md",
+                "docs/node_modules/pkg/readme.md",
+                "notes.txt"
+            ]
+        );
+
+        assert!(build_glob_set(&["docs/[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_collect_files_exclude_globs() {
+        let dir = glob_fixture();
+
+        assert_eq!(
+            collect_with_globs(dir.path(), &[], &["**/node_modules/**", "*.txt"]),
+            ["README.md", "docs/api/index.md", "docs/guide.md"]
+        );
+    }
+
+    #[test]
+    fn test_collect_files_exclude_wins_over_include() {
+        let dir = glob_fixture();
+
+        assert_eq!(
+            collect_with_globs(
+                dir.path(),
+                &["docs/**/*.md"],
+                &["**/node_modules/**", "docs/api/**"]
+            ),
+            ["docs/guide.md"]
+        );
+    }
+
+    #[test]
+    fn test_collect_files_skips_hidden_and_gitignored() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "README.md",
+            ".notes.md",
+            ".git/description.txt",
+            ".git/logs/HEAD.txt",
+            "docs/guide.md",
+            "vendor/lib.md",
+        ] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "content").unwrap();
+        }
+        std::fs::write(dir.path().join(".gitignore"), "vendor/\n").unwrap();
+
+        let names = |recursive: bool, hidden: bool, respect_gitignore: bool| -> Vec<String> {
+            let mut names: Vec<String> = collect_files(
+                dir.path(),
+                &CollectOptions {
+                    recursive,
+                    hidden,
+                    respect_gitignore,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .files
+            .iter()
+            .map(|p| {
+                let relative = p.strip_prefix(dir.path()).unwrap();
+                relative.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            names(true, false, false),
+            ["README.md", "docs/guide.md", "vendor/lib.md"]
+        );
+        assert_eq!(names(false, false, false), ["README.md"]);
+        assert_eq!(
+            names(true, true, false),
+            [
+                ".git/description.txt",
+                ".git/logs/HEAD.txt",
+                ".notes.md",
+                "README.md",
+                "docs/guide.md",
+                "vendor/lib.md"
+            ]
+        );
+        assert_eq!(names(true, false, true), ["README.md", "docs/guide.md"]);
+        assert_eq!(names(false, true, true), [".notes.md", "README.md"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_files_symlinks() {
+        let outside = tempfile::tempdir().unwrap();
+        let target = outside.path().join("secret.txt");
+        std::fs::write(&target, "outside the source tree").unwrap();
+
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(source.path().join("inside.txt"), "inside").unwrap();
+        std::os::unix::fs::symlink(&target, source.path().join("link.txt")).unwrap();
+
+        for recursive in [false, true] {
+            let skipped = collect_files(
+                source.path(),
+                &CollectOptions {
+                    recursive,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(skipped.files.len(), 1);
+            assert!(skipped.files[0].ends_with("inside.txt"));
+
+            let followed = collect_files(
+                source.path(),
+                &CollectOptions {
+                    recursive,
+                    follow_symlinks: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(followed.files.len(), 2);
+        }
+    }
+
+    fn search_args(flags: &[&str]) -> SearchArgs {
+        let argv = ["vectdb", "search", "query"].iter().chain(flags);
+        match Cli::parse_from(argv).command {
+            Commands::Search(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_search_falls_back_to_configured_limits() {
+        let mut config = Config::default();
+        config.search.default_top_k = 3;
+        config.search.similarity_threshold = 0.4;
+
+        assert_eq!(search_limits(&search_args(&[]), &config), (3, 0.4));
+        assert_eq!(
+            search_limits(&search_args(&["-k", "7", "--threshold", "0.6"]), &config),
+            (7, 0.6)
+        );
+    }
+
+    #[test]
+    fn test_relative_threshold_replaces_configured_threshold() {
+        let mut config = Config::default();
+        config.search.similarity_threshold = 0.7;
+
+        let (_, threshold) = search_limits(&search_args(&["--relative-threshold", "0.9"]), &config);
+        assert_eq!(threshold, 0.0);
+    }
+
+    #[test]
+    fn test_document_list_output() {
+        use vectdb::domain::Document;
+        use vectdb::repositories::DocumentSummary;
+
+        let mut document = Document::new("notes.md".to_string(), "Notes");
+        document.id = Some(7);
+        document.created_at = 1_705_343_400;
+        document
+            .metadata
+            .insert("lang".to_string(), "en".to_string());
+        document
+            .metadata
+            .insert("author".to_string(), "ann".to_string());
+        let documents = vec![DocumentSummary {
+            document,
+            chunk_count: 3,
+        }];
+
+        let mut text = Vec::new();
+        write_document_list(&mut text, &documents, false).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "[7] notes.md (3 chunks, 2024-01-15 18:30 UTC)\n    author: ann\n    lang: en\n"
+        );
+
+        let mut json = Vec::new();
+        write_document_list(&mut json, &documents, true).unwrap();
+        let records: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(records[0]["source"], "notes.md");
+        assert_eq!(records[0]["chunk_count"], 3);
+
+        let mut empty = Vec::new();
+        write_document_list(&mut empty, &[], false).unwrap();
+        assert_eq!(String::from_utf8(empty).unwrap(), "No documents found.\n");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_piped_stdin_dedups_by_hash() {
+        use vectdb::domain::ChunkStrategy;
+        use vectdb::{IngestionService, OllamaClient, VectorStore};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .mount(&server)
+            .await;
+
+        let piped: &[u8] = b"Text piped in by a shell pipeline.";
+        assert!(
+            read_stdin_source(std::path::Path::new("docs"), piped)
+                .unwrap()
+                .is_none()
+        );
+        let content = read_stdin_source(std::path::Path::new("-"), piped)
+            .unwrap()
+            .unwrap();
+        assert_eq!(content, "Text piped in by a shell pipeline.");
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(VectorStore::in_memory().unwrap(), ollama);
+        let strategy = ChunkStrategy::Semantic { max_size: 100 };
+        let first = service
+            .ingest_text(STDIN_SOURCE, &content, "model", strategy)
+            .await
+            .unwrap();
+        assert!(!first.skipped);
+        assert_eq!(
+            service
+                .store()
+                .get_document(first.document_id)
+                .unwrap()
+                .unwrap()
+                .source,
+            "stdin"
+        );
+
+        let second = service
+            .ingest_text(STDIN_SOURCE, &content, "model", strategy)
+            .await
+            .unwrap();
+        assert!(second.skipped);
+        assert_eq!(service.store().count_documents().unwrap(), 1);
+    }
+
+    fn ingest_args(flags: &[&str]) -> IngestArgs {
+        let argv = ["vectdb", "ingest", "docs", "--dry-run"]
+            .iter()
+            .chain(flags);
+        match Cli::parse_from(argv).command {
+            Commands::Ingest(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_dry_run_strategy_follows_config_and_flags() {
+        use vectdb::domain::ChunkStrategy;
+
+        let mut config = Config::default();
+        config.chunking.strategy = "token".to_string();
+        config.chunking.max_chunk_size = 300;
+        config.chunking.overlap_size = 30;
+
+        let strategy = ingest_strategy(&ingest_args(&[]), &config).unwrap();
+        assert!(matches!(
+            strategy,
+            ChunkStrategy::TokenBased {
+                max_tokens: 300,
+                overlap_tokens: 30
+            }
+        ));
+
+        let strategy = ingest_strategy(&ingest_args(&["--overlap", "10"]), &config).unwrap();
+        assert!(matches!(
+            strategy,
+            ChunkStrategy::TokenBased {
+                max_tokens: 300,
+                overlap_tokens: 10
+            }
+        ));
+
+        let args = ingest_args(&["--strategy", "semantic", "--chunk-size", "120"]);
+        let strategy = ingest_strategy(&args, &config).unwrap();
+        assert!(matches!(
+            strategy,
+            ChunkStrategy::Semantic { max_size: 120 }
+        ));
+
+        assert!(ingest_strategy(&ingest_args(&["--strategy", "words"]), &config).is_err());
+    }
+}
@@ -2,4 +2,7 @@
 
 pub mod vector_store;
 
-pub use vector_store::VectorStore;
+pub use vector_store::{
+    DeletionPreview, DocumentSort, DocumentSummary, SearchHistoryEntry, SortOrder,
+    StoragePrecision, VectorStore,
+};
@@ -2,15 +2,35 @@
 //!
 //! Provides database operations for documents, chunks, and embeddings using SQLite.
 
-use crate::domain::{Chunk, Document, Embedding, SearchResult};
-use crate::error::Result;
-use rusqlite::{Connection, OptionalExtension, params};
+use crate::config::Config;
+use crate::domain::{
+    Chunk, ChunkRecord, Document, DocumentRecord, EmbeddedChunk, Embedding, EmbeddingRecord,
+    NonFiniteEmbeddings, SearchResult,
+};
+use crate::error::{Result, VectDbError};
+use half::f16;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 use std::path::Path;
-use tracing::{debug, info};
+use std::sync::Once;
+use std::time::Instant;
+use tracing::{debug, info, warn};
 
 /// Vector Store manages all database operations
 pub struct VectorStore {
     conn: Connection,
+    high_precision_scoring: bool,
+    normalize_embeddings: bool,
+    non_finite_embeddings: NonFiniteEmbeddings,
+    /// Embeddings scored by the most recent similarity search
+    rows_scanned: Cell<usize>,
+    /// Find `search_similar` candidates with the sqlite-vec index
+    use_ann: bool,
+    /// Dimensions whose `vec0` table is known to exist
+    vec_tables: RefCell<HashSet<usize>>,
 }
 
 impl VectorStore {
@@ -18,6 +38,7 @@ impl VectorStore {
     pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
         info!("Opening database at: {:?}", db_path.as_ref());
 
+        register_sqlite_vec();
         let conn = Connection::open(db_path)?;
 
         // Enable WAL mode for better concurrency
@@ -25,25 +46,110 @@ impl VectorStore {
         conn.pragma_update(None, "synchronous", "NORMAL")?;
         conn.pragma_update(None, "foreign_keys", true)?;
 
-        let mut store = Self { conn };
+        let mut store = Self {
+            conn,
+            high_precision_scoring: false,
+            normalize_embeddings: false,
+            non_finite_embeddings: NonFiniteEmbeddings::default(),
+            rows_scanned: Cell::new(0),
+            use_ann: true,
+            vec_tables: RefCell::default(),
+        };
         store.init_schema()?;
 
         Ok(store)
     }
 
+    /// Open the configured database with the configured storage and scoring options
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self::new(&config.database.path)?
+            .with_normalized_embeddings(config.database.normalize_embeddings)
+            .with_non_finite_embeddings(config.database.non_finite_embeddings)
+            .with_high_precision_scoring(config.search.high_precision_scoring)
+            .with_ann(config.database.use_ann))
+    }
+
+    /// Open an existing database without write access, e.g. a read replica
+    ///
+    /// The schema is neither created nor migrated, so the file must have been
+    /// written by this version (a copy or `VACUUM INTO` of the primary).
+    pub fn open_read_only<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        info!("Opening read-only database at: {:?}", db_path.as_ref());
+
+        register_sqlite_vec();
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+
+        Ok(Self {
+            conn,
+            high_precision_scoring: false,
+            normalize_embeddings: false,
+            non_finite_embeddings: NonFiniteEmbeddings::default(),
+            rows_scanned: Cell::new(0),
+            use_ann: true,
+            vec_tables: RefCell::default(),
+        })
+    }
+
     /// Create an in-memory database (useful for testing)
     pub fn in_memory() -> Result<Self> {
         info!("Creating in-memory database");
 
+        register_sqlite_vec();
         let conn = Connection::open_in_memory()?;
         conn.pragma_update(None, "foreign_keys", true)?;
 
-        let mut store = Self { conn };
+        let mut store = Self {
+            conn,
+            high_precision_scoring: false,
+            normalize_embeddings: false,
+            non_finite_embeddings: NonFiniteEmbeddings::default(),
+            rows_scanned: Cell::new(0),
+            use_ann: true,
+            vec_tables: RefCell::default(),
+        };
         store.init_schema()?;
 
         Ok(store)
     }
 
+    /// Accumulate search similarities in f64 instead of f32
+    ///
+    /// Slower, but avoids rounding that can reorder near-tied results for
+    /// high-dimensional vectors.
+    pub fn with_high_precision_scoring(mut self, enabled: bool) -> Self {
+        self.high_precision_scoring = enabled;
+        self
+    }
+
+    /// Store embeddings scaled to unit length
+    ///
+    /// Cosine similarity against a normalized vector is a plain dot product,
+    /// so searches skip computing each stored vector's magnitude. Rows are
+    /// flagged individually, so databases mixing both forms still score
+    /// correctly. Vectors read back are the normalized form.
+    pub fn with_normalized_embeddings(mut self, enabled: bool) -> Self {
+        self.normalize_embeddings = enabled;
+        self
+    }
+
+    /// Find similarity search candidates with the sqlite-vec index (on by default)
+    ///
+    /// Disabled, `search_similar` scores every embedding of the model in Rust,
+    /// which is slower but useful as a reference for the index.
+    pub fn with_ann(mut self, enabled: bool) -> Self {
+        self.use_ann = enabled;
+        self
+    }
+
+    /// How embeddings containing NaN or infinite values are stored
+    pub fn with_non_finite_embeddings(mut self, policy: NonFiniteEmbeddings) -> Self {
+        self.non_finite_embeddings = policy;
+        self
+    }
+
     /// Initialize the database schema
     fn init_schema(&mut self) -> Result<()> {
         info!("Initializing database schema");
@@ -55,10 +161,18 @@ impl VectorStore {
                 source TEXT NOT NULL,
                 content_hash TEXT UNIQUE NOT NULL,
                 metadata TEXT,
-                created_at INTEGER NOT NULL
+                created_at INTEGER NOT NULL,
+                complete INTEGER NOT NULL DEFAULT 1
             )",
             [],
         )?;
+        // Databases created before documents were written in several transactions
+        if !self.has_column("documents", "complete")? {
+            self.conn.execute(
+                "ALTER TABLE documents ADD COLUMN complete INTEGER NOT NULL DEFAULT 1",
+                [],
+            )?;
+        }
 
         // Create chunks table
         self.conn.execute(
@@ -81,36 +195,281 @@ impl VectorStore {
                 model TEXT NOT NULL,
                 vector BLOB NOT NULL,
                 dimension INTEGER NOT NULL,
+                normalized INTEGER NOT NULL DEFAULT 0,
                 FOREIGN KEY (chunk_id) REFERENCES chunks(id) ON DELETE CASCADE
             )",
             [],
         )?;
+        // Databases created before vectors could be stored normalized
+        if !self.has_column("embeddings", "normalized")? {
+            self.conn.execute(
+                "ALTER TABLE embeddings ADD COLUMN normalized INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
 
-        // Create indices
+        // Create embedding cache table (keyed by sha256 of model + text)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS embedding_cache (
+                key TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                dimension INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Create import checkpoint table (last committed line per input file)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS import_checkpoints (
+                input TEXT PRIMARY KEY,
+                line_offset INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Create model dimension table (expected embedding size per model)
+        self.init_model_dimensions()?;
+
+        // Index every known embedding size with sqlite-vec
+        for dimension in self.model_dimensions()?.into_iter().map(|(_, d)| d) {
+            self.ensure_vec_table(dimension)?;
+        }
+
+        // Create search history table (recent queries, newest has the highest id)
         self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_chunks_document ON chunks(document_id)",
+            "CREATE TABLE IF NOT EXISTS search_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                query TEXT NOT NULL,
+                result_count INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
             [],
         )?;
 
+        // Create model version table (weights digest embeddings were made with)
         self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_embeddings_model ON embeddings(model)",
+            "CREATE TABLE IF NOT EXISTS model_versions (
+                model TEXT PRIMARY KEY,
+                digest TEXT NOT NULL,
+                recorded_at INTEGER NOT NULL
+            )",
             [],
         )?;
 
+        // Create indices
+        self.create_secondary_indices()?;
+
+        self.init_fts()?;
+
         info!("Schema initialized successfully");
         Ok(())
     }
 
+    /// Create the sqlite-vec table for `dimension` if missing, indexing existing embeddings
+    ///
+    /// Rows follow deletions of their embeddings through triggers; inserts and
+    /// updates are written by `upsert_embedding`.
+    fn ensure_vec_table(&mut self, dimension: usize) -> Result<()> {
+        if self.has_vec_table(dimension)? {
+            return Ok(());
+        }
+
+        let table = vec_table_name(dimension);
+        info!("Creating sqlite-vec index {}", table);
+
+        // A savepoint, unlike `in_transaction`, also nests inside a caller's transaction
+        let savepoint = self.conn.savepoint()?;
+        savepoint.execute_batch(&format!(
+            "CREATE VIRTUAL TABLE {table} USING vec0(
+                 chunk_id INTEGER PRIMARY KEY,
+                 model TEXT PARTITION KEY,
+                 embedding FLOAT[{dimension}] distance_metric=cosine
+             );
+             CREATE TRIGGER {table}_delete AFTER DELETE ON embeddings
+             WHEN old.dimension = {dimension} BEGIN
+                 DELETE FROM {table} WHERE chunk_id = old.chunk_id;
+             END;
+             CREATE TRIGGER {table}_update AFTER UPDATE OF dimension ON embeddings
+             WHEN old.dimension = {dimension} AND new.dimension != {dimension} BEGIN
+                 DELETE FROM {table} WHERE chunk_id = old.chunk_id;
+             END;"
+        ))?;
+        {
+            let mut select = savepoint
+                .prepare("SELECT chunk_id, model, vector FROM embeddings WHERE dimension = ?1")?;
+            let mut insert = savepoint.prepare(&format!(
+                "INSERT INTO {} (chunk_id, model, embedding) VALUES (?1, ?2, ?3)",
+                table
+            ))?;
+            let mut rows = select.query(params![dimension])?;
+            while let Some(row) = rows.next()? {
+                let chunk_id: i64 = row.get(0)?;
+                let model: String = row.get(1)?;
+                let bytes: Vec<u8> = row.get(2)?;
+                let vector = bytes_to_vector(&bytes, dimension);
+                insert.execute(params![chunk_id, model, vector_to_bytes(&vector)])?;
+            }
+        }
+        savepoint.commit()?;
+
+        self.vec_tables.borrow_mut().insert(dimension);
+        Ok(())
+    }
+
+    /// Whether the sqlite-vec table for `dimension` exists
+    fn has_vec_table(&self, dimension: usize) -> Result<bool> {
+        if self.vec_tables.borrow().contains(&dimension) {
+            return Ok(true);
+        }
+
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            params![vec_table_name(dimension)],
+            |row| row.get(0),
+        )?;
+        if exists {
+            self.vec_tables.borrow_mut().insert(dimension);
+        }
+
+        Ok(exists)
+    }
+
+    /// Whether `table` has a column named `column`
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(names.iter().any(|name| name == column))
+    }
+
+    /// Create the lookup indices that bulk mode drops
+    fn create_secondary_indices(&self) -> Result<()> {
+        for (name, definition) in SECONDARY_INDICES {
+            self.conn.execute(
+                &format!("CREATE INDEX IF NOT EXISTS {} ON {}", name, definition),
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Create the model dimension table and the triggers that forget unused models
+    ///
+    /// Searches check query vectors against this table rather than scanning
+    /// the embeddings, so the embeddings are only read to backfill a database
+    /// created before the table or its triggers existed.
+    fn init_model_dimensions(&mut self) -> Result<()> {
+        let exists = |store: &Self, kind: &str, name: &str| -> Result<bool> {
+            Ok(store.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = ?1 AND name = ?2)",
+                params![kind, name],
+                |row| row.get(0),
+            )?)
+        };
+        let table_existed = exists(self, "table", "model_dimensions")?;
+        let triggers_existed = exists(self, "trigger", "model_dimensions_delete")?;
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_dimensions (
+                model TEXT PRIMARY KEY,
+                dimension INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        if !table_existed {
+            // Prefer the most common dimension of a model
+            self.conn.execute(
+                "INSERT OR IGNORE INTO model_dimensions (model, dimension)
+                 SELECT model, dimension FROM embeddings
+                 GROUP BY model, dimension ORDER BY COUNT(*) DESC",
+                [],
+            )?;
+        }
+
+        // A model's dimension is forgotten once its last embedding is gone
+        if !triggers_existed {
+            self.conn.execute(
+                "DELETE FROM model_dimensions
+                 WHERE NOT EXISTS (SELECT 1 FROM embeddings WHERE model = model_dimensions.model)",
+                [],
+            )?;
+        }
+        self.conn.execute_batch(
+            "CREATE TRIGGER IF NOT EXISTS model_dimensions_delete AFTER DELETE ON embeddings
+             WHEN NOT EXISTS (SELECT 1 FROM embeddings WHERE model = old.model) BEGIN
+                 DELETE FROM model_dimensions WHERE model = old.model;
+             END;
+             CREATE TRIGGER IF NOT EXISTS model_dimensions_update AFTER UPDATE OF model ON embeddings
+             WHEN NOT EXISTS (SELECT 1 FROM embeddings WHERE model = old.model) BEGIN
+                 DELETE FROM model_dimensions WHERE model = old.model;
+             END;",
+        )?;
+
+        Ok(())
+    }
+
+    /// Create the FTS5 index over chunk content and the triggers that keep it in sync
+    ///
+    /// A database created before the index existed gets it backfilled once.
+    fn init_fts(&mut self) -> Result<()> {
+        let existed: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'chunks_fts')",
+            [],
+            |row| row.get(0),
+        )?;
+
+        self.conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts
+                 USING fts5(content, content='chunks', content_rowid='id');
+             CREATE TRIGGER IF NOT EXISTS chunks_fts_insert AFTER INSERT ON chunks BEGIN
+                 INSERT INTO chunks_fts(rowid, content) VALUES (new.id, new.content);
+             END;
+             CREATE TRIGGER IF NOT EXISTS chunks_fts_delete AFTER DELETE ON chunks BEGIN
+                 INSERT INTO chunks_fts(chunks_fts, rowid, content)
+                     VALUES ('delete', old.id, old.content);
+             END;
+             CREATE TRIGGER IF NOT EXISTS chunks_fts_update AFTER UPDATE OF content ON chunks BEGIN
+                 INSERT INTO chunks_fts(chunks_fts, rowid, content)
+                     VALUES ('delete', old.id, old.content);
+                 INSERT INTO chunks_fts(rowid, content) VALUES (new.id, new.content);
+             END;",
+        )?;
+
+        if !existed {
+            self.conn
+                .execute("INSERT INTO chunks_fts(chunks_fts) VALUES ('rebuild')", [])?;
+        }
+        Ok(())
+    }
+
     // ============================================================================
     // Document Operations
     // ============================================================================
 
     /// Insert a new document
+    ///
+    /// An incomplete copy of the same content, left behind by an interrupted
+    /// ingest, is removed first.
     pub fn insert_document(&mut self, doc: &Document) -> Result<i64> {
         debug!("Inserting document: {}", doc.source);
 
         let metadata_json = serde_json::to_string(&doc.metadata)?;
 
+        let removed = self.conn.execute(
+            "DELETE FROM documents WHERE content_hash = ?1 AND complete = 0",
+            params![&doc.content_hash],
+        )?;
+        if removed > 0 {
+            info!("Replacing incompletely ingested document: {}", doc.source);
+        }
+
         self.conn.execute(
             "INSERT INTO documents (source, content_hash, metadata, created_at)
              VALUES (?1, ?2, ?3, ?4)",
@@ -164,7 +523,7 @@ impl VectorStore {
             .conn
             .query_row(
                 "SELECT id, source, content_hash, metadata, created_at FROM documents
-                 WHERE content_hash = ?1",
+                 WHERE content_hash = ?1 AND complete = 1",
                 params![content_hash],
                 |row| {
                     let metadata_json: String = row.get(3)?;
@@ -184,6 +543,121 @@ impl VectorStore {
         Ok(result)
     }
 
+    /// List documents with their chunk counts, sorted and paginated
+    pub fn list_documents(
+        &self,
+        limit: usize,
+        offset: usize,
+        sort: DocumentSort,
+        order: SortOrder,
+    ) -> Result<Vec<DocumentSummary>> {
+        debug!(
+            "Listing documents (limit={}, offset={}, sort={:?}, order={:?})",
+            limit, offset, sort, order
+        );
+
+        let column = match sort {
+            DocumentSort::Date => "d.created_at",
+            DocumentSort::Source => "d.source",
+            DocumentSort::Chunks => "chunk_count",
+        };
+        let direction = match order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT d.id, d.source, d.content_hash, d.metadata, d.created_at,
+                    (SELECT COUNT(*) FROM chunks c WHERE c.document_id = d.id) AS chunk_count
+             FROM documents d
+             WHERE d.complete = 1
+             ORDER BY {column} {direction}, d.id {direction}
+             LIMIT ?1 OFFSET ?2"
+        ))?;
+
+        let documents = stmt
+            .query_map(params![limit as i64, offset as i64], |row| {
+                Ok(DocumentSummary {
+                    document: document_from_row(row, 0)?,
+                    chunk_count: row.get(5)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(documents)
+    }
+
+    /// Ids of all fully ingested documents, in insertion order
+    pub fn document_ids(&self) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM documents WHERE complete = 1 ORDER BY id")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(ids)
+    }
+
+    /// A document with its chunks and stored embeddings, detached from row ids
+    ///
+    /// The counterpart of [`import_record`](Self::import_record).
+    pub fn document_record(&self, id: i64) -> Result<Option<DocumentRecord>> {
+        let Some(document) = self.get_document(id)? else {
+            return Ok(None);
+        };
+
+        let mut chunks = Vec::new();
+        for chunk in self.get_chunks_for_document(id)? {
+            let embedding = match chunk.id {
+                Some(chunk_id) => self.get_embedding(chunk_id)?,
+                None => None,
+            };
+            chunks.push(ChunkRecord {
+                chunk_index: chunk.chunk_index,
+                content: chunk.content,
+                token_count: chunk.token_count,
+                embedding: embedding.map(|e| EmbeddingRecord {
+                    model: e.model,
+                    vector: e.vector,
+                }),
+            });
+        }
+
+        Ok(Some(DocumentRecord {
+            source: document.source,
+            content_hash: document.content_hash,
+            metadata: document.metadata,
+            created_at: document.created_at,
+            chunks,
+        }))
+    }
+
+    /// Write every document as one JSON line, returning how many were written
+    ///
+    /// Records are read and written one document at a time, so memory use
+    /// does not grow with the database. Without `include_vectors` chunks are
+    /// written without their embeddings.
+    pub fn export_jsonl(&self, mut writer: impl Write, include_vectors: bool) -> Result<usize> {
+        let mut written = 0;
+        for id in self.document_ids()? {
+            let Some(mut record) = self.document_record(id)? else {
+                continue;
+            };
+            if !include_vectors {
+                for chunk in &mut record.chunks {
+                    chunk.embedding = None;
+                }
+            }
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+            written += 1;
+        }
+        writer.flush()?;
+
+        Ok(written)
+    }
+
     /// Count total documents
     pub fn count_documents(&self) -> Result<i64> {
         let count: i64 = self
@@ -193,6 +667,89 @@ impl VectorStore {
         Ok(count)
     }
 
+    /// Delete a document together with its chunks and embeddings
+    ///
+    /// Chunks and embeddings are removed by the `ON DELETE CASCADE` foreign
+    /// keys, which SQLite only enforces because every connection enables
+    /// the `foreign_keys` pragma. Returns whether a document was removed.
+    pub fn delete_document(&mut self, id: i64) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM documents WHERE id = ?1", params![id])?;
+
+        debug!("Deleted document {} ({} row(s))", id, deleted);
+        Ok(deleted > 0)
+    }
+
+    /// Delete every document ingested from `source`, returning how many were removed
+    pub fn delete_document_by_source(&mut self, source: &str) -> Result<usize> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM documents WHERE source = ?1", params![source])?;
+
+        debug!("Deleted {} document(s) with source {}", deleted, source);
+        Ok(deleted)
+    }
+
+    /// Report what `delete_document` would remove, without deleting anything
+    pub fn preview_delete_document(&self, id: i64) -> Result<DeletionPreview> {
+        self.preview_deletion("id = ?1", &id)
+    }
+
+    /// Report what `delete_document_by_source` would remove, without deleting anything
+    pub fn preview_delete_document_by_source(&self, source: &str) -> Result<DeletionPreview> {
+        self.preview_deletion("source = ?1", &source)
+    }
+
+    /// Report the documents an interrupted ingest left incomplete, without deleting anything
+    ///
+    /// These are the documents `prune` removes. A document still being
+    /// ingested by another process is incomplete too.
+    pub fn preview_prune(&self) -> Result<DeletionPreview> {
+        self.preview_deletion("complete = ?1", &0)
+    }
+
+    /// Count the documents matching `predicate` and the rows cascading from them
+    fn preview_deletion(
+        &self,
+        predicate: &str,
+        value: &dyn rusqlite::ToSql,
+    ) -> Result<DeletionPreview> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, source FROM documents WHERE {} ORDER BY id",
+            predicate
+        ))?;
+        let documents = stmt
+            .query_map([value], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let chunk_count = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM chunks
+                 WHERE document_id IN (SELECT id FROM documents WHERE {})",
+                predicate
+            ),
+            [value],
+            |row| row.get(0),
+        )?;
+        let embedding_count = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM embeddings e
+                 JOIN chunks c ON e.chunk_id = c.id
+                 WHERE c.document_id IN (SELECT id FROM documents WHERE {})",
+                predicate
+            ),
+            [value],
+            |row| row.get(0),
+        )?;
+
+        Ok(DeletionPreview {
+            documents,
+            chunk_count,
+            embedding_count,
+        })
+    }
+
     // ============================================================================
     // Chunk Operations
     // ============================================================================
@@ -219,6 +776,39 @@ impl VectorStore {
         Ok(id)
     }
 
+    /// Replace a chunk's text and token count, e.g. to correct OCR or extraction errors
+    ///
+    /// The chunk's embedding is deleted, since it no longer matches the text;
+    /// the next `reindex` embeds it again. The keyword index follows through
+    /// its update trigger.
+    pub fn update_chunk_content(
+        &mut self,
+        chunk_id: i64,
+        new_content: &str,
+        token_count: usize,
+    ) -> Result<()> {
+        // A savepoint, unlike `in_transaction`, also nests inside a caller's transaction
+        let savepoint = self.conn.savepoint()?;
+        let updated = savepoint.execute(
+            "UPDATE chunks SET content = ?1, token_count = ?2 WHERE id = ?3",
+            params![new_content, token_count, chunk_id],
+        )?;
+        if updated == 0 {
+            return Err(VectDbError::InvalidInput(format!(
+                "Chunk {} does not exist",
+                chunk_id
+            )));
+        }
+        savepoint.execute(
+            "DELETE FROM embeddings WHERE chunk_id = ?1",
+            params![chunk_id],
+        )?;
+        savepoint.commit()?;
+
+        debug!("Updated content of chunk {}", chunk_id);
+        Ok(())
+    }
+
     /// Get all chunks for a document
     pub fn get_chunks_for_document(&self, document_id: i64) -> Result<Vec<Chunk>> {
         debug!("Getting chunks for document {}", document_id);
@@ -269,6 +859,79 @@ impl VectorStore {
         Ok(result)
     }
 
+    /// Up to `limit` chunks with an id above `after_id`, in id order
+    ///
+    /// With `skip_model`, chunks already embedded with that model are left out.
+    pub fn chunks_after(
+        &self,
+        after_id: i64,
+        limit: usize,
+        skip_model: Option<&str>,
+    ) -> Result<Vec<Chunk>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, c.document_id, c.chunk_index, c.content, c.token_count
+             FROM chunks c
+             LEFT JOIN embeddings e ON e.chunk_id = c.id
+             WHERE c.id > ?1 AND (?3 IS NULL OR e.model IS NULL OR e.model != ?3)
+             ORDER BY c.id
+             LIMIT ?2",
+        )?;
+
+        let chunks = stmt
+            .query_map(params![after_id, limit as i64, skip_model], |row| {
+                Ok(Chunk {
+                    id: Some(row.get(0)?),
+                    document_id: row.get(1)?,
+                    chunk_index: row.get(2)?,
+                    content: row.get(3)?,
+                    token_count: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(chunks)
+    }
+
+    /// Pick up to `n` random chunks together with their documents
+    pub fn sample_chunks(&self, n: usize) -> Result<Vec<(Chunk, Document)>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{} ORDER BY RANDOM() LIMIT ?1", SAMPLE_SQL))?;
+
+        let samples = stmt
+            .query_map(params![n as i64], sample_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(samples)
+    }
+
+    /// Pick up to `n` chunks like [`sample_chunks`](Self::sample_chunks), reproducibly for a seed
+    pub fn sample_chunks_seeded(&self, n: usize, seed: u64) -> Result<Vec<(Chunk, Document)>> {
+        let mut ids: Vec<i64> = self
+            .conn
+            .prepare(
+                "SELECT c.id FROM chunks c
+                 JOIN documents d ON c.document_id = d.id
+                 WHERE d.complete = 1 ORDER BY c.id",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut rng = fastrand::Rng::with_seed(seed);
+        rng.shuffle(&mut ids);
+        ids.truncate(n);
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{} AND c.id = ?1", SAMPLE_SQL))?;
+        let mut samples = Vec::with_capacity(ids.len());
+        for id in ids {
+            samples.push(stmt.query_row(params![id], sample_from_row)?);
+        }
+
+        Ok(samples)
+    }
+
     /// Count total chunks
     pub fn count_chunks(&self) -> Result<i64> {
         let count: i64 = self
@@ -283,20 +946,96 @@ impl VectorStore {
     // ============================================================================
 
     /// Insert or update an embedding for a chunk
+    ///
+    /// Fails if the vector's size differs from the dimension recorded for its model.
     pub fn upsert_embedding(&mut self, embedding: &Embedding) -> Result<()> {
         debug!("Upserting embedding for chunk {}", embedding.chunk_id);
 
-        // Convert vector to bytes
-        let vector_bytes = vector_to_bytes(&embedding.vector);
+        let non_finite = embedding.vector.iter().filter(|v| !v.is_finite()).count();
+        let sanitized = if non_finite == 0 {
+            None
+        } else {
+            match self.non_finite_embeddings {
+                NonFiniteEmbeddings::Reject => {
+                    warn!(
+                        "Rejecting embedding for chunk {}: {} non-finite value(s)",
+                        embedding.chunk_id, non_finite
+                    );
+                    return Err(VectDbError::InvalidInput(format!(
+                        "Embedding for chunk {} contains {} NaN or infinite value(s)",
+                        embedding.chunk_id, non_finite
+                    )));
+                }
+                NonFiniteEmbeddings::Zero => {
+                    warn!(
+                        "Zeroing {} non-finite value(s) in the embedding for chunk {}",
+                        non_finite, embedding.chunk_id
+                    );
+                    Some(
+                        embedding
+                            .vector
+                            .iter()
+                            .map(|&v| if v.is_finite() { v } else { 0.0 })
+                            .collect::<Vec<f32>>(),
+                    )
+                }
+            }
+        };
+        let vector = sanitized.as_deref().unwrap_or(&embedding.vector);
+
+        // Convert vector to bytes, normalized if enabled (zero vectors can't be)
+        let normalized = self
+            .normalize_embeddings
+            .then(|| l2_normalized(vector))
+            .flatten();
+        let vector_bytes = vector_to_bytes(normalized.as_deref().unwrap_or(vector));
+
+        if let Some(expected) = self.model_dimension(&embedding.model)?
+            && expected != embedding.dimension
+        {
+            return Err(VectDbError::InvalidInput(format!(
+                "Embedding for chunk {} has {} dimensions, but {} embeddings have {}",
+                embedding.chunk_id, embedding.dimension, embedding.model, expected
+            )));
+        }
 
+        // An update rather than a replace, so the model_dimensions triggers see a model change
         self.conn.execute(
-            "INSERT OR REPLACE INTO embeddings (chunk_id, model, vector, dimension)
-             VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO embeddings (chunk_id, model, vector, dimension, normalized)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(chunk_id) DO UPDATE SET model = excluded.model,
+                 vector = excluded.vector, dimension = excluded.dimension,
+                 normalized = excluded.normalized",
             params![
                 embedding.chunk_id,
                 &embedding.model,
                 &vector_bytes,
-                embedding.dimension
+                embedding.dimension,
+                normalized.is_some()
+            ],
+        )?;
+
+        // The first embedding stored for a model fixes its expected dimension
+        self.conn.execute(
+            "INSERT OR IGNORE INTO model_dimensions (model, dimension) VALUES (?1, ?2)",
+            params![&embedding.model, embedding.dimension],
+        )?;
+
+        self.ensure_vec_table(embedding.dimension)?;
+        let table = vec_table_name(embedding.dimension);
+        self.conn.execute(
+            &format!("DELETE FROM {} WHERE chunk_id = ?1", table),
+            params![embedding.chunk_id],
+        )?;
+        self.conn.execute(
+            &format!(
+                "INSERT INTO {} (chunk_id, model, embedding) VALUES (?1, ?2, ?3)",
+                table
+            ),
+            params![
+                embedding.chunk_id,
+                &embedding.model,
+                vector_to_bytes(normalized.as_deref().unwrap_or(vector))
             ],
         )?;
 
@@ -314,7 +1053,7 @@ impl VectorStore {
                 params![chunk_id],
                 |row| {
                     let vector_bytes: Vec<u8> = row.get(2)?;
-                    let vector = bytes_to_vector(&vector_bytes);
+                    let vector = bytes_to_vector(&vector_bytes, row.get(3)?);
 
                     Ok(Embedding {
                         chunk_id: row.get(0)?,
@@ -329,6 +1068,38 @@ impl VectorStore {
         Ok(result)
     }
 
+    /// Embedding vectors of the given chunks, keyed by chunk id
+    ///
+    /// Reads them with one `IN (...)` query per batch of ids instead of one
+    /// query per chunk; chunks without an embedding are left out.
+    pub fn get_embedding_vectors(&self, chunk_ids: &[i64]) -> Result<HashMap<i64, Vec<f32>>> {
+        let mut vectors = HashMap::with_capacity(chunk_ids.len());
+
+        for batch in chunk_ids.chunks(MAX_IDS_PER_QUERY) {
+            let placeholders = (0..batch.len())
+                .map(|i| format!("?{}", i + 1))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT chunk_id, vector, dimension FROM embeddings WHERE chunk_id IN ({})",
+                placeholders
+            ))?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(batch), |row| {
+                let vector_bytes: Vec<u8> = row.get(1)?;
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    bytes_to_vector(&vector_bytes, row.get(2)?),
+                ))
+            })?;
+            for row in rows {
+                let (chunk_id, vector) = row?;
+                vectors.insert(chunk_id, vector);
+            }
+        }
+
+        Ok(vectors)
+    }
+
     /// Count total embeddings
     pub fn count_embeddings(&self) -> Result<i64> {
         let count: i64 = self
@@ -338,301 +1109,3027 @@ impl VectorStore {
         Ok(count)
     }
 
-    // ============================================================================
-    // Search Operations (Placeholder for now - will use sqlite-vec in future)
-    // ============================================================================
+    /// Count chunks that have an embedding (each chunk holds at most one, for one model)
+    pub fn count_distinct_chunks_with_embeddings(&self) -> Result<i64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(DISTINCT chunk_id) FROM embeddings",
+            [],
+            |row| row.get(0),
+        )?;
 
-    /// Search for similar vectors (naive implementation using cosine similarity)
-    ///
-    /// Note: This is a placeholder implementation. In Phase 2b, we'll integrate
-    /// sqlite-vec for efficient vector similarity search using HNSW or IVF indices.
-    pub fn search_similar(
-        &self,
-        query_vector: &[f32],
-        model: &str,
-        top_k: usize,
-    ) -> Result<Vec<SearchResult>> {
-        debug!("Searching for similar vectors (top_k={})", top_k);
+        Ok(count)
+    }
 
-        // Get all embeddings for the specified model
-        let mut stmt = self.conn.prepare(
-            "SELECT e.chunk_id, e.model, e.vector, e.dimension,
-                    c.id, c.document_id, c.chunk_index, c.content, c.token_count,
-                    d.id, d.source, d.content_hash, d.metadata, d.created_at
-             FROM embeddings e
-             JOIN chunks c ON e.chunk_id = c.id
-             JOIN documents d ON c.document_id = d.id
-             WHERE e.model = ?1",
+    /// Count chunks that have an embedding for the given model
+    pub fn count_chunks_for_model(&self, model: &str) -> Result<i64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(DISTINCT chunk_id) FROM embeddings WHERE model = ?1",
+            params![model],
+            |row| row.get(0),
         )?;
 
-        let mut results: Vec<(f32, SearchResult)> = stmt
-            .query_map(params![model], |row| {
-                // Parse embedding
-                let vector_bytes: Vec<u8> = row.get(2)?;
-                let vector = bytes_to_vector(&vector_bytes);
+        Ok(count)
+    }
 
-                // Calculate cosine similarity
-                let similarity = cosine_similarity(query_vector, &vector);
+    /// Count embedded chunks per model, ordered by model name
+    pub fn count_chunks_by_model(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT model, COUNT(DISTINCT chunk_id) FROM embeddings GROUP BY model ORDER BY model",
+        )?;
 
-                // Parse chunk
-                let chunk = Chunk {
-                    id: Some(row.get(4)?),
-                    document_id: row.get(5)?,
-                    chunk_index: row.get(6)?,
-                    content: row.get(7)?,
-                    token_count: row.get(8)?,
-                };
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
 
-                // Parse document
+        Ok(counts)
+    }
+
+    /// Expected embedding dimension recorded for `model`
+    pub fn model_dimension(&self, model: &str) -> Result<Option<usize>> {
+        let dimension = self
+            .conn
+            .query_row(
+                "SELECT dimension FROM model_dimensions WHERE model = ?1",
+                params![model],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+
+        Ok(dimension.map(|d| d as usize))
+    }
+
+    /// Expected embedding dimension of every model, ordered by model name
+    pub fn model_dimensions(&self) -> Result<Vec<(String, usize)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT model, dimension FROM model_dimensions ORDER BY model")?;
+
+        let dimensions = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(dimensions)
+    }
+
+    /// Forget the dimension recorded for `model`, so the next embedding stored sets it
+    ///
+    /// For re-embedding all of a model's chunks at a new size.
+    pub fn reset_model_dimension(&mut self, model: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM model_dimensions WHERE model = ?1",
+            params![model],
+        )?;
+        Ok(())
+    }
+
+    /// Reject a query vector whose dimension cannot match the stored embeddings
+    ///
+    /// Compares against the dimension recorded for `model`, or, when `model`
+    /// has none, against those of the other models. Only the small
+    /// `model_dimensions` table is read, never the embeddings. Cosine
+    /// similarity over mismatched dimensions would otherwise score every row
+    /// as 0.0.
+    fn check_query_dimension(&self, query_vector: &[f32], model: &str) -> Result<()> {
+        let query_dimension = query_vector.len();
+
+        if let Some(stored) = self.model_dimension(model)? {
+            if stored != query_dimension {
+                return Err(VectDbError::InvalidInput(format!(
+                    "Query embedding has {} dimensions but embeddings stored for model '{}' \
+                     have {}. Was the query embedded with a different model, or were the \
+                     stored embeddings truncated (--dimensions / ollama.truncate_dimension)?",
+                    query_dimension, model, stored
+                )));
+            }
+            return Ok(());
+        }
+
+        let recorded = self.model_dimensions()?;
+        if !recorded.is_empty()
+            && recorded
+                .iter()
+                .all(|(_, dimension)| *dimension != query_dimension)
+        {
+            let models = recorded
+                .iter()
+                .map(|(name, dimension)| format!("'{}' ({} dimensions)", name, dimension))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(VectDbError::InvalidInput(format!(
+                "No embeddings stored for model '{}' ({} dimensions); the database was built \
+                 with {}. Search with --model {} or re-ingest",
+                model, query_dimension, models, recorded[0].0
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Find documents whose chunks are not all embedded with the given model
+    ///
+    /// Also reports documents whose ingest was interrupted before all their
+    /// chunks were stored. Returns each incomplete document together with its
+    /// embedded chunk count and stored chunk count.
+    pub fn documents_with_incomplete_embeddings(
+        &self,
+        model: &str,
+    ) -> Result<Vec<(Document, i64, i64)>> {
+        debug!("Finding documents with incomplete embeddings for {}", model);
+
+        let mut stmt = self.conn.prepare(
+            "SELECT d.id, d.source, d.content_hash, d.metadata, d.created_at,
+                    COUNT(e.chunk_id) AS embedded, COUNT(c.id) AS total
+             FROM documents d
+             JOIN chunks c ON c.document_id = d.id
+             LEFT JOIN embeddings e ON e.chunk_id = c.id AND e.model = ?1
+             GROUP BY d.id
+             HAVING embedded < total OR d.complete = 0
+             ORDER BY d.id",
+        )?;
+
+        let documents = stmt
+            .query_map(params![model], |row| {
+                Ok((document_from_row(row, 0)?, row.get(5)?, row.get(6)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(documents)
+    }
+
+    // ============================================================================
+    // Embedding Cache Operations
+    // ============================================================================
+
+    /// Look up a cached embedding for the given model and text
+    pub fn get_cached_embedding(&self, model: &str, text: &str) -> Result<Option<Vec<f32>>> {
+        let key = embedding_cache_key(model, text);
+
+        let result = self
+            .conn
+            .query_row(
+                "SELECT vector, dimension FROM embedding_cache WHERE key = ?1",
+                params![key],
+                |row| {
+                    let vector_bytes: Vec<u8> = row.get(0)?;
+                    Ok(bytes_to_vector(&vector_bytes, row.get(1)?))
+                },
+            )
+            .optional()?;
+
+        Ok(result)
+    }
+
+    /// Store an embedding in the cache for the given model and text
+    pub fn put_cached_embedding(&mut self, model: &str, text: &str, vector: &[f32]) -> Result<()> {
+        let key = embedding_cache_key(model, text);
+        let created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO embedding_cache (key, model, vector, dimension, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                key,
+                model,
+                vector_to_bytes(vector),
+                vector.len(),
+                created_at
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Count cached embeddings
+    pub fn count_cached_embeddings(&self) -> Result<i64> {
+        let count: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM embedding_cache", [], |row| row.get(0))?;
+
+        Ok(count)
+    }
+
+    /// Remove all cached embeddings, returning how many were removed
+    pub fn clear_embedding_cache(&mut self) -> Result<usize> {
+        info!("Clearing embedding cache");
+        let removed = self.conn.execute("DELETE FROM embedding_cache", [])?;
+        Ok(removed)
+    }
+
+    // ============================================================================
+    // Import Operations
+    // ============================================================================
+
+    /// Run `f` inside a transaction, committing on success and rolling back on error
+    pub fn in_transaction<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("BEGIN")?;
+
+        match f(self) {
+            Ok(value) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.conn.execute_batch("ROLLBACK")?;
+                // A vec0 table created inside the transaction is gone again
+                self.vec_tables.borrow_mut().clear();
+                Err(e)
+            }
+        }
+    }
+
+    /// Insert a document with its embedded chunks, committing every `batch_size` chunks
+    ///
+    /// Bounding each transaction keeps lock hold times short for very large
+    /// files, and a crash loses at most the batch in flight. The document stays
+    /// flagged incomplete until its last chunk is stored, so an interrupted
+    /// ingest is neither mistaken for a finished duplicate nor kept when the
+    /// file is ingested again. `on_commit` is called after each commit with the
+    /// number of chunks stored so far. Returns the new document's ID.
+    pub fn insert_document_with_chunks(
+        &mut self,
+        document: &Document,
+        chunks: &[EmbeddedChunk],
+        model: &str,
+        batch_size: usize,
+        on_commit: impl FnMut(usize),
+    ) -> Result<i64> {
+        let document_id = self.begin_document(document)?;
+        self.append_chunks(document_id, 0, chunks, model, batch_size, on_commit)?;
+        self.finish_document(document_id)?;
+        Ok(document_id)
+    }
+
+    /// Insert a document flagged incomplete, to be filled by `append_chunks`
+    ///
+    /// Until `finish_document` is called, hash lookups ignore the document and
+    /// `documents_with_incomplete_embeddings` reports it.
+    pub fn begin_document(&mut self, document: &Document) -> Result<i64> {
+        self.in_transaction(|store| {
+            let document_id = store.insert_document(document)?;
+            store.conn.execute(
+                "UPDATE documents SET complete = 0 WHERE id = ?1",
+                params![document_id],
+            )?;
+            Ok(document_id)
+        })
+    }
+
+    /// Store embedded chunks of a document from `first_index` on, committing every `batch_size`
+    ///
+    /// `on_commit` is called after each commit with the number of these chunks
+    /// stored so far.
+    pub fn append_chunks(
+        &mut self,
+        document_id: i64,
+        first_index: usize,
+        chunks: &[EmbeddedChunk],
+        model: &str,
+        batch_size: usize,
+        mut on_commit: impl FnMut(usize),
+    ) -> Result<()> {
+        if batch_size == 0 {
+            return Err(VectDbError::InvalidInput(
+                "Commit batch size must be greater than 0".to_string(),
+            ));
+        }
+
+        let mut stored = 0;
+        while stored < chunks.len() {
+            let end = (stored + batch_size).min(chunks.len());
+            self.in_transaction(|store| {
+                store.insert_chunk_batch(
+                    document_id,
+                    first_index + stored,
+                    &chunks[stored..end],
+                    model,
+                )
+            })?;
+            stored = end;
+            on_commit(stored);
+        }
+
+        Ok(())
+    }
+
+    /// Mark a document started with `begin_document` as fully stored
+    pub fn finish_document(&mut self, document_id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE documents SET complete = 1 WHERE id = ?1",
+            params![document_id],
+        )?;
+        Ok(())
+    }
+
+    /// Insert consecutive chunks of a document, starting at `first_index`, with their embeddings
+    fn insert_chunk_batch(
+        &mut self,
+        document_id: i64,
+        first_index: usize,
+        chunks: &[EmbeddedChunk],
+        model: &str,
+    ) -> Result<()> {
+        for (offset, embedded) in chunks.iter().enumerate() {
+            let chunk = Chunk::new(document_id, first_index + offset, embedded.content.clone())
+                .with_token_count(embedded.token_count);
+            let chunk_id = self.insert_chunk(&chunk)?;
+            self.upsert_embedding(&Embedding::new(
+                chunk_id,
+                model.to_string(),
+                embedded.vector.clone(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Insert a dumped document with its chunks and stored embeddings
+    ///
+    /// Returns `false` without writing anything if a document with the same
+    /// content hash already exists.
+    pub fn import_record(&mut self, record: &DocumentRecord) -> Result<bool> {
+        if self.get_document_by_hash(&record.content_hash)?.is_some() {
+            debug!("Skipping duplicate document: {}", record.source);
+            return Ok(false);
+        }
+
+        let document_id = self.insert_document(&Document {
+            id: None,
+            source: record.source.clone(),
+            content_hash: record.content_hash.clone(),
+            metadata: record.metadata.clone(),
+            created_at: record.created_at,
+        })?;
+
+        for chunk in &record.chunks {
+            let chunk_id = self.insert_chunk(&Chunk {
+                id: None,
+                document_id,
+                chunk_index: chunk.chunk_index,
+                content: chunk.content.clone(),
+                token_count: chunk.token_count,
+            })?;
+
+            if let Some(embedding) = &chunk.embedding {
+                self.upsert_embedding(&Embedding::new(
+                    chunk_id,
+                    embedding.model.clone(),
+                    embedding.vector.clone(),
+                ))?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Get the number of lines already committed for an import input
+    pub fn get_import_checkpoint(&self, input: &str) -> Result<Option<u64>> {
+        let offset = self
+            .conn
+            .query_row(
+                "SELECT line_offset FROM import_checkpoints WHERE input = ?1",
+                params![input],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+
+        Ok(offset.map(|offset| offset as u64))
+    }
+
+    /// Record the number of lines committed for an import input
+    pub fn set_import_checkpoint(&mut self, input: &str, line_offset: u64) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO import_checkpoints (input, line_offset, updated_at)
+             VALUES (?1, ?2, ?3)",
+            params![input, line_offset as i64, now],
+        )?;
+
+        Ok(())
+    }
+
+    /// Forget the checkpoint for an import input
+    pub fn clear_import_checkpoint(&mut self, input: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM import_checkpoints WHERE input = ?1",
+            params![input],
+        )?;
+
+        Ok(())
+    }
+
+    // ============================================================================
+    // Search History Operations
+    // ============================================================================
+
+    /// Record a search, keeping only the newest `max_entries` searches
+    pub fn record_search(
+        &mut self,
+        query: &str,
+        result_count: usize,
+        max_entries: usize,
+    ) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        self.in_transaction(|store| {
+            store.conn.execute(
+                "INSERT INTO search_history (query, result_count, created_at)
+                 VALUES (?1, ?2, ?3)",
+                params![query, result_count as i64, now],
+            )?;
+            store.conn.execute(
+                "DELETE FROM search_history WHERE id NOT IN (
+                     SELECT id FROM search_history ORDER BY id DESC LIMIT ?1
+                 )",
+                params![max_entries as i64],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// The `limit` most recent searches, newest first
+    pub fn recent_searches(&self, limit: usize) -> Result<Vec<SearchHistoryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT query, result_count, created_at FROM search_history
+             ORDER BY id DESC LIMIT ?1",
+        )?;
+
+        let entries = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(SearchHistoryEntry {
+                    query: row.get(0)?,
+                    result_count: row.get(1)?,
+                    created_at: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    // ============================================================================
+    // Model Version Operations
+    // ============================================================================
+
+    /// Digest of the model weights recorded for `model`, if any
+    pub fn model_version(&self, model: &str) -> Result<Option<String>> {
+        let digest = self
+            .conn
+            .query_row(
+                "SELECT digest FROM model_versions WHERE model = ?1",
+                params![model],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(digest)
+    }
+
+    /// Record the digest of the model weights embeddings are made with
+    pub fn record_model_version(&mut self, model: &str, digest: &str) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO model_versions (model, digest, recorded_at)
+             VALUES (?1, ?2, ?3)",
+            params![model, digest, now],
+        )?;
+
+        Ok(())
+    }
+
+    /// Compare the current model digest with the recorded one
+    ///
+    /// The first digest seen for a model is recorded. Returns a warning when
+    /// the model has since changed, leaving the recorded digest untouched so
+    /// the warning persists until the embeddings are rebuilt.
+    pub fn check_model_version(&mut self, model: &str, digest: &str) -> Result<Option<String>> {
+        match self.model_version(model)? {
+            None => {
+                self.record_model_version(model, digest)?;
+                Ok(None)
+            }
+            Some(recorded) if recorded == digest => Ok(None),
+            Some(recorded) => Ok(Some(format!(
+                "Model '{}' has changed since its embeddings were stored ({} -> {}); \
+                 results may be stale. Run `vectdb reindex {} --force` to refresh them.",
+                model,
+                short_digest(&recorded),
+                short_digest(digest),
+                model
+            ))),
+        }
+    }
+
+    // ============================================================================
+    // Search Operations
+    // ============================================================================
+
+    /// Search for the embeddings of `model` most similar to `query_vector`
+    ///
+    /// Returns `top_k` results after skipping the best `offset`. Ties are
+    /// broken by chunk id, so consecutive pages never overlap or skip results.
+    /// Candidates come from the sqlite-vec index and are rescored exactly; the
+    /// scan in Rust is the fallback when the index is disabled, missing (an
+    /// unmigrated read-only copy) or too few of its candidates survive
+    /// rescoring, e.g. because they belong to partially ingested documents.
+    pub fn search_similar(
+        &self,
+        query_vector: &[f32],
+        model: &str,
+        top_k: usize,
+        offset: usize,
+    ) -> Result<Vec<SearchResult>> {
+        debug!(
+            "Searching for similar vectors (top_k={}, offset={})",
+            top_k, offset
+        );
+        self.check_query_dimension(query_vector, model)?;
+        self.rows_scanned.set(0);
+
+        let needed = offset.saturating_add(top_k);
+        let mut results = match self.ann_candidates(query_vector, model, needed)? {
+            Some(chunk_ids) => self.score_chunks(query_vector, model, &chunk_ids)?,
+            None => Vec::new(),
+        };
+        if results.len() < needed {
+            results = self.score_rows(SEARCH_SQL, &[&model], query_vector)?;
+        }
+        rank_results(&mut results, top_k, offset);
+
+        Ok(results)
+    }
+
+    /// Chunk ids of the nearest embeddings according to the sqlite-vec index
+    ///
+    /// Returns None when the index cannot be used for this search.
+    fn ann_candidates(
+        &self,
+        query_vector: &[f32],
+        model: &str,
+        needed: usize,
+    ) -> Result<Option<Vec<i64>>> {
+        let dimension = query_vector.len();
+        if !self.use_ann || needed > VEC0_MAX_K || !self.has_vec_table(dimension)? {
+            return Ok(None);
+        }
+
+        // Extra candidates keep rounding differences between the index's
+        // distances and the exact rescoring from changing the page
+        let k = needed.saturating_mul(2).min(VEC0_MAX_K);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT chunk_id FROM {} WHERE embedding MATCH ?1 AND k = ?2 AND model = ?3",
+            vec_table_name(dimension)
+        ))?;
+        let chunk_ids = stmt
+            .query_map(
+                params![vector_to_bytes(query_vector), k as i64, model],
+                |row| row.get(0),
+            )?
+            .collect::<std::result::Result<Vec<i64>, _>>()?;
+
+        debug!("sqlite-vec returned {} candidates", chunk_ids.len());
+        Ok(Some(chunk_ids))
+    }
+
+    /// Score the given chunks' embeddings for `model` against the query vector
+    fn score_chunks(
+        &self,
+        query_vector: &[f32],
+        model: &str,
+        chunk_ids: &[i64],
+    ) -> Result<Vec<SearchResult>> {
+        let mut results = Vec::with_capacity(chunk_ids.len());
+        for batch in chunk_ids.chunks(MAX_IDS_PER_QUERY) {
+            let placeholders = (0..batch.len())
+                .map(|i| format!("?{}", i + 2))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let sql = format!("{} AND e.chunk_id IN ({})", SEARCH_SQL, placeholders);
+
+            let mut params: Vec<&dyn rusqlite::ToSql> = vec![&model];
+            params.extend(batch.iter().map(|id| id as &dyn rusqlite::ToSql));
+
+            results.extend(self.score_rows(&sql, &params, query_vector)?);
+        }
+
+        Ok(results)
+    }
+
+    /// Search combining BM25 keyword relevance with cosine similarity
+    ///
+    /// Both scores are min-max normalized over the candidates before blending
+    /// as `alpha * vector + (1 - alpha) * keyword`, so `alpha = 1.0` is a pure
+    /// vector search. The blended score is returned as the result similarity.
+    pub fn hybrid_search(
+        &self,
+        query_text: &str,
+        query_vector: &[f32],
+        model: &str,
+        top_k: usize,
+        alpha: f32,
+    ) -> Result<Vec<SearchResult>> {
+        debug!(
+            "Hybrid search (top_k={}, alpha={}) for '{}'",
+            top_k, alpha, query_text
+        );
+        self.check_query_dimension(query_vector, model)?;
+        self.rows_scanned.set(0);
+
+        let mut results = self.score_rows(SEARCH_SQL, &[&model], query_vector)?;
+        let keyword_scores = self.keyword_scores(query_text)?;
+
+        let vector_scores: Vec<f32> = results.iter().map(|r| r.similarity).collect();
+        let text_scores: Vec<f32> = results
+            .iter()
+            .map(|r| {
+                r.chunk
+                    .id
+                    .and_then(|id| keyword_scores.get(&id).copied())
+                    .unwrap_or(0.0)
+            })
+            .collect();
+
+        let vector_scores = min_max_normalize(&vector_scores);
+        let text_scores = min_max_normalize(&text_scores);
+        for (result, (v, t)) in results
+            .iter_mut()
+            .zip(vector_scores.into_iter().zip(text_scores))
+        {
+            result.similarity = alpha * v + (1.0 - alpha) * t;
+        }
+        rank_results(&mut results, top_k, 0);
+
+        Ok(results)
+    }
+
+    /// BM25 relevance of every chunk matching any term of `query_text` (higher is better)
+    fn keyword_scores(&self, query_text: &str) -> Result<HashMap<i64, f32>> {
+        let Some(match_expr) = fts_match_expression(query_text) else {
+            return Ok(HashMap::new());
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT rowid, bm25(chunks_fts) FROM chunks_fts WHERE chunks_fts MATCH ?1")?;
+        let scores = stmt
+            .query_map(params![match_expr], |row| {
+                // bm25() is negative, with more relevant rows further below zero
+                Ok((row.get::<_, i64>(0)?, -row.get::<_, f64>(1)? as f32))
+            })?
+            .collect::<std::result::Result<HashMap<_, _>, _>>()?;
+
+        Ok(scores)
+    }
+
+    /// Search for similar vectors within the given documents only
+    ///
+    /// Document ids are bound in batches to stay under SQLite's host
+    /// parameter limit; an empty id list matches nothing.
+    pub fn search_within_documents(
+        &self,
+        query_vector: &[f32],
+        model: &str,
+        top_k: usize,
+        document_ids: &[i64],
+    ) -> Result<Vec<SearchResult>> {
+        debug!(
+            "Searching for similar vectors in {} document(s) (top_k={})",
+            document_ids.len(),
+            top_k
+        );
+        self.check_query_dimension(query_vector, model)?;
+        self.rows_scanned.set(0);
+
+        let mut results = Vec::new();
+        for batch in document_ids.chunks(MAX_IDS_PER_QUERY) {
+            let placeholders = (0..batch.len())
+                .map(|i| format!("?{}", i + 2))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let sql = format!("{} AND d.id IN ({})", SEARCH_SQL, placeholders);
+
+            let mut params: Vec<&dyn rusqlite::ToSql> = vec![&model];
+            params.extend(batch.iter().map(|id| id as &dyn rusqlite::ToSql));
+
+            results.extend(self.score_rows(&sql, &params, query_vector)?);
+        }
+        rank_results(&mut results, top_k, 0);
+
+        Ok(results)
+    }
+
+    /// Run a search query and score every returned embedding against the query vector
+    fn score_rows(
+        &self,
+        sql: &str,
+        params: &[&dyn rusqlite::ToSql],
+        query_vector: &[f32],
+    ) -> Result<Vec<SearchResult>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let unit_query = l2_normalized(query_vector);
+
+        let results = stmt
+            .query_map(params, |row| {
+                // Parse embedding
+                let vector_bytes: Vec<u8> = row.get(2)?;
+                let vector = bytes_to_vector(&vector_bytes, row.get(3)?);
+                let normalized: bool = row.get(14)?;
+
+                // Calculate cosine similarity (a dot product for unit vectors)
+                let similarity = match (&unit_query, normalized, self.high_precision_scoring) {
+                    (Some(unit), true, true) => dot_product_f64(unit, &vector),
+                    (Some(unit), true, false) => dot_product(unit, &vector),
+                    (_, _, true) => cosine_similarity_f64(query_vector, &vector),
+                    (_, _, false) => cosine_similarity(query_vector, &vector),
+                };
+
+                // Parse chunk
+                let chunk = Chunk {
+                    id: Some(row.get(4)?),
+                    document_id: row.get(5)?,
+                    chunk_index: row.get(6)?,
+                    content: row.get(7)?,
+                    token_count: row.get(8)?,
+                };
+
+                // Parse document
                 let metadata_json: String = row.get(12)?;
                 let metadata = serde_json::from_str(&metadata_json).unwrap_or_default();
 
-                let document = Document {
-                    id: Some(row.get(9)?),
-                    source: row.get(10)?,
-                    content_hash: row.get(11)?,
-                    metadata,
-                    created_at: row.get(13)?,
-                };
+                let document = Document {
+                    id: Some(row.get(9)?),
+                    source: row.get(10)?,
+                    content_hash: row.get(11)?,
+                    metadata,
+                    created_at: row.get(13)?,
+                };
+
+                Ok(SearchResult {
+                    chunk,
+                    document,
+                    similarity,
+                    snippet: None,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        self.rows_scanned
+            .set(self.rows_scanned.get() + results.len());
+
+        Ok(results)
+    }
+
+    /// Interrupt queries still running at `deadline` (None = no limit)
+    ///
+    /// Installs a SQLite progress handler, so a long similarity scan stops
+    /// part-way instead of finishing after the deadline has passed; the
+    /// interrupted query fails with an error for which [`is_interrupted`] holds.
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        match deadline {
+            Some(deadline) => self.conn.progress_handler(
+                DEADLINE_CHECK_INTERVAL,
+                Some(move || Instant::now() >= deadline),
+            ),
+            None => self.conn.progress_handler(0, None::<fn() -> bool>),
+        }
+    }
+
+    /// Number of embeddings scored by the most recent similarity search
+    ///
+    /// Counts every candidate compared with the query, before any `top_k` cut.
+    /// With the sqlite-vec index, only the candidates it returned are scored.
+    pub fn rows_scanned(&self) -> usize {
+        self.rows_scanned.get()
+    }
+
+    /// Find the existing document whose mean embedding is most similar to `mean_vector`
+    ///
+    /// Only documents with embeddings for `model` of the mean vector's
+    /// dimension are considered, and documents ingested from `exclude_source`
+    /// are skipped so an edited file is not
+    /// matched against its own earlier version. Returns the document and its
+    /// similarity if it reaches `threshold`.
+    pub fn nearest_duplicate(
+        &self,
+        mean_vector: &[f32],
+        model: &str,
+        threshold: f32,
+        exclude_source: &str,
+    ) -> Result<Option<(Document, f32)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.document_id, e.vector, e.dimension
+             FROM embeddings e
+             JOIN chunks c ON e.chunk_id = c.id
+             JOIN documents d ON c.document_id = d.id
+             WHERE e.model = ?1 AND d.source != ?2 AND d.complete = 1 AND e.dimension = ?3
+             ORDER BY c.document_id",
+        )?;
+
+        let mut sums: Vec<(i64, Vec<f32>, usize)> = Vec::new();
+        let rows = stmt.query_map(
+            params![model, exclude_source, mean_vector.len() as i64],
+            |row| {
+                let vector_bytes: Vec<u8> = row.get(1)?;
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    bytes_to_vector(&vector_bytes, row.get(2)?),
+                ))
+            },
+        )?;
+
+        for row in rows {
+            let (document_id, vector) = row?;
+            match sums.last_mut() {
+                Some((id, sum, count)) if *id == document_id => {
+                    sum.iter_mut().zip(&vector).for_each(|(s, v)| *s += v);
+                    *count += 1;
+                }
+                _ => sums.push((document_id, vector, 1)),
+            }
+        }
+
+        // The mean's direction equals the sum's, so cosine similarity can use the sum directly
+        let best = sums
+            .into_iter()
+            .map(|(document_id, sum, _)| (document_id, cosine_similarity(mean_vector, &sum)))
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        match best {
+            Some((document_id, similarity)) => Ok(self
+                .get_document(document_id)?
+                .map(|document| (document, similarity))),
+            None => Ok(None),
+        }
+    }
+
+    /// Return the `EXPLAIN QUERY PLAN` rows for the similarity search query
+    ///
+    /// Each entry is the plan detail, indented by its depth in the plan tree.
+    pub fn explain_search_plan(&self, model: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("EXPLAIN QUERY PLAN {}", SEARCH_SQL))?;
+
+        let rows = stmt
+            .query_map(params![model], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        // Rows arrive in tree order, so a node's parent has already been seen
+        let mut depths: std::collections::HashMap<i64, usize> = std::collections::HashMap::new();
+        let plan = rows
+            .into_iter()
+            .map(|(id, parent, detail)| {
+                let depth = depths.get(&parent).map(|d| d + 1).unwrap_or(0);
+                depths.insert(id, depth);
+                format!("{}{}", "  ".repeat(depth), detail)
+            })
+            .collect();
+
+        Ok(plan)
+    }
+
+    // ============================================================================
+    // Database Maintenance
+    // ============================================================================
+
+    /// Run VACUUM to optimize database size
+    pub fn vacuum(&self) -> Result<()> {
+        info!("Running VACUUM on database");
+        self.conn.execute("VACUUM", [])?;
+        Ok(())
+    }
+
+    /// Whether the database uses `auto_vacuum = INCREMENTAL`
+    pub fn is_incremental_vacuum_enabled(&self) -> Result<bool> {
+        let mode: i64 = self
+            .conn
+            .query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+        Ok(mode == 2)
+    }
+
+    /// Switch the database to `auto_vacuum = INCREMENTAL`
+    ///
+    /// SQLite only applies a new auto_vacuum mode to an existing database on
+    /// the next VACUUM, so this runs one full VACUUM. Does nothing if the mode
+    /// is already incremental; returns whether the mode was changed.
+    pub fn enable_incremental_vacuum(&self) -> Result<bool> {
+        if self.is_incremental_vacuum_enabled()? {
+            return Ok(false);
+        }
+
+        info!("Switching database to incremental auto_vacuum");
+        self.conn
+            .execute_batch("PRAGMA auto_vacuum = INCREMENTAL")?;
+        self.vacuum()?;
+        Ok(true)
+    }
+
+    /// Reclaim up to `pages` free pages without rewriting the database
+    ///
+    /// `0` reclaims every free page. Only has an effect once incremental
+    /// auto_vacuum is enabled. Returns the number of pages reclaimed.
+    pub fn incremental_vacuum(&self, pages: u32) -> Result<i64> {
+        info!("Running incremental vacuum ({} pages)", pages);
+        let free_before = self.freelist_count()?;
+        // The pragma frees one page per step, so step it to completion
+        let mut stmt = self
+            .conn
+            .prepare(&format!("PRAGMA incremental_vacuum({})", pages))?;
+        let mut rows = stmt.query([])?;
+        while rows.next()?.is_some() {}
+
+        Ok(free_before - self.freelist_count()?)
+    }
+
+    /// Number of unused pages in the database file
+    pub fn freelist_count(&self) -> Result<i64> {
+        let count = self
+            .conn
+            .query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Speed up a one-shot bulk load at the expense of crash safety
+    ///
+    /// Turns off `synchronous` for this connection and drops the secondary
+    /// indices, which are rebuilt once by [`end_bulk_mode`](Self::end_bulk_mode)
+    /// instead of being updated row by row. A crash or power loss before
+    /// `end_bulk_mode` may leave the database corrupt; only use this for loads
+    /// that can be redone from scratch. Opening the database again recreates
+    /// any missing index.
+    pub fn begin_bulk_mode(&self) -> Result<()> {
+        info!("Entering bulk mode (synchronous=OFF, secondary indices dropped)");
+        self.conn.pragma_update(None, "synchronous", "OFF")?;
+        for (name, _) in SECONDARY_INDICES {
+            self.conn
+                .execute(&format!("DROP INDEX IF EXISTS {}", name), [])?;
+        }
+        Ok(())
+    }
+
+    /// Rebuild the indices dropped by bulk mode and restore safe durability settings
+    pub fn end_bulk_mode(&self) -> Result<()> {
+        info!("Leaving bulk mode, rebuilding indices");
+        self.create_secondary_indices()?;
+        self.conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Ok(())
+    }
+
+    /// Run ANALYZE to update query optimizer statistics
+    pub fn analyze(&self) -> Result<()> {
+        info!("Running ANALYZE on database");
+        self.conn.execute("ANALYZE", [])?;
+        Ok(())
+    }
+
+    /// Re-encode every stored embedding in the given precision
+    ///
+    /// Embeddings are rewritten in batches of `batch_size`, one transaction per
+    /// batch. Vectors already in the target format are left untouched. Returns
+    /// the number of embeddings rewritten; run `vacuum` afterwards to reclaim space.
+    pub fn recompress_embeddings(
+        &mut self,
+        precision: StoragePrecision,
+        batch_size: usize,
+    ) -> Result<usize> {
+        let mut last_chunk_id = i64::MIN;
+        let mut rewritten = 0;
+
+        loop {
+            let batch: Vec<(i64, Vec<u8>, usize)> = self
+                .conn
+                .prepare(
+                    "SELECT chunk_id, vector, dimension FROM embeddings
+                     WHERE chunk_id > ?1 ORDER BY chunk_id LIMIT ?2",
+                )?
+                .query_map(params![last_chunk_id, batch_size.max(1) as i64], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+
+            let Some((last, _, _)) = batch.last() else {
+                break;
+            };
+            last_chunk_id = *last;
+
+            rewritten += self.in_transaction(|store| {
+                let mut count = 0;
+                for (chunk_id, bytes, dimension) in &batch {
+                    let encoded = encode_vector(&bytes_to_vector(bytes, *dimension), precision);
+                    if encoded.len() != bytes.len() {
+                        store.conn.execute(
+                            "UPDATE embeddings SET vector = ?1 WHERE chunk_id = ?2",
+                            params![encoded, chunk_id],
+                        )?;
+                        count += 1;
+                    }
+                }
+                Ok(count)
+            })?;
+        }
+
+        info!("Re-encoded {} embeddings as {:?}", rewritten, precision);
+
+        Ok(rewritten)
+    }
+
+    /// SQLite's `data_version`: changes whenever another connection commits
+    ///
+    /// Values are only comparable across calls on the same store; this
+    /// connection's own writes never change it.
+    pub fn data_version(&self) -> Result<i64> {
+        Ok(self
+            .conn
+            .query_row("PRAGMA data_version", [], |row| row.get(0))?)
+    }
+
+    /// Get database statistics
+    pub fn get_stats(&self) -> Result<DatabaseStats> {
+        let doc_count = self.count_documents()?;
+        let chunk_count = self.count_chunks()?;
+        let embedding_count = self.count_embeddings()?;
+        let embedded_chunk_count = self.count_distinct_chunks_with_embeddings()?;
+
+        // Get database file size
+        let page_count: i64 = self
+            .conn
+            .query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: i64 = self
+            .conn
+            .query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        let db_size_bytes = page_count * page_size;
+
+        Ok(DatabaseStats {
+            document_count: doc_count,
+            chunk_count,
+            embedding_count,
+            embedded_chunk_count,
+            db_size_bytes,
+        })
+    }
+}
+
+/// Query used to fetch candidate embeddings for similarity search
+const SEARCH_SQL: &str = "SELECT e.chunk_id, e.model, e.vector, e.dimension,
+        c.id, c.document_id, c.chunk_index, c.content, c.token_count,
+        d.id, d.source, d.content_hash, d.metadata, d.created_at, e.normalized
+ FROM embeddings e
+ JOIN chunks c ON e.chunk_id = c.id
+ JOIN documents d ON c.document_id = d.id
+ WHERE e.model = ?1 AND d.complete = 1";
+
+/// Largest `k` a sqlite-vec KNN query accepts
+const VEC0_MAX_K: usize = 4096;
+
+/// Name of the sqlite-vec table indexing embeddings of `dimension` values
+fn vec_table_name(dimension: usize) -> String {
+    format!("vec_chunks_{}", dimension)
+}
+
+/// Load the sqlite-vec extension into every connection opened from now on
+fn register_sqlite_vec() {
+    static REGISTER: Once = Once::new();
+
+    REGISTER.call_once(|| {
+        type EntryPoint = unsafe extern "C" fn(
+            *mut rusqlite::ffi::sqlite3,
+            *mut *const std::os::raw::c_char,
+            *const rusqlite::ffi::sqlite3_api_routines,
+        ) -> std::os::raw::c_int;
+
+        // SAFETY: `sqlite3_vec_init` is an SQLite extension entry point, which
+        // is the signature `sqlite3_auto_extension` expects
+        unsafe {
+            let entry_point = std::mem::transmute::<*const (), EntryPoint>(
+                sqlite_vec::sqlite3_vec_init as *const (),
+            );
+            rusqlite::ffi::sqlite3_auto_extension(Some(entry_point));
+        }
+    });
+}
+
+/// SQLite virtual machine steps between deadline checks of a running query
+const DEADLINE_CHECK_INTERVAL: i32 = 1000;
+
+/// Whether a query failed because it was interrupted at its deadline
+pub fn is_interrupted(error: &VectDbError) -> bool {
+    matches!(
+        error.root_cause(),
+        VectDbError::Database(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::OperationInterrupted
+    )
+}
+
+/// Ids bound per `IN (...)` query (SQLite allows 999 parameters)
+const MAX_IDS_PER_QUERY: usize = 900;
+
+/// Indices that only speed up lookups: (name, table and columns)
+///
+/// Bulk mode drops and recreates these; uniqueness constraints are never dropped.
+const SECONDARY_INDICES: [(&str, &str); 2] = [
+    ("idx_chunks_document", "chunks(document_id)"),
+    ("idx_embeddings_model", "embeddings(model)"),
+];
+
+/// First 12 hex digits of a digest such as `sha256:0a1b...`, for messages
+fn short_digest(digest: &str) -> &str {
+    let hex = digest.split_once(':').map_or(digest, |(_, hex)| hex);
+    hex.get(..12).unwrap_or(hex)
+}
+
+/// Build an FTS5 query matching any word of `text`, with each word quoted
+///
+/// Quoting keeps user input such as `C++` or `error: 404` from being parsed
+/// as FTS5 operators. Returns `None` if the text has no words.
+fn fts_match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"", term))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+/// Scale scores linearly onto [0, 1]; all-equal scores map to 0
+fn min_max_normalize(scores: &[f32]) -> Vec<f32> {
+    let min = scores.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+    scores
+        .iter()
+        .map(|&s| if range > 0.0 { (s - min) / range } else { 0.0 })
+        .collect()
+}
+
+/// Sort results by similarity, skip the first `offset` and keep the next `top_k`
+fn rank_results(results: &mut Vec<SearchResult>, top_k: usize, offset: usize) {
+    results.sort_by(compare_results);
+    results.drain(..offset.min(results.len()));
+    results.truncate(top_k);
+}
+
+/// Ranking order: similarity descending, then chunk id ascending
+///
+/// The tie-break makes the order total, so paging with an offset is stable.
+pub fn compare_results(a: &SearchResult, b: &SearchResult) -> std::cmp::Ordering {
+    b.similarity
+        .partial_cmp(&a.similarity)
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then_with(|| a.chunk.id.cmp(&b.chunk.id))
+}
+
+/// On-disk precision of embedding vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoragePrecision {
+    /// 32-bit floats (4 bytes per dimension)
+    F32,
+    /// 16-bit floats (2 bytes per dimension)
+    F16,
+}
+
+impl StoragePrecision {
+    fn bytes_per_value(&self) -> usize {
+        match self {
+            StoragePrecision::F32 => 4,
+            StoragePrecision::F16 => 2,
+        }
+    }
+}
+
+impl std::str::FromStr for StoragePrecision {
+    type Err = VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "f32" => Ok(StoragePrecision::F32),
+            "f16" => Ok(StoragePrecision::F16),
+            other => Err(VectDbError::InvalidInput(format!(
+                "Invalid storage precision: {}. Use f32 or f16",
+                other
+            ))),
+        }
+    }
+}
+
+/// Sort key for listing documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentSort {
+    /// Creation time
+    Date,
+    /// Source path
+    Source,
+    /// Number of chunks
+    Chunks,
+}
+
+impl std::str::FromStr for DocumentSort {
+    type Err = VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "date" => Ok(DocumentSort::Date),
+            "source" => Ok(DocumentSort::Source),
+            "chunks" => Ok(DocumentSort::Chunks),
+            _ => Err(VectDbError::InvalidInput(format!(
+                "Invalid sort key '{}'. Expected one of: date, source, chunks",
+                s
+            ))),
+        }
+    }
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(VectDbError::InvalidInput(format!(
+                "Invalid sort order '{}'. Expected asc or desc",
+                s
+            ))),
+        }
+    }
+}
+
+/// A document together with its chunk count
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentSummary {
+    pub document: Document,
+    pub chunk_count: i64,
+}
+
+/// What a deletion would remove
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeletionPreview {
+    /// Ids and sources of the matching documents
+    pub documents: Vec<(i64, String)>,
+    pub chunk_count: i64,
+    pub embedding_count: i64,
+}
+
+/// A recorded search
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    pub result_count: i64,
+    pub created_at: i64,
+}
+
+/// Database statistics
+#[derive(Debug, Clone)]
+pub struct DatabaseStats {
+    pub document_count: i64,
+    pub chunk_count: i64,
+    pub embedding_count: i64,
+    pub embedded_chunk_count: i64,
+    pub db_size_bytes: i64,
+}
+
+impl DatabaseStats {
+    /// Percentage of chunks with an embedding (0-100)
+    pub fn embedding_coverage(&self) -> f64 {
+        coverage_percent(self.embedded_chunk_count, self.chunk_count)
+    }
+}
+
+/// Percentage of `part` over `total`, clamped to 0-100
+pub fn coverage_percent(part: i64, total: i64) -> f64 {
+    if total <= 0 {
+        return 0.0;
+    }
+
+    (part as f64 / total as f64 * 100.0).clamp(0.0, 100.0)
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/// Build a document from five consecutive columns starting at `start`
+/// (id, source, content_hash, metadata, created_at)
+fn document_from_row(row: &rusqlite::Row, start: usize) -> rusqlite::Result<Document> {
+    let metadata_json: String = row.get(start + 3)?;
+    let metadata = serde_json::from_str(&metadata_json).unwrap_or_default();
+
+    Ok(Document {
+        id: Some(row.get(start)?),
+        source: row.get(start + 1)?,
+        content_hash: row.get(start + 2)?,
+        metadata,
+        created_at: row.get(start + 4)?,
+    })
+}
+
+/// Compute the embedding cache key: sha256 of the model name and text
+fn embedding_cache_key(model: &str, text: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(text.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Convert a vector of f32 to bytes (little-endian)
+fn vector_to_bytes(vector: &[f32]) -> Vec<u8> {
+    encode_vector(vector, StoragePrecision::F32)
+}
+
+/// Encode a vector in the given storage precision (little-endian)
+fn encode_vector(vector: &[f32], precision: StoragePrecision) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(vector.len() * precision.bytes_per_value());
+    for value in vector {
+        match precision {
+            StoragePrecision::F32 => bytes.extend_from_slice(&value.to_le_bytes()),
+            StoragePrecision::F16 => bytes.extend_from_slice(&f16::from_f32(*value).to_le_bytes()),
+        }
+    }
+    bytes
+}
+
+/// Convert bytes to a vector of f32 (little-endian)
+///
+/// The storage precision is inferred from the blob length: two bytes per
+/// dimension is f16, anything else is read as f32.
+fn bytes_to_vector(bytes: &[u8], dimension: usize) -> Vec<f32> {
+    if dimension > 0 && bytes.len() == dimension * 2 {
+        return bytes
+            .chunks_exact(2)
+            .map(|chunk| f16::from_le_bytes([chunk[0], chunk[1]]).to_f32())
+            .collect();
+    }
+
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| {
+            let array: [u8; 4] = chunk.try_into().unwrap();
+            f32::from_le_bytes(array)
+        })
+        .collect()
+}
+
+/// Chunks joined with their documents, as read by [`sample_from_row`]
+const SAMPLE_SQL: &str = "SELECT c.id, c.document_id, c.chunk_index, c.content, c.token_count,
+            d.id, d.source, d.content_hash, d.metadata, d.created_at
+     FROM chunks c
+     JOIN documents d ON c.document_id = d.id
+     WHERE d.complete = 1";
+
+fn sample_from_row(row: &rusqlite::Row) -> rusqlite::Result<(Chunk, Document)> {
+    let metadata_json: String = row.get(8)?;
+
+    Ok((
+        Chunk {
+            id: Some(row.get(0)?),
+            document_id: row.get(1)?,
+            chunk_index: row.get(2)?,
+            content: row.get(3)?,
+            token_count: row.get(4)?,
+        },
+        Document {
+            id: Some(row.get(5)?),
+            source: row.get(6)?,
+            content_hash: row.get(7)?,
+            metadata: serde_json::from_str(&metadata_json).unwrap_or_default(),
+            created_at: row.get(9)?,
+        },
+    ))
+}
+
+/// Calculate cosine similarity between two vectors
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let magnitude_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let magnitude_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if magnitude_a == 0.0 || magnitude_b == 0.0 {
+        return 0.0;
+    }
+
+    dot_product / (magnitude_a * magnitude_b)
+}
+
+/// Cosine similarity with dot product and magnitudes accumulated in f64
+pub fn cosine_similarity_f64(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot_product: f64 = a.iter().zip(b.iter()).map(|(x, y)| (x * y) as f64).sum();
+    let magnitude_a: f64 = a.iter().map(|x| (x * x) as f64).sum::<f64>().sqrt();
+    let magnitude_b: f64 = b.iter().map(|x| (x * x) as f64).sum::<f64>().sqrt();
+
+    if magnitude_a == 0.0 || magnitude_b == 0.0 {
+        return 0.0;
+    }
+
+    (dot_product / (magnitude_a * magnitude_b)) as f32
+}
+
+/// Dot product of two vectors (0.0 if their lengths differ)
+///
+/// Equals the cosine similarity when both vectors have unit length.
+pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Dot product accumulated in f64
+pub fn dot_product_f64(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x * y) as f64)
+        .sum::<f64>() as f32
+}
+
+/// The vector scaled to unit length, or None for a zero vector
+pub fn l2_normalized(vector: &[f32]) -> Option<Vec<f32>> {
+    let magnitude = vector.iter().map(|x| (x * x) as f64).sum::<f64>().sqrt();
+    if magnitude == 0.0 || !magnitude.is_finite() {
+        return None;
+    }
+
+    Some(
+        vector
+            .iter()
+            .map(|x| (*x as f64 / magnitude) as f32)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_conversion() {
+        let original = vec![1.0, 2.5, -3.15, 0.0];
+        let bytes = vector_to_bytes(&original);
+        let converted = bytes_to_vector(&bytes, original.len());
+
+        assert_eq!(original.len(), converted.len());
+        for (a, b) in original.iter().zip(converted.iter()) {
+            assert!((a - b).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        let a = vec![1.0, 0.0, 0.0];
+        let b = vec![1.0, 0.0, 0.0];
+        assert!((cosine_similarity(&a, &b) - 1.0).abs() < 0.0001);
+
+        let a = vec![1.0, 0.0, 0.0];
+        let b = vec![0.0, 1.0, 0.0];
+        assert!(cosine_similarity(&a, &b).abs() < 0.0001);
+
+        let a = vec![1.0, 1.0];
+        let b = vec![1.0, 1.0];
+        assert!((cosine_similarity(&a, &b) - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_database_creation() {
+        let store = VectorStore::in_memory().unwrap();
+        let stats = store.get_stats().unwrap();
+
+        assert_eq!(stats.document_count, 0);
+        assert_eq!(stats.chunk_count, 0);
+        assert_eq!(stats.embedding_count, 0);
+    }
+
+    #[test]
+    fn test_document_operations() {
+        let mut store = VectorStore::in_memory().unwrap();
+
+        let doc = Document::new("test.txt".to_string(), "Hello world");
+        let doc_id = store.insert_document(&doc).unwrap();
+
+        assert!(doc_id > 0);
+
+        let retrieved = store.get_document(doc_id).unwrap().unwrap();
+        assert_eq!(retrieved.source, "test.txt");
+        assert_eq!(retrieved.content_hash, doc.content_hash);
+
+        let count = store.count_documents().unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_chunk_operations() {
+        let mut store = VectorStore::in_memory().unwrap();
+
+        let doc = Document::new("test.txt".to_string(), "Hello world");
+        let doc_id = store.insert_document(&doc).unwrap();
+
+        let chunk = Chunk::new(doc_id, 0, "Hello world".to_string());
+        let chunk_id = store.insert_chunk(&chunk).unwrap();
+
+        assert!(chunk_id > 0);
+
+        let retrieved = store.get_chunk(chunk_id).unwrap().unwrap();
+        assert_eq!(retrieved.content, "Hello world");
+        assert_eq!(retrieved.chunk_index, 0);
+
+        let chunks = store.get_chunks_for_document(doc_id).unwrap();
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    fn test_embedding_operations() {
+        let mut store = VectorStore::in_memory().unwrap();
+
+        let doc = Document::new("test.txt".to_string(), "Hello world");
+        let doc_id = store.insert_document(&doc).unwrap();
+
+        let chunk = Chunk::new(doc_id, 0, "Hello world".to_string());
+        let chunk_id = store.insert_chunk(&chunk).unwrap();
+
+        let vector = vec![0.1, 0.2, 0.3];
+        let embedding = Embedding::new(chunk_id, "test-model".to_string(), vector.clone());
+
+        store.upsert_embedding(&embedding).unwrap();
+
+        let retrieved = store.get_embedding(chunk_id).unwrap().unwrap();
+        assert_eq!(retrieved.model, "test-model");
+        assert_eq!(retrieved.dimension, 3);
+        assert_eq!(retrieved.vector, vector);
+
+        let bare_id = store
+            .insert_chunk(&Chunk::new(doc_id, 1, "No embedding".to_string()))
+            .unwrap();
+        let vectors = store.get_embedding_vectors(&[chunk_id, bare_id]).unwrap();
+        assert_eq!(vectors.len(), 1);
+        assert_eq!(vectors[&chunk_id], vector);
+    }
+
+    #[test]
+    fn test_search_similar() {
+        let mut store = VectorStore::in_memory().unwrap();
+
+        // Insert test data
+        let doc = Document::new("test.txt".to_string(), "Test document");
+        let doc_id = store.insert_document(&doc).unwrap();
+
+        let chunk1 = Chunk::new(doc_id, 0, "First chunk".to_string());
+        let chunk1_id = store.insert_chunk(&chunk1).unwrap();
+
+        let chunk2 = Chunk::new(doc_id, 1, "Second chunk".to_string());
+        let chunk2_id = store.insert_chunk(&chunk2).unwrap();
+
+        // Insert embeddings
+        let embedding1 = Embedding::new(chunk1_id, "model".to_string(), vec![1.0, 0.0, 0.0]);
+        store.upsert_embedding(&embedding1).unwrap();
+
+        let embedding2 = Embedding::new(chunk2_id, "model".to_string(), vec![0.0, 1.0, 0.0]);
+        store.upsert_embedding(&embedding2).unwrap();
+
+        // Search with query similar to first embedding
+        let query = vec![0.9, 0.1, 0.0];
+        let results = store.search_similar(&query, "model", 2, 0).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].chunk.content, "First chunk");
+        assert!(results[0].similarity > results[1].similarity);
+    }
+
+    #[test]
+    fn test_high_precision_scoring_changes_ordering() {
+        // Large opposing components swallow the small ones when summed in f32
+        let dimension = 768;
+        let query = vec![1.0f32; dimension];
+        let mut first = vec![1.0f32; dimension];
+        first[0] = 1e8;
+        first[dimension - 1] = -1e8;
+        let mut second = vec![0.5f32; dimension];
+        second[0] = 1e8;
+        second[1] = -1e8;
+
+        // f32 loses the small terms of `first` entirely and ranks `second` higher
+        assert!(cosine_similarity(&query, &first) < cosine_similarity(&query, &second));
+        // f64 keeps them, and `first` really is the closer vector
+        assert!(cosine_similarity_f64(&query, &first) > cosine_similarity_f64(&query, &second));
+
+        let mut store = VectorStore::in_memory()
+            .unwrap()
+            .with_high_precision_scoring(true);
+        let doc_id = store
+            .insert_document(&Document::new("doc.txt".to_string(), "Document"))
+            .unwrap();
+        for (idx, vector) in [first, second].into_iter().enumerate() {
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, idx, format!("Chunk {}", idx)))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(chunk_id, "model".to_string(), vector))
+                .unwrap();
+        }
+
+        let results = store.search_similar(&query, "model", 2, 0).unwrap();
+        assert_eq!(results[0].chunk.chunk_index, 0);
+    }
+
+    #[test]
+    fn test_hybrid_search_favors_exact_terms() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let mut ids = Vec::new();
+        for (source, content, vector) in [
+            ("a.log", "request failed with error E1234", vec![0.8, 0.6]),
+            (
+                "b.md",
+                "general notes about failed requests",
+                vec![1.0, 0.0],
+            ),
+            ("c.md", "unrelated gardening tips", vec![0.0, 1.0]),
+        ] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), content))
+                .unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, content.to_string()))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(chunk_id, "model".to_string(), vector))
+                .unwrap();
+            ids.push(doc_id);
+        }
+
+        let query = [1.0, 0.0];
+        let vector_only = store
+            .hybrid_search("E1234", &query, "model", 3, 1.0)
+            .unwrap();
+        assert_eq!(vector_only[0].document.source, "b.md");
+
+        let blended = store
+            .hybrid_search("E1234", &query, "model", 3, 0.5)
+            .unwrap();
+        assert_eq!(blended[0].document.source, "a.log");
+        assert!(blended.iter().all(|r| (0.0..=1.0).contains(&r.similarity)));
+
+        // Deleting a document removes its chunks from the keyword index too
+        store.delete_document(ids[0]).unwrap();
+        assert!(store.keyword_scores("E1234").unwrap().is_empty());
+        assert_eq!(store.keyword_scores("C++ notes!").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_model_version_change_warns() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let old = "sha256:aaaaaaaaaaaaaaaaaaaa";
+        let new = "sha256:bbbbbbbbbbbbbbbbbbbb";
+
+        assert!(store.check_model_version("model", old).unwrap().is_none());
+        assert_eq!(store.model_version("model").unwrap().as_deref(), Some(old));
+        assert!(store.check_model_version("model", old).unwrap().is_none());
+
+        let warning = store.check_model_version("model", new).unwrap().unwrap();
+        assert!(warning.contains("stale"));
+        assert!(warning.contains("aaaaaaaaaaaa -> bbbbbbbbbbbb"));
+        // The warning repeats until the new version is recorded
+        assert!(store.check_model_version("model", new).unwrap().is_some());
+
+        store.record_model_version("model", new).unwrap();
+        assert!(store.check_model_version("model", new).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_incremental_vacuum_reclaims_pages_in_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = VectorStore::new(dir.path().join("vectors.db")).unwrap();
+
+        for doc in 0..20 {
+            let source = format!("doc{}.txt", doc);
+            let doc_id = store
+                .insert_document(&Document::new(source.clone(), &source))
+                .unwrap();
+            for idx in 0..10 {
+                let chunk_id = store
+                    .insert_chunk(&Chunk::new(doc_id, idx, "x".repeat(500)))
+                    .unwrap();
+                store
+                    .upsert_embedding(&Embedding::new(
+                        chunk_id,
+                        "test-model".to_string(),
+                        vec![0.5; 256],
+                    ))
+                    .unwrap();
+            }
+        }
+
+        assert!(store.enable_incremental_vacuum().unwrap());
+        assert!(store.is_incremental_vacuum_enabled().unwrap());
+        assert!(!store.enable_incremental_vacuum().unwrap());
+
+        for doc in 0..20 {
+            store
+                .delete_document_by_source(&format!("doc{}.txt", doc))
+                .unwrap();
+        }
+        let free = store.freelist_count().unwrap();
+        assert!(free > 10);
+
+        assert_eq!(store.incremental_vacuum(10).unwrap(), 10);
+        assert_eq!(store.freelist_count().unwrap(), free - 10);
+        assert_eq!(store.incremental_vacuum(0).unwrap(), free - 10);
+        assert_eq!(store.freelist_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_search_history_is_pruned() {
+        let mut store = VectorStore::in_memory().unwrap();
+        for n in 0..5 {
+            store.record_search(&format!("query {}", n), n, 3).unwrap();
+        }
+
+        let history = store.recent_searches(10).unwrap();
+        let queries: Vec<&str> = history.iter().map(|e| e.query.as_str()).collect();
+        assert_eq!(queries, vec!["query 4", "query 3", "query 2"]);
+        assert_eq!(history[0].result_count, 4);
+        assert_eq!(store.recent_searches(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bulk_mode_restores_indices() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = VectorStore::new(dir.path().join("vectors.db")).unwrap();
+        let index_count = |store: &VectorStore| -> i64 {
+            store
+                .conn
+                .query_row(
+                    "SELECT COUNT(*) FROM sqlite_master
+                     WHERE type = 'index' AND name IN ('idx_chunks_document', 'idx_embeddings_model')",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(index_count(&store), 2);
+
+        store.begin_bulk_mode().unwrap();
+        assert_eq!(index_count(&store), 0);
+
+        store
+            .in_transaction(|store| {
+                for doc in 0..50 {
+                    let source = format!("doc{}.txt", doc);
+                    let doc_id = store.insert_document(&Document::new(source.clone(), &source))?;
+                    for idx in 0..20 {
+                        let chunk_id = store.insert_chunk(&Chunk::new(
+                            doc_id,
+                            idx,
+                            format!("Chunk {} of {}", idx, source),
+                        ))?;
+                        store.upsert_embedding(&Embedding::new(
+                            chunk_id,
+                            "test-model".to_string(),
+                            vec![doc as f32, idx as f32, 1.0],
+                        ))?;
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        store.end_bulk_mode().unwrap();
+        assert_eq!(index_count(&store), 2);
+
+        let integrity: String = store
+            .conn
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(integrity, "ok");
+        let synchronous: i64 = store
+            .conn
+            .query_row("PRAGMA synchronous", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(synchronous, 1); // NORMAL
+
+        assert_eq!(store.count_chunks().unwrap(), 1000);
+        assert_eq!(store.count_chunks_for_model("test-model").unwrap(), 1000);
+    }
+
+    #[test]
+    fn test_normalized_dot_product_matches_cosine() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        let mut random_vector =
+            || -> Vec<f32> { (0..768).map(|_| rng.f32() * 2.0 - 1.0).collect() };
+
+        for _ in 0..200 {
+            let a = random_vector();
+            let b = random_vector();
+            let dot = dot_product(&l2_normalized(&a).unwrap(), &l2_normalized(&b).unwrap());
+            assert!((dot - cosine_similarity(&a, &b)).abs() < 1e-5);
+        }
+        assert_eq!(l2_normalized(&[0.0, 0.0]), None);
+
+        // Stored normalized, searched as dot products, scored the same as before
+        let query = random_vector();
+        let vectors: Vec<Vec<f32>> = (0..20).map(|_| random_vector()).collect();
+        let mut plain = VectorStore::in_memory().unwrap();
+        let mut normalized = VectorStore::in_memory()
+            .unwrap()
+            .with_normalized_embeddings(true);
+        for store in [&mut plain, &mut normalized] {
+            let doc_id = store
+                .insert_document(&Document::new("doc.txt".to_string(), "Document"))
+                .unwrap();
+            for (idx, vector) in vectors.iter().enumerate() {
+                let chunk_id = store
+                    .insert_chunk(&Chunk::new(doc_id, idx, format!("Chunk {}", idx)))
+                    .unwrap();
+                store
+                    .upsert_embedding(&Embedding::new(
+                        chunk_id,
+                        "test-model".to_string(),
+                        vector.clone(),
+                    ))
+                    .unwrap();
+            }
+        }
+
+        let stored = normalized.get_embedding(1).unwrap().unwrap();
+        let magnitude: f32 = stored.vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((magnitude - 1.0).abs() < 1e-5);
+
+        let expected = plain.search_similar(&query, "test-model", 20, 0).unwrap();
+        let actual = normalized
+            .search_similar(&query, "test-model", 20, 0)
+            .unwrap();
+        for (e, a) in expected.iter().zip(&actual) {
+            assert_eq!(e.chunk.id, a.chunk.id);
+            assert!((e.similarity - a.similarity).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_search_similar_pages_are_stable() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("test.txt".to_string(), "Test document"))
+            .unwrap();
+
+        // Pairs of identical vectors produce similarity ties
+        let mut chunk_ids = Vec::new();
+        for idx in 0..6 {
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, idx, format!("chunk {}", idx)))
+                .unwrap();
+            let vector = vec![1.0, (idx / 2) as f32];
+            store
+                .upsert_embedding(&Embedding::new(chunk_id, "model".to_string(), vector))
+                .unwrap();
+            chunk_ids.push(chunk_id);
+        }
+
+        let query = [1.0, 0.0];
+        let all: Vec<i64> = store
+            .search_similar(&query, "model", 6, 0)
+            .unwrap()
+            .iter()
+            .filter_map(|r| r.chunk.id)
+            .collect();
+        assert_eq!(all, chunk_ids);
+
+        let mut paged = Vec::new();
+        for offset in (0..6).step_by(2) {
+            let page = store.search_similar(&query, "model", 2, offset).unwrap();
+            paged.extend(page.iter().filter_map(|r| r.chunk.id));
+        }
+        assert_eq!(paged, all);
+        assert!(
+            store
+                .search_similar(&query, "model", 2, 10)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_sample_chunks() {
+        let mut store = VectorStore::in_memory().unwrap();
+        assert!(store.sample_chunks(3).unwrap().is_empty());
+
+        for idx in 0..5 {
+            let doc_id = store
+                .insert_document(&Document::new(format!("doc{}.txt", idx), &idx.to_string()))
+                .unwrap();
+            store
+                .insert_chunk(&Chunk::new(doc_id, 0, format!("Chunk {}", idx)))
+                .unwrap();
+        }
+
+        let samples = store.sample_chunks(3).unwrap();
+        assert_eq!(samples.len(), 3);
+        assert!(
+            samples
+                .iter()
+                .all(|(chunk, doc)| Some(chunk.document_id) == doc.id)
+        );
+        assert_eq!(store.sample_chunks(10).unwrap().len(), 5);
+
+        let first = store.sample_chunks_seeded(3, 42).unwrap();
+        let second = store.sample_chunks_seeded(3, 42).unwrap();
+        assert_eq!(first.len(), 3);
+        let ids = |samples: &[(Chunk, Document)]| -> Vec<Option<i64>> {
+            samples.iter().map(|(chunk, _)| chunk.id).collect()
+        };
+        assert_eq!(ids(&first), ids(&second));
+    }
+
+    #[test]
+    fn test_delete_document_cascades() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = VectorStore::new(dir.path().join("vectors.db")).unwrap();
+
+        let mut doc_ids = Vec::new();
+        for source in ["a.txt", "b.txt"] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            for idx in 0..2 {
+                let chunk_id = store
+                    .insert_chunk(&Chunk::new(doc_id, idx, format!("{} {}", source, idx)))
+                    .unwrap();
+                store
+                    .upsert_embedding(&Embedding::new(
+                        chunk_id,
+                        "test-model".to_string(),
+                        vec![1.0, idx as f32],
+                    ))
+                    .unwrap();
+            }
+            doc_ids.push(doc_id);
+        }
+
+        assert!(store.delete_document(doc_ids[0]).unwrap());
+        assert!(!store.delete_document(doc_ids[0]).unwrap());
+        assert_eq!(store.count_chunks().unwrap(), 2);
+        assert_eq!(store.count_embeddings().unwrap(), 2);
+
+        assert_eq!(store.delete_document_by_source("b.txt").unwrap(), 1);
+        assert_eq!(store.delete_document_by_source("b.txt").unwrap(), 0);
+        assert_eq!(store.count_documents().unwrap(), 0);
+        assert_eq!(store.count_chunks().unwrap(), 0);
+        assert_eq!(store.count_embeddings().unwrap(), 0);
+    }
+
+    fn embedded_chunk(idx: usize, vector: Vec<f32>) -> EmbeddedChunk {
+        EmbeddedChunk {
+            content: format!("chunk {}", idx),
+            token_count: 3,
+            vector,
+        }
+    }
+
+    #[test]
+    fn test_insert_document_with_chunks_commits_in_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vectors.db");
+        let mut store = VectorStore::new(&path).unwrap();
+        let observer = VectorStore::new(&path).unwrap();
+
+        let chunks: Vec<EmbeddedChunk> = (0..10)
+            .map(|idx| embedded_chunk(idx, vec![1.0, idx as f32]))
+            .collect();
+        let mut version = observer.data_version().unwrap();
+        let mut commits = Vec::new();
+        let doc_id = store
+            .insert_document_with_chunks(
+                &Document::new("big.txt".to_string(), "big"),
+                &chunks,
+                "test-model",
+                4,
+                |stored| {
+                    // Each batch is visible to other connections as soon as it commits
+                    let current = observer.data_version().unwrap();
+                    assert_ne!(current, version);
+                    version = current;
+                    assert_eq!(observer.count_chunks().unwrap(), stored as i64);
+                    commits.push(stored);
+                },
+            )
+            .unwrap();
+
+        assert_eq!(commits, vec![4, 8, 10]);
+        let stored = store.get_chunks_for_document(doc_id).unwrap();
+        let indices: Vec<usize> = stored.iter().map(|chunk| chunk.chunk_index).collect();
+        assert_eq!(indices, (0..10).collect::<Vec<_>>());
+        assert!(stored.iter().all(|chunk| chunk.token_count == Some(3)));
+        assert_eq!(store.count_embeddings().unwrap(), 10);
+
+        let result = store.insert_document_with_chunks(
+            &Document::new("other.txt".to_string(), "other"),
+            &chunks,
+            "test-model",
+            0,
+            |_| {},
+        );
+        assert!(matches!(result, Err(VectDbError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_interrupted_document_is_incomplete_and_replaced() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let document = Document::new("big.txt".to_string(), "big");
+
+        // The second batch holds a NaN, which is rejected after the first batch committed
+        let mut chunks: Vec<EmbeddedChunk> = (0..4)
+            .map(|idx| embedded_chunk(idx, vec![1.0, idx as f32]))
+            .collect();
+        chunks[3].vector[0] = f32::NAN;
+        let result = store.insert_document_with_chunks(&document, &chunks, "test-model", 2, |_| {});
+        assert!(result.is_err());
+        assert_eq!(store.count_chunks().unwrap(), 2);
+
+        // Every stored chunk is embedded, yet the document is reported and not a duplicate
+        assert!(
+            store
+                .get_document_by_hash(&document.content_hash)
+                .unwrap()
+                .is_none()
+        );
+        let incomplete = store
+            .documents_with_incomplete_embeddings("test-model")
+            .unwrap();
+        assert_eq!(incomplete.len(), 1);
+        assert_eq!((incomplete[0].1, incomplete[0].2), (2, 2));
+
+        // Ingesting the same content again replaces the truncated copy
+        chunks[3].vector[0] = 1.0;
+        let doc_id = store
+            .insert_document_with_chunks(&document, &chunks, "test-model", 2, |_| {})
+            .unwrap();
+        assert_eq!(store.count_documents().unwrap(), 1);
+        assert_eq!(store.count_chunks().unwrap(), 4);
+        assert_eq!(
+            store
+                .get_document_by_hash(&document.content_hash)
+                .unwrap()
+                .and_then(|d| d.id),
+            Some(doc_id)
+        );
+        assert!(
+            store
+                .documents_with_incomplete_embeddings("test-model")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_update_chunk_content_drops_stale_embedding() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("scan.pdf".to_string(), "scan"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "Tbe qnick brown f0x".to_string()))
+            .unwrap();
+        let other_id = store
+            .insert_chunk(&Chunk::new(doc_id, 1, "untouched".to_string()))
+            .unwrap();
+        for id in [chunk_id, other_id] {
+            store
+                .upsert_embedding(&Embedding::new(
+                    id,
+                    "test-model".to_string(),
+                    vec![1.0, 0.0],
+                ))
+                .unwrap();
+        }
+
+        let corrected = "The quick brown fox jumps over the lazy dog";
+        store.update_chunk_content(chunk_id, corrected, 9).unwrap();
+
+        let chunk = store.get_chunk(chunk_id).unwrap().unwrap();
+        assert_eq!(chunk.content, corrected);
+        assert_eq!(chunk.token_count, Some(9));
+        assert!(store.get_embedding(chunk_id).unwrap().is_none());
+        assert!(store.get_embedding(other_id).unwrap().is_some());
+        assert_eq!(store.count_chunks_for_model("test-model").unwrap(), 1);
+
+        // The keyword index follows the new text
+        assert!(
+            store
+                .keyword_scores("lazy dog")
+                .unwrap()
+                .contains_key(&chunk_id)
+        );
+        assert!(store.keyword_scores("qnick").unwrap().is_empty());
+
+        assert!(matches!(
+            store.update_chunk_content(999, "missing", 1),
+            Err(VectDbError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_preview_deletion_counts_without_deleting() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let mut doc_ids = Vec::new();
+        for (source, chunks) in [("a.txt", 3), ("b.txt", 1), ("b.txt", 2)] {
+            let doc_id = store
+                .insert_document(&Document::new(
+                    source.to_string(),
+                    &format!("{}{}", source, chunks),
+                ))
+                .unwrap();
+            for idx in 0..chunks {
+                let chunk_id = store
+                    .insert_chunk(&Chunk::new(doc_id, idx, format!("{} {}", source, idx)))
+                    .unwrap();
+                // Only the first chunk of each document is embedded
+                if idx == 0 {
+                    store
+                        .upsert_embedding(&Embedding::new(
+                            chunk_id,
+                            "test-model".to_string(),
+                            vec![1.0, 0.0],
+                        ))
+                        .unwrap();
+                }
+            }
+            doc_ids.push(doc_id);
+        }
+
+        let preview = store.preview_delete_document(doc_ids[0]).unwrap();
+        assert_eq!(preview.documents, vec![(doc_ids[0], "a.txt".to_string())]);
+        assert_eq!(preview.chunk_count, 3);
+        assert_eq!(preview.embedding_count, 1);
+
+        let preview = store.preview_delete_document_by_source("b.txt").unwrap();
+        assert_eq!(
+            preview.documents,
+            vec![
+                (doc_ids[1], "b.txt".to_string()),
+                (doc_ids[2], "b.txt".to_string())
+            ]
+        );
+        assert_eq!(preview.chunk_count, 3);
+        assert_eq!(preview.embedding_count, 2);
+
+        assert!(
+            store
+                .preview_delete_document(999)
+                .unwrap()
+                .documents
+                .is_empty()
+        );
+        assert_eq!(store.count_documents().unwrap(), 3);
+        assert_eq!(store.count_chunks().unwrap(), 6);
+        assert_eq!(store.count_embeddings().unwrap(), 3);
+
+        // Only an unfinished document is up for pruning
+        assert!(store.preview_prune().unwrap().documents.is_empty());
+        let partial = store
+            .begin_document(&Document::new("partial.txt".to_string(), "partial"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(partial, 0, "partial 0".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_id,
+                "test-model".to_string(),
+                vec![0.0, 1.0],
+            ))
+            .unwrap();
+        let preview = store.preview_prune().unwrap();
+        assert_eq!(
+            preview.documents,
+            vec![(partial, "partial.txt".to_string())]
+        );
+        assert_eq!(preview.chunk_count, 1);
+        assert_eq!(preview.embedding_count, 1);
+        assert_eq!(store.count_documents().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_documents_with_incomplete_embeddings() {
+        let mut store = VectorStore::in_memory().unwrap();
+
+        let doc = Document::new("partial.txt".to_string(), "Partial document");
+        let doc_id = store.insert_document(&doc).unwrap();
+        let chunk1_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "First".to_string()))
+            .unwrap();
+        store
+            .insert_chunk(&Chunk::new(doc_id, 1, "Second".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&Embedding::new(chunk1_id, "model".to_string(), vec![1.0]))
+            .unwrap();
+
+        let complete = Document::new("complete.txt".to_string(), "Complete document");
+        let complete_id = store.insert_document(&complete).unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(complete_id, 0, "Only".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&Embedding::new(chunk_id, "model".to_string(), vec![1.0]))
+            .unwrap();
+
+        let incomplete = store.documents_with_incomplete_embeddings("model").unwrap();
+        assert_eq!(incomplete.len(), 1);
+
+        let (document, embedded, total) = &incomplete[0];
+        assert_eq!(document.source, "partial.txt");
+        assert_eq!(*embedded, 1);
+        assert_eq!(*total, 2);
+    }
+
+    #[test]
+    fn test_explain_search_plan() {
+        let store = VectorStore::in_memory().unwrap();
+        let plan = store.explain_search_plan("model").unwrap();
 
-                Ok((
-                    similarity,
-                    SearchResult {
-                        chunk,
-                        document,
-                        similarity,
-                    },
-                ))
-            })?
-            .collect::<std::result::Result<Vec<_>, _>>()?;
+        assert!(!plan.is_empty());
+        assert!(plan.iter().any(|line| line.contains("embeddings")));
+    }
+
+    #[test]
+    fn test_embedding_cache() {
+        let mut store = VectorStore::in_memory().unwrap();
+
+        assert!(
+            store
+                .get_cached_embedding("model", "text")
+                .unwrap()
+                .is_none()
+        );
 
-        // Sort by similarity (descending) and take top k
-        results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-        results.truncate(top_k);
+        store
+            .put_cached_embedding("model", "text", &[0.5, 0.25])
+            .unwrap();
 
-        let search_results = results.into_iter().map(|(_, result)| result).collect();
+        assert_eq!(
+            store.get_cached_embedding("model", "text").unwrap(),
+            Some(vec![0.5, 0.25])
+        );
+        assert!(
+            store
+                .get_cached_embedding("other", "text")
+                .unwrap()
+                .is_none()
+        );
 
-        Ok(search_results)
+        assert_eq!(store.clear_embedding_cache().unwrap(), 1);
+        assert_eq!(store.count_cached_embeddings().unwrap(), 0);
     }
 
-    // ============================================================================
-    // Database Maintenance
-    // ============================================================================
+    #[test]
+    fn test_list_documents_sorted_by_source() {
+        let mut store = VectorStore::in_memory().unwrap();
 
-    /// Run VACUUM to optimize database size
-    pub fn vacuum(&self) -> Result<()> {
-        info!("Running VACUUM on database");
-        self.conn.execute("VACUUM", [])?;
-        Ok(())
+        for source in ["charlie.txt", "alpha.txt", "bravo.txt"] {
+            let doc = Document::new(source.to_string(), source);
+            let doc_id = store.insert_document(&doc).unwrap();
+            store
+                .insert_chunk(&Chunk::new(doc_id, 0, source.to_string()))
+                .unwrap();
+        }
+
+        let documents = store
+            .list_documents(10, 0, DocumentSort::Source, SortOrder::Asc)
+            .unwrap();
+        let sources: Vec<&str> = documents
+            .iter()
+            .map(|d| d.document.source.as_str())
+            .collect();
+
+        assert_eq!(sources, vec!["alpha.txt", "bravo.txt", "charlie.txt"]);
+        assert!(documents.iter().all(|d| d.chunk_count == 1));
+
+        let page = store
+            .list_documents(1, 1, DocumentSort::Source, SortOrder::Desc)
+            .unwrap();
+        assert_eq!(page[0].document.source, "bravo.txt");
     }
 
-    /// Run ANALYZE to update query optimizer statistics
-    pub fn analyze(&self) -> Result<()> {
-        info!("Running ANALYZE on database");
-        self.conn.execute("ANALYZE", [])?;
-        Ok(())
+    #[test]
+    fn test_document_sort_parsing() {
+        assert_eq!(
+            "chunks".parse::<DocumentSort>().unwrap(),
+            DocumentSort::Chunks
+        );
+        assert_eq!("asc".parse::<SortOrder>().unwrap(), SortOrder::Asc);
+        assert!("size".parse::<DocumentSort>().is_err());
+        assert!("up".parse::<SortOrder>().is_err());
     }
 
-    /// Get database statistics
-    pub fn get_stats(&self) -> Result<DatabaseStats> {
-        let doc_count = self.count_documents()?;
-        let chunk_count = self.count_chunks()?;
-        let embedding_count = self.count_embeddings()?;
+    #[test]
+    fn test_coverage_counts_embedded_chunks_per_model() {
+        let mut store = VectorStore::in_memory().unwrap();
 
-        // Get database file size
-        let page_count: i64 = self
-            .conn
-            .query_row("PRAGMA page_count", [], |row| row.get(0))?;
-        let page_size: i64 = self
-            .conn
-            .query_row("PRAGMA page_size", [], |row| row.get(0))?;
-        let db_size_bytes = page_count * page_size;
+        let doc = Document::new("test.txt".to_string(), "Test document");
+        let doc_id = store.insert_document(&doc).unwrap();
 
-        Ok(DatabaseStats {
-            document_count: doc_count,
-            chunk_count,
-            embedding_count,
-            db_size_bytes,
-        })
-    }
-}
+        let mut chunk_ids = Vec::new();
+        for idx in 0..2 {
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, idx, format!("Chunk {}", idx)))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(chunk_id, "model-a".to_string(), vec![1.0]))
+                .unwrap();
+            chunk_ids.push(chunk_id);
+        }
+        store
+            .insert_chunk(&Chunk::new(doc_id, 2, "Unembedded".to_string()))
+            .unwrap();
 
-/// Database statistics
-#[derive(Debug, Clone)]
-pub struct DatabaseStats {
-    pub document_count: i64,
-    pub chunk_count: i64,
-    pub embedding_count: i64,
-    pub db_size_bytes: i64,
-}
+        // A chunk holds one embedding: re-embedding it with another model replaces it
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_ids[1],
+                "model-b".to_string(),
+                vec![1.0],
+            ))
+            .unwrap();
 
-// ============================================================================
-// Helper Functions
-// ============================================================================
+        let stats = store.get_stats().unwrap();
+        assert_eq!(stats.embedding_count, 2);
+        assert_eq!(stats.embedded_chunk_count, 2);
+        assert!((stats.embedding_coverage() - 200.0 / 3.0).abs() < 0.01);
 
-/// Convert a vector of f32 to bytes (little-endian)
-fn vector_to_bytes(vector: &[f32]) -> Vec<u8> {
-    let mut bytes = Vec::with_capacity(vector.len() * 4);
-    for value in vector {
-        bytes.extend_from_slice(&value.to_le_bytes());
+        let by_model = store.count_chunks_by_model().unwrap();
+        assert_eq!(
+            by_model,
+            vec![("model-a".to_string(), 1), ("model-b".to_string(), 1)]
+        );
+        for (model, count) in &by_model {
+            assert_eq!(store.count_chunks_for_model(model).unwrap(), *count);
+        }
+        assert_eq!(coverage_percent(5, 3), 100.0);
     }
-    bytes
-}
 
-/// Convert bytes to a vector of f32 (little-endian)
-fn bytes_to_vector(bytes: &[u8]) -> Vec<f32> {
-    bytes
-        .chunks_exact(4)
-        .map(|chunk| {
-            let array: [u8; 4] = chunk.try_into().unwrap();
-            f32::from_le_bytes(array)
-        })
-        .collect()
-}
+    #[test]
+    fn test_model_dimension_fixed_by_first_embedding() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("doc.txt".to_string(), "Document"))
+            .unwrap();
+        let mut results = Vec::new();
+        for (idx, dimension) in [(0, 768), (1, 384), (2, 384)] {
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, idx, format!("Chunk {}", idx)))
+                .unwrap();
+            results.push(store.upsert_embedding(&Embedding::new(
+                chunk_id,
+                "nomic-embed-text".to_string(),
+                vec![0.5; dimension],
+            )));
+        }
 
-/// Calculate cosine similarity between two vectors
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    if a.len() != b.len() {
-        return 0.0;
-    }
+        // The first embedding set the size; later 384-dim vectors are refused
+        assert!(results[0].is_ok());
+        for result in &results[1..] {
+            let err = result.as_ref().unwrap_err();
+            assert!(matches!(err, VectDbError::InvalidInput(_)));
+            assert!(err.to_string().contains("384 dimensions"));
+        }
+        assert_eq!(store.count_embeddings().unwrap(), 1);
+        assert_eq!(
+            store.model_dimension("nomic-embed-text").unwrap(),
+            Some(768)
+        );
+        assert_eq!(store.model_dimension("other").unwrap(), None);
 
-    let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let magnitude_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let magnitude_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        // A 384-dim query is an error, not a page of zero scores
+        let err = store
+            .search_similar(&[0.5; 384], "nomic-embed-text", 10, 0)
+            .unwrap_err();
+        assert!(matches!(err, VectDbError::InvalidInput(_)));
+        assert!(err.to_string().contains("have 768"));
 
-    if magnitude_a == 0.0 || magnitude_b == 0.0 {
-        return 0.0;
+        store.reset_model_dimension("nomic-embed-text").unwrap();
+        assert_eq!(store.model_dimension("nomic-embed-text").unwrap(), None);
     }
 
-    dot_product / (magnitude_a * magnitude_b)
-}
+    #[test]
+    fn test_model_dimension_forgotten_with_last_embedding() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("doc.txt".to_string(), "Document"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "Chunk".to_string()))
+            .unwrap();
+        let embed = |store: &mut VectorStore, model: &str, dimension: usize| {
+            store.upsert_embedding(&Embedding::new(
+                chunk_id,
+                model.to_string(),
+                vec![0.5; dimension],
+            ))
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // Moving the only embedding to another model releases the old model's size
+        embed(&mut store, "model-a", 4).unwrap();
+        embed(&mut store, "model-b", 8).unwrap();
+        assert_eq!(
+            store.model_dimensions().unwrap(),
+            vec![("model-b".to_string(), 8)]
+        );
+        embed(&mut store, "model-a", 16).unwrap();
+        assert_eq!(store.model_dimension("model-a").unwrap(), Some(16));
+
+        // Deleting the document removes the last embedding of the model
+        store.delete_document(doc_id).unwrap();
+        assert!(store.model_dimensions().unwrap().is_empty());
+    }
 
     #[test]
-    fn test_vector_conversion() {
-        let original = vec![1.0, 2.5, -3.15, 0.0];
-        let bytes = vector_to_bytes(&original);
-        let converted = bytes_to_vector(&bytes);
+    fn test_deadline_interrupts_scan() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("doc.txt".to_string(), "Document"))
+            .unwrap();
+        for idx in 0..200 {
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, idx, format!("Chunk {}", idx)))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(chunk_id, "model".to_string(), vec![0.5; 8]))
+                .unwrap();
+        }
 
-        assert_eq!(original.len(), converted.len());
-        for (a, b) in original.iter().zip(converted.iter()) {
-            assert!((a - b).abs() < 0.0001);
+        // The scan in Rust steps through every row, so the handler gets to run
+        let store = store.with_ann(false);
+        store.set_deadline(Some(Instant::now()));
+        let err = store.search_similar(&[0.5; 8], "model", 5, 0).unwrap_err();
+        assert!(is_interrupted(&err));
+
+        store.set_deadline(None);
+        assert_eq!(
+            store
+                .search_similar(&[0.5; 8], "model", 5, 0)
+                .unwrap()
+                .len(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_ann_matches_brute_force() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("doc.txt".to_string(), "Document"))
+            .unwrap();
+        let mut rng = fastrand::Rng::with_seed(7);
+        let mut random_vector = || -> Vec<f32> { (0..32).map(|_| rng.f32() * 2.0 - 1.0).collect() };
+        for idx in 0..1000 {
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, idx, format!("Chunk {}", idx)))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(
+                    chunk_id,
+                    "model".to_string(),
+                    random_vector(),
+                ))
+                .unwrap();
         }
+        let query = random_vector();
+
+        let ids = |results: Vec<SearchResult>| -> Vec<i64> {
+            results.iter().map(|r| r.chunk.id.unwrap()).collect()
+        };
+        let ann = ids(store.search_similar(&query, "model", 10, 0).unwrap());
+        assert_eq!(store.rows_scanned(), 20);
+        let store = store.with_ann(false);
+        let brute_force = ids(store.search_similar(&query, "model", 10, 0).unwrap());
+        assert_eq!(store.rows_scanned(), 1000);
+
+        assert_eq!(ann.len(), 10);
+        let overlap = ann.iter().filter(|id| brute_force.contains(id)).count();
+        assert!(overlap >= 9, "top-10 overlap {} of 10", overlap);
+
+        // Deleting the document removes its rows from the index too
+        let mut store = store;
+        store.delete_document(doc_id).unwrap();
+        let indexed: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM vec_chunks_32", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(indexed, 0);
     }
 
     #[test]
-    fn test_cosine_similarity() {
-        let a = vec![1.0, 0.0, 0.0];
-        let b = vec![1.0, 0.0, 0.0];
-        assert!((cosine_similarity(&a, &b) - 1.0).abs() < 0.0001);
+    fn test_rolled_back_vec_table_is_recreated() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("doc.txt".to_string(), "Document"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "Chunk".to_string()))
+            .unwrap();
+        let embedding = Embedding::new(chunk_id, "model".to_string(), vec![1.0, 0.0, 0.0]);
 
-        let a = vec![1.0, 0.0, 0.0];
-        let b = vec![0.0, 1.0, 0.0];
-        assert!(cosine_similarity(&a, &b).abs() < 0.0001);
+        let failed: Result<()> = store.in_transaction(|store| {
+            store.upsert_embedding(&embedding)?;
+            Err(VectDbError::Other("abort".to_string()))
+        });
+        assert!(failed.is_err());
 
-        let a = vec![1.0, 1.0];
-        let b = vec![1.0, 1.0];
-        assert!((cosine_similarity(&a, &b) - 1.0).abs() < 0.0001);
+        store.upsert_embedding(&embedding).unwrap();
+        let results = store
+            .search_similar(&[1.0, 0.0, 0.0], "model", 1, 0)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(store.rows_scanned(), 1);
     }
 
     #[test]
-    fn test_database_creation() {
-        let store = VectorStore::in_memory().unwrap();
-        let stats = store.get_stats().unwrap();
+    fn test_search_rejects_mismatched_dimension() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("doc.txt".to_string(), "Document"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "Chunk".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_id,
+                "model-x".to_string(),
+                vec![0.5; 8],
+            ))
+            .unwrap();
 
-        assert_eq!(stats.document_count, 0);
-        assert_eq!(stats.chunk_count, 0);
-        assert_eq!(stats.embedding_count, 0);
+        let err = store
+            .search_similar(&[0.5; 4], "model-x", 10, 0)
+            .unwrap_err();
+        assert!(matches!(err, VectDbError::InvalidInput(_)));
+        assert!(err.to_string().contains("4 dimensions"));
+        assert!(err.to_string().contains("have 8"));
+
+        let err = store
+            .search_similar(&[0.5; 4], "model-y", 10, 0)
+            .unwrap_err();
+        assert!(err.to_string().contains("model-x"));
+
+        let err = store
+            .search_within_documents(&[0.5; 4], "model-x", 10, &[doc_id])
+            .unwrap_err();
+        assert!(matches!(err, VectDbError::InvalidInput(_)));
+
+        assert_eq!(
+            store
+                .search_similar(&[0.5; 8], "model-x", 10, 0)
+                .unwrap()
+                .len(),
+            1
+        );
     }
 
     #[test]
-    fn test_document_operations() {
+    fn test_search_within_documents() {
         let mut store = VectorStore::in_memory().unwrap();
 
-        let doc = Document::new("test.txt".to_string(), "Hello world");
-        let doc_id = store.insert_document(&doc).unwrap();
+        let mut doc_ids = Vec::new();
+        for idx in 0..3 {
+            let doc = Document::new(format!("doc{}.txt", idx), &format!("Document {}", idx));
+            let doc_id = store.insert_document(&doc).unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, format!("Chunk {}", idx)))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(
+                    chunk_id,
+                    "test-model".to_string(),
+                    vec![1.0, idx as f32],
+                ))
+                .unwrap();
+            doc_ids.push(doc_id);
+        }
 
-        assert!(doc_id > 0);
+        let scoped = [doc_ids[0], doc_ids[2]];
+        let results = store
+            .search_within_documents(&[1.0, 0.0], "test-model", 10, &scoped)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(
+            results
+                .iter()
+                .all(|r| scoped.contains(&r.document.id.unwrap()))
+        );
+        assert_eq!(results[0].document.id, Some(doc_ids[0]));
 
-        let retrieved = store.get_document(doc_id).unwrap().unwrap();
-        assert_eq!(retrieved.source, "test.txt");
-        assert_eq!(retrieved.content_hash, doc.content_hash);
+        // More ids than fit in one query are batched
+        let many: Vec<i64> = (1000..3000).chain([doc_ids[1]]).collect();
+        let results = store
+            .search_within_documents(&[1.0, 0.0], "test-model", 10, &many)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.id, Some(doc_ids[1]));
 
-        let count = store.count_documents().unwrap();
-        assert_eq!(count, 1);
+        assert!(
+            store
+                .search_within_documents(&[1.0, 0.0], "test-model", 10, &[])
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[test]
-    fn test_chunk_operations() {
+    fn test_import_checkpoint_and_rollback() {
         let mut store = VectorStore::in_memory().unwrap();
+        assert_eq!(store.get_import_checkpoint("dump.jsonl").unwrap(), None);
 
-        let doc = Document::new("test.txt".to_string(), "Hello world");
-        let doc_id = store.insert_document(&doc).unwrap();
+        store.set_import_checkpoint("dump.jsonl", 42).unwrap();
+        assert_eq!(store.get_import_checkpoint("dump.jsonl").unwrap(), Some(42));
 
-        let chunk = Chunk::new(doc_id, 0, "Hello world".to_string());
-        let chunk_id = store.insert_chunk(&chunk).unwrap();
+        let record = DocumentRecord {
+            source: "a.txt".to_string(),
+            content_hash: "hash-a".to_string(),
+            metadata: Default::default(),
+            created_at: 0,
+            chunks: vec![],
+        };
 
-        assert!(chunk_id > 0);
+        // A failing transaction leaves neither the document nor the checkpoint behind
+        let result: Result<()> = store.in_transaction(|store| {
+            store.import_record(&record)?;
+            store.set_import_checkpoint("dump.jsonl", 43)?;
+            Err(VectDbError::Other("interrupted".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(store.count_documents().unwrap(), 0);
+        assert_eq!(store.get_import_checkpoint("dump.jsonl").unwrap(), Some(42));
 
-        let retrieved = store.get_chunk(chunk_id).unwrap().unwrap();
-        assert_eq!(retrieved.content, "Hello world");
-        assert_eq!(retrieved.chunk_index, 0);
+        assert!(store.import_record(&record).unwrap());
+        assert!(!store.import_record(&record).unwrap());
 
-        let chunks = store.get_chunks_for_document(doc_id).unwrap();
-        assert_eq!(chunks.len(), 1);
+        store.clear_import_checkpoint("dump.jsonl").unwrap();
+        assert_eq!(store.get_import_checkpoint("dump.jsonl").unwrap(), None);
     }
 
     #[test]
-    fn test_embedding_operations() {
+    fn test_non_finite_embeddings_rejected_or_zeroed() {
         let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("a.txt".to_string(), "a"))
+            .unwrap();
+        let mut chunk_ids = Vec::new();
+        for idx in 0..3 {
+            chunk_ids.push(
+                store
+                    .insert_chunk(&Chunk::new(doc_id, idx, format!("chunk {}", idx)))
+                    .unwrap(),
+            );
+        }
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_ids[0],
+                "test-model".to_string(),
+                vec![1.0, 0.0, 0.0],
+            ))
+            .unwrap();
 
-        let doc = Document::new("test.txt".to_string(), "Hello world");
-        let doc_id = store.insert_document(&doc).unwrap();
+        let poisoned = vec![f32::NAN, 1.0, f32::INFINITY];
+        let rejected = store.upsert_embedding(&Embedding::new(
+            chunk_ids[1],
+            "test-model".to_string(),
+            poisoned.clone(),
+        ));
+        assert!(matches!(rejected, Err(VectDbError::InvalidInput(_))));
+        assert_eq!(store.count_embeddings().unwrap(), 1);
 
-        let chunk = Chunk::new(doc_id, 0, "Hello world".to_string());
-        let chunk_id = store.insert_chunk(&chunk).unwrap();
+        let mut store = store.with_non_finite_embeddings(NonFiniteEmbeddings::Zero);
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_ids[2],
+                "test-model".to_string(),
+                poisoned,
+            ))
+            .unwrap();
+        assert_eq!(
+            store.get_embedding(chunk_ids[2]).unwrap().unwrap().vector,
+            vec![0.0, 1.0, 0.0]
+        );
 
-        let vector = vec![0.1, 0.2, 0.3];
-        let embedding = Embedding::new(chunk_id, "test-model".to_string(), vector.clone());
+        let results = store
+            .search_similar(&[1.0, 1.0, 0.0], "test-model", 10, 0)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.similarity.is_finite()));
+    }
 
-        store.upsert_embedding(&embedding).unwrap();
+    #[test]
+    fn test_export_jsonl_round_trip() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let vector = vec![0.25, -1.5, 3.0e-7];
+        for source in ["a.txt", "b.txt"] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, format!("{} chunk", source)))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(
+                    chunk_id,
+                    "test-model".to_string(),
+                    vector.clone(),
+                ))
+                .unwrap();
+        }
 
-        let retrieved = store.get_embedding(chunk_id).unwrap().unwrap();
-        assert_eq!(retrieved.model, "test-model");
-        assert_eq!(retrieved.dimension, 3);
-        assert_eq!(retrieved.vector, vector);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dump.jsonl");
+        let file = std::fs::File::create(&path).unwrap();
+        assert_eq!(store.export_jsonl(file, true).unwrap(), 2);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<DocumentRecord> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].source, "a.txt");
+        assert_eq!(records[1].chunks[0].content, "b.txt chunk");
+        let embedding = records[0].chunks[0].embedding.as_ref().unwrap();
+        assert_eq!(embedding.model, "test-model");
+        assert_eq!(embedding.vector, vector);
+        assert!(
+            contents.contains("\"vector\":\""),
+            "vectors are base64 strings"
+        );
+
+        let mut copy = VectorStore::in_memory().unwrap();
+        for record in &records {
+            assert!(copy.import_record(record).unwrap());
+        }
+        assert_eq!(copy.count_embeddings().unwrap(), 2);
+
+        let mut without_vectors = Vec::new();
+        store.export_jsonl(&mut without_vectors, false).unwrap();
+        let record: DocumentRecord =
+            serde_json::from_slice(without_vectors.split(|&b| b == b'\n').next().unwrap()).unwrap();
+        assert!(record.chunks[0].embedding.is_none());
+        assert_eq!(record.chunks[0].content, "a.txt chunk");
     }
 
     #[test]
-    fn test_search_similar() {
-        let mut store = VectorStore::in_memory().unwrap();
+    fn test_recompress_embeddings_to_f16() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = VectorStore::new(dir.path().join("vectors.db")).unwrap();
 
-        // Insert test data
         let doc = Document::new("test.txt".to_string(), "Test document");
         let doc_id = store.insert_document(&doc).unwrap();
+        for idx in 0..200 {
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, idx, format!("Chunk {}", idx)))
+                .unwrap();
+            let vector: Vec<f32> = (0..256).map(|d| ((idx * 7 + d) % 13) as f32).collect();
+            store
+                .upsert_embedding(&Embedding::new(chunk_id, "test-model".to_string(), vector))
+                .unwrap();
+        }
 
-        let chunk1 = Chunk::new(doc_id, 0, "First chunk".to_string());
-        let chunk1_id = store.insert_chunk(&chunk1).unwrap();
+        let query: Vec<f32> = (0..256).map(|d| ((42 * 7 + d) % 13) as f32).collect();
+        let before_top = store.search_similar(&query, "test-model", 1, 0).unwrap();
+        store.vacuum().unwrap();
+        let size_before = store.get_stats().unwrap().db_size_bytes;
 
-        let chunk2 = Chunk::new(doc_id, 1, "Second chunk".to_string());
-        let chunk2_id = store.insert_chunk(&chunk2).unwrap();
+        let rewritten = store
+            .recompress_embeddings(StoragePrecision::F16, 64)
+            .unwrap();
+        assert_eq!(rewritten, 200);
+        assert_eq!(
+            store
+                .recompress_embeddings(StoragePrecision::F16, 64)
+                .unwrap(),
+            0
+        );
+        store.vacuum().unwrap();
 
-        // Insert embeddings
-        let embedding1 = Embedding::new(chunk1_id, "model".to_string(), vec![1.0, 0.0, 0.0]);
-        store.upsert_embedding(&embedding1).unwrap();
+        assert!(store.get_stats().unwrap().db_size_bytes < size_before);
+        let after_top = store.search_similar(&query, "test-model", 1, 0).unwrap();
+        assert_eq!(after_top[0].chunk.id, before_top[0].chunk.id);
+        assert_eq!(
+            store
+                .get_embedding(after_top[0].chunk.id.unwrap())
+                .unwrap()
+                .unwrap()
+                .vector,
+            query
+        );
+    }
 
-        let embedding2 = Embedding::new(chunk2_id, "model".to_string(), vec![0.0, 1.0, 0.0]);
-        store.upsert_embedding(&embedding2).unwrap();
+    #[test]
+    fn test_nearest_duplicate() {
+        let mut store = VectorStore::in_memory().unwrap();
 
-        // Search with query similar to first embedding
-        let query = vec![0.9, 0.1, 0.0];
-        let results = store.search_similar(&query, "model", 2).unwrap();
+        for (source, vectors) in [
+            ("a.txt", vec![vec![1.0, 0.0, 0.0], vec![0.8, 0.2, 0.0]]),
+            ("b.txt", vec![vec![0.0, 0.0, 1.0]]),
+        ] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            for (idx, vector) in vectors.into_iter().enumerate() {
+                let chunk_id = store
+                    .insert_chunk(&Chunk::new(doc_id, idx, format!("{} {}", source, idx)))
+                    .unwrap();
+                store
+                    .upsert_embedding(&Embedding::new(chunk_id, "test-model".to_string(), vector))
+                    .unwrap();
+            }
+        }
 
-        assert_eq!(results.len(), 2);
-        assert_eq!(results[0].chunk.content, "First chunk");
-        assert!(results[0].similarity > results[1].similarity);
+        let (document, similarity) = store
+            .nearest_duplicate(&[0.9, 0.1, 0.0], "test-model", 0.95, "c.txt")
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.source, "a.txt");
+        assert!(similarity > 0.99);
+
+        assert!(
+            store
+                .nearest_duplicate(&[0.5, 0.5, 0.5], "test-model", 0.95, "c.txt")
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            store
+                .nearest_duplicate(&[0.9, 0.1, 0.0], "other-model", 0.0, "c.txt")
+                .unwrap()
+                .is_none()
+        );
+
+        // A document is never a near-duplicate of an earlier version of itself
+        let (document, _) = store
+            .nearest_duplicate(&[0.9, 0.1, 0.0], "test-model", 0.0, "a.txt")
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.source, "b.txt");
+    }
+
+    #[test]
+    fn test_partially_committed_document_is_hidden() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let complete = store
+            .insert_document(&Document::new("complete.txt".to_string(), "complete"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(complete, 0, "complete 0".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_id,
+                "test-model".to_string(),
+                vec![0.0, 1.0],
+            ))
+            .unwrap();
+
+        // One batch of a larger file is committed before ingestion stops
+        let partial = store
+            .begin_document(&Document::new("partial.txt".to_string(), "partial"))
+            .unwrap();
+        store
+            .append_chunks(
+                partial,
+                0,
+                &[embedded_chunk(0, vec![1.0, 0.0])],
+                "test-model",
+                1,
+                |_| {},
+            )
+            .unwrap();
+
+        let results = store
+            .search_similar(&[1.0, 0.0], "test-model", 10, 0)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.source, "complete.txt");
+
+        let listed = store
+            .list_documents(10, 0, DocumentSort::Source, SortOrder::Asc)
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].document.source, "complete.txt");
+        assert_eq!(store.document_ids().unwrap(), vec![complete]);
+        assert!(
+            store
+                .sample_chunks(10)
+                .unwrap()
+                .iter()
+                .all(|(_, document)| document.source == "complete.txt")
+        );
+
+        store.finish_document(partial).unwrap();
+        let results = store
+            .search_similar(&[1.0, 0.0], "test-model", 10, 0)
+            .unwrap();
+        assert_eq!(results[0].document.source, "partial.txt");
+    }
+
+    #[test]
+    fn test_search_scans_when_ann_candidates_are_filtered_out() {
+        let mut store = VectorStore::in_memory().unwrap();
+        let complete = store
+            .insert_document(&Document::new("complete.txt".to_string(), "complete"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(complete, 0, "complete 0".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_id,
+                "test-model".to_string(),
+                vec![0.0, 1.0],
+            ))
+            .unwrap();
+
+        // The index's nearest candidates all belong to an unfinished document
+        let partial = store
+            .begin_document(&Document::new("partial.txt".to_string(), "partial"))
+            .unwrap();
+        let chunks: Vec<EmbeddedChunk> = (0..4)
+            .map(|idx| embedded_chunk(idx, vec![1.0, 0.01 * idx as f32]))
+            .collect();
+        store
+            .append_chunks(partial, 0, &chunks, "test-model", 4, |_| {})
+            .unwrap();
+
+        let results = store
+            .search_similar(&[1.0, 0.0], "test-model", 1, 0)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.source, "complete.txt");
+    }
+
+    #[test]
+    fn test_nearest_duplicate_skips_other_dimensions() {
+        let mut store = VectorStore::in_memory().unwrap();
+        for (source, vector) in [("a.txt", vec![1.0, 0.0, 0.0]), ("b.txt", vec![0.0, 1.0])] {
+            // Embeddings truncated to another size after the model was reset
+            store.reset_model_dimension("test-model").unwrap();
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, source.to_string()))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(chunk_id, "test-model".to_string(), vector))
+                .unwrap();
+        }
+
+        let (document, similarity) = store
+            .nearest_duplicate(&[1.0, 0.0], "test-model", 0.0, "c.txt")
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.source, "b.txt");
+        assert_eq!(similarity, 0.0);
+
+        assert!(
+            store
+                .nearest_duplicate(&[1.0, 0.0, 0.0, 0.0], "test-model", 0.0, "c.txt")
+                .unwrap()
+                .is_none()
+        );
     }
 }
@@ -1,72 +1,334 @@
 //! Web server for HTTP API and UI
 
-use crate::clients::OllamaClient;
+use crate::clients::{Embedder, EmbeddingProvider};
 use crate::config::Config;
 use crate::domain::SearchResult;
-use crate::error::Result;
-use crate::repositories::VectorStore;
+use crate::error::{Result, VectDbError};
+use crate::repositories::vector_store::is_interrupted;
+use crate::repositories::{DocumentSort, DocumentSummary, SortOrder, VectorStore};
+use crate::services::IngestionService;
+use crate::services::ingestion::IngestionOptions;
+use crate::services::search::{check_deadline, group_by_source, parse_document_ids, with_deadline};
 use axum::{
     Json, Router,
-    extract::{Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Query, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::{self, Next},
     response::{Html, IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::future::IntoFuture;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use subtle::ConstantTimeEq;
+use tower::ServiceBuilder;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::request_id::{
+    MakeRequestId, PropagateRequestIdLayer, RequestId, SetRequestIdLayer,
+};
 use tower_http::trace::TraceLayer;
-use tracing::{info, warn};
+use tracing::{info, info_span, warn};
+
+/// Document records buffered between the database reader and an export response
+const EXPORT_BUFFER_RECORDS: usize = 16;
+
+/// Endpoints open without `server.api_key`, so health probes keep working
+const UNAUTHENTICATED_API_PATHS: [&str; 3] = ["/api/health", "/api/livez", "/api/readyz"];
+
+/// How long in-flight requests may run after a shutdown signal
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
 
 /// Shared application state
 #[derive(Clone)]
 pub struct AppState {
     pub config: Config,
-    pub ollama: Arc<OllamaClient>,
+    pub embedder: Arc<Embedder>,
+    /// While set, `/api/ingest` answers 503 (toggled by the admin endpoints)
+    pub ingestion_paused: Arc<AtomicBool>,
+    /// Responses cached until the database changes
+    data_watch: Arc<DataWatch>,
 }
 
 impl AppState {
-    pub fn new(config: Config, ollama: OllamaClient) -> Self {
+    pub fn new(config: Config, embedder: Embedder) -> Self {
         Self {
             config,
-            ollama: Arc::new(ollama),
+            embedder: Arc::new(embedder),
+            ingestion_paused: Arc::new(AtomicBool::new(false)),
+            data_watch: Arc::new(DataWatch::default()),
+        }
+    }
+}
+
+/// Server-side caches dropped whenever the database changes
+///
+/// Requests open their own connections, so they always read fresh data; only
+/// values kept between requests can go stale while `vectdb ingest` writes to
+/// the same file. A long-lived watch connection reads `PRAGMA data_version`,
+/// which moves on every commit made by any other connection.
+#[derive(Default)]
+struct DataWatch {
+    inner: std::sync::Mutex<WatchedData>,
+}
+
+#[derive(Default)]
+struct WatchedData {
+    store: Option<VectorStore>,
+    data_version: Option<i64>,
+    stats: Option<StatsResponse>,
+}
+
+impl DataWatch {
+    /// Cached statistics, recomputed with `compute` if the database changed since
+    ///
+    /// `open` opens the watch connection on first use.
+    fn stats(
+        &self,
+        open: impl FnOnce() -> Result<VectorStore>,
+        compute: impl FnOnce() -> Result<StatsResponse>,
+    ) -> Result<StatsResponse> {
+        let mut data = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        if data.store.is_none() {
+            data.store = Some(open()?);
+        }
+        let version = data
+            .store
+            .as_ref()
+            .map(VectorStore::data_version)
+            .transpose()?;
+        if data.data_version != version {
+            data.data_version = version;
+            data.stats = None;
+        }
+
+        match &data.stats {
+            Some(stats) => Ok(stats.clone()),
+            None => {
+                let stats = compute()?;
+                data.stats = Some(stats.clone());
+                Ok(stats)
+            }
         }
     }
 }
 
+/// Open the database that searches and statistics read from
+///
+/// With `database.read_path` set this is the read replica, opened read-only;
+/// otherwise the primary.
+fn open_read_store(state: &AppState) -> Result<VectorStore> {
+    match &state.config.database.read_path {
+        Some(path) => Ok(VectorStore::open_read_only(path)?
+            .with_high_precision_scoring(state.config.search.high_precision_scoring)
+            .with_ann(state.config.database.use_ann)),
+        None => VectorStore::from_config(&state.config),
+    }
+}
+
 /// Start the web server
 pub async fn serve(host: String, port: u16, config: Config) -> Result<()> {
     info!("Starting web server on {}:{}", host, port);
 
-    // Initialize Ollama client
-    let ollama = OllamaClient::new(
-        config.ollama.base_url.clone(),
-        config.ollama.timeout_seconds,
-    )?;
+    // Initialize the embedding provider
+    let embedder = Embedder::from_config(&config)?;
+    if let Some(ollama) = embedder.as_ollama() {
+        match ollama
+            .check_version(
+                config.ollama.min_version(),
+                config.ollama.strict_version_check,
+            )
+            .await
+        {
+            Ok(None) => {}
+            Ok(Some(warning)) => warn!("{}", warning),
+            Err(e) if config.ollama.strict_version_check => return Err(e),
+            Err(e) => warn!("Could not check the Ollama version: {}", e),
+        }
+    }
 
-    let state = AppState::new(config, ollama);
+    let state = AppState::new(config, embedder);
+    let app = router(state);
 
-    // Build routes
-    let app = Router::new()
+    // Bind and serve
+    let addr = format!("{}:{}", host, port);
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+
+    info!("Server listening on http://{}", addr);
+    info!("API documentation available at http://{}/api/health", addr);
+
+    serve_until(listener, app, shutdown_signal(), SHUTDOWN_GRACE).await?;
+    info!("Server stopped");
+
+    Ok(())
+}
+
+/// Serve until `shutdown` resolves, then give in-flight requests up to `grace`
+///
+/// New connections are refused as soon as `shutdown` resolves.
+async fn serve_until(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+    grace: Duration,
+) -> Result<()> {
+    let (signalled_tx, signalled_rx) = tokio::sync::oneshot::channel();
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            shutdown.await;
+            let _ = signalled_tx.send(());
+        })
+        .into_future();
+    let grace_expired = async {
+        if signalled_rx.await.is_ok() {
+            tokio::time::sleep(grace).await;
+        } else {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    tokio::select! {
+        result = server => result?,
+        _ = grace_expired => warn!(
+            "Requests still running after {}s, shutting down anyway",
+            grace.as_secs_f64()
+        ),
+    }
+
+    Ok(())
+}
+
+/// Resolve on Ctrl+C or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Cannot listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Cannot listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    info!("Shutdown signal received, shutting down");
+}
+
+/// Build the application router
+pub fn router(state: AppState) -> Router {
+    let request_id_header = HeaderName::try_from(&state.config.server.request_id_header)
+        .unwrap_or_else(|_| HeaderName::from_static("x-request-id"));
+    let span_header = request_id_header.clone();
+
+    let mut router = Router::new()
         .route("/", get(index_handler))
         .route("/build-info.js", get(build_info_handler))
         .route("/favicon.ico", get(favicon_handler))
         .route("/api/health", get(health_handler))
+        .route("/api/livez", get(livez_handler))
+        .route("/api/readyz", get(readyz_handler))
         .route("/api/stats", get(stats_handler))
-        .route("/api/search", get(search_handler))
+        .route("/api/search", get(search_handler).post(search_post_handler))
+        .route("/api/search/sources", get(search_sources_handler))
+        .route("/api/history", get(history_handler))
         .route("/api/models", get(models_handler))
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
+        .route("/api/documents", get(documents_handler))
+        .route("/api/ingest", post(ingest_handler))
+        .route("/api/export", get(export_handler))
+        .route("/api/admin/pause", post(pause_handler))
+        .route("/api/admin/resume", post(resume_handler))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_api_key,
+        ));
+    // Without configured origins no CORS headers are sent, so browsers only allow same-origin calls
+    if !state.config.server.cors_origins.is_empty() {
+        router = router.layer(cors_layer(&state.config.server.cors_origins));
+    }
 
-    // Bind and serve
-    let addr = format!("{}:{}", host, port);
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    router
+        .layer(
+            // Tag each request with an id (kept if the client sent one), log it
+            // in the request span and echo it in the response
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::new(
+                    request_id_header.clone(),
+                    RandomRequestId,
+                ))
+                .layer(
+                    TraceLayer::new_for_http().make_span_with(move |request: &Request| {
+                        request_span(request, &span_header)
+                    }),
+                )
+                .layer(PropagateRequestIdLayer::new(request_id_header)),
+        )
+        .with_state(state)
+}
 
-    info!("Server listening on http://{}", addr);
-    info!("API documentation available at http://{}/api/health", addr);
+/// CORS policy allowing `origins` ("*" = any origin)
+///
+/// Preflight `OPTIONS` requests, e.g. before a JSON `POST /api/search`, are
+/// answered by the layer itself.
+fn cors_layer(origins: &[String]) -> CorsLayer {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+}
 
-    axum::serve(listener, app).await?;
+/// Span for one request, carrying its id so every log line while handling it shows it
+fn request_span(request: &Request, request_id_header: &HeaderName) -> tracing::Span {
+    let request_id = request
+        .headers()
+        .get(request_id_header)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("-");
+    info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id = %request_id,
+    )
+}
 
-    Ok(())
+/// Generates random 16-digit hex request ids
+#[derive(Clone, Copy)]
+struct RandomRequestId;
+
+impl MakeRequestId for RandomRequestId {
+    fn make_request_id<B>(&mut self, _request: &axum::http::Request<B>) -> Option<RequestId> {
+        let id = format!("{:016x}", fastrand::u64(..));
+        HeaderValue::from_str(&id).ok().map(RequestId::new)
+    }
 }
 
 // ============================================================================
@@ -104,7 +366,7 @@ async fn favicon_handler() -> Response {
 
 /// Health check endpoint
 async fn health_handler(State(state): State<AppState>) -> impl IntoResponse {
-    let ollama_healthy = state.ollama.health_check().await.unwrap_or(false);
+    let ollama_healthy = state.embedder.health_check().await.unwrap_or(false);
 
     let health = HealthResponse {
         status: "ok".to_string(),
@@ -114,27 +376,60 @@ async fn health_handler(State(state): State<AppState>) -> impl IntoResponse {
     Json(health)
 }
 
-/// Statistics endpoint
-async fn stats_handler(State(state): State<AppState>) -> Response {
-    // Create a new connection for this request
-    let store = match VectorStore::new(&state.config.database.path) {
-        Ok(s) => s,
+/// Liveness probe - succeeds whenever the process is serving requests
+async fn livez_handler() -> impl IntoResponse {
+    (StatusCode::OK, "ok")
+}
+
+/// Readiness probe - succeeds only when the database opens and the default model embeds
+async fn readyz_handler(State(state): State<AppState>) -> Response {
+    let database_available = match VectorStore::from_config(&state.config) {
+        Ok(_) => true,
         Err(e) => {
-            warn!("Failed to open database: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            warn!("Readiness check: failed to open database: {}", e);
+            false
         }
     };
 
-    match store.get_stats() {
-        Ok(stats) => {
-            let response = StatsResponse {
-                document_count: stats.document_count,
-                chunk_count: stats.chunk_count,
-                embedding_count: stats.embedding_count,
-                db_size_bytes: stats.db_size_bytes,
-            };
-            Json(response).into_response()
-        }
+    let ollama_available = state.embedder.health_check().await.unwrap_or(false);
+    let model = state.config.default_model();
+    let model_available =
+        ollama_available && state.embedder.has_model(model).await.unwrap_or(false);
+    // Listing a model does not mean it loads: embed a probe to be sure
+    let embedding_available = model_available
+        && state
+            .embedder
+            .embed_health_check(model)
+            .await
+            .unwrap_or(false);
+
+    let ready = database_available && ollama_available && model_available && embedding_available;
+    let response = ReadinessResponse {
+        status: if ready { "ready" } else { "not_ready" }.to_string(),
+        database_available,
+        ollama_available,
+        model_available,
+        embedding_available,
+    };
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(response)).into_response()
+}
+
+/// Statistics endpoint
+async fn stats_handler(State(state): State<AppState>) -> Response {
+    // The SQLite work, and the lock on the cached value, stay off the async workers
+    let stats = tokio::task::spawn_blocking(move || compute_stats(&state))
+        .await
+        .unwrap_or_else(|e| Err(VectDbError::Other(format!("Stats task failed: {}", e))));
+
+    match stats {
+        Ok(response) => Json(response).into_response(),
         Err(e) => {
             warn!("Failed to get stats: {}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
@@ -142,27 +437,209 @@ async fn stats_handler(State(state): State<AppState>) -> Response {
     }
 }
 
+/// Database statistics, reused from the cache while `server.cache_stats` is on
+fn compute_stats(state: &AppState) -> Result<StatsResponse> {
+    let compute = || {
+        // Create a new connection for this request
+        let stats = open_read_store(state)?.get_stats()?;
+        Ok(StatsResponse {
+            document_count: stats.document_count,
+            chunk_count: stats.chunk_count,
+            embedding_count: stats.embedding_count,
+            db_size_bytes: stats.db_size_bytes,
+        })
+    };
+    // A replaced replica file is invisible to the watch connection, which keeps the old one open
+    if state.config.server.cache_stats && state.config.database.read_path.is_none() {
+        state.data_watch.stats(|| open_read_store(state), compute)
+    } else {
+        compute()
+    }
+}
+
 /// Search endpoint
 async fn search_handler(
     State(state): State<AppState>,
     Query(params): Query<SearchQuery>,
 ) -> Response {
-    if params.query.is_empty() {
-        return (StatusCode::BAD_REQUEST, "Query parameter is required").into_response();
+    match run_search(&state, &params, params.top_k, params.offset).await {
+        Ok(results) => {
+            record_search(&state, &params.query, results.len());
+            let response: Vec<SearchResultResponse> =
+                results.iter().map(SearchResultResponse::from).collect();
+            Json(response).into_response()
+        }
+        Err(failure) => failure.into_response(),
     }
+}
 
-    // Generate the query embedding first (this is the async part)
-    let model = state.config.ollama.default_model.clone();
-    let query_embedding = match state.ollama.embed(&model, &params.query).await {
-        Ok(emb) => emb,
-        Err(e) => {
-            warn!("Failed to generate embedding: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
-        }
+/// Search endpoint taking a JSON body, for long queries and metadata filters
+async fn search_post_handler(
+    State(state): State<AppState>,
+    Json(request): Json<SearchRequest>,
+) -> Response {
+    let params = SearchQuery {
+        query: request.query,
+        top_k: request.top_k,
+        threshold: request.threshold,
+        doc_ids: None,
+        offset: 0,
+    };
+
+    // A filter can reject any chunk, so rank them all before taking top_k
+    let limit = if request.filter.is_empty() {
+        params.top_k
+    } else {
+        usize::MAX
     };
 
+    match run_search(&state, &params, limit, 0).await {
+        Ok(results) => {
+            let response: Vec<SearchResultResponse> = results
+                .iter()
+                .filter(|r| {
+                    request
+                        .filter
+                        .iter()
+                        .all(|(key, value)| r.document.metadata.get(key) == Some(value))
+                })
+                .take(params.top_k)
+                .map(SearchResultResponse::from)
+                .collect();
+            record_search(&state, &params.query, response.len());
+            Json(response).into_response()
+        }
+        Err(failure) => failure.into_response(),
+    }
+}
+
+/// Matching document sources with their best score, without chunk content
+async fn search_sources_handler(
+    State(state): State<AppState>,
+    Query(params): Query<SearchQuery>,
+) -> Response {
+    // Every chunk is scored anyway; rank them all so top_k counts documents
+    match run_search(&state, &params, usize::MAX, 0).await {
+        Ok(results) => {
+            let sources: Vec<_> = group_by_source(&results)
+                .into_iter()
+                .skip(params.offset)
+                .take(params.top_k)
+                .collect();
+            record_search(&state, &params.query, sources.len());
+            Json(sources).into_response()
+        }
+        Err(failure) => failure.into_response(),
+    }
+}
+
+/// Embed the query and rank up to `limit` chunks after `offset` for the search endpoints
+async fn run_search(
+    state: &AppState,
+    params: &SearchQuery,
+    limit: usize,
+    offset: usize,
+) -> std::result::Result<Vec<SearchResult>, SearchFailure> {
+    if params.query.trim().is_empty() {
+        return Err(SearchFailure::BadRequest(
+            "Query parameter is required".to_string(),
+        ));
+    }
+
+    let document_ids = params
+        .doc_ids
+        .as_deref()
+        .map(parse_document_ids)
+        .transpose()
+        .map_err(|e| SearchFailure::BadRequest(e.to_string()))?;
+
+    // Generate the query embedding first (this is the async part)
+    let model = state.config.default_model().to_string();
+    let deadline = state.config.search.deadline_ms.map(Duration::from_millis);
+    let started = Instant::now();
+    let embedding = with_deadline(deadline, state.embedder.embed(&model, &params.query)).await;
+    if check_deadline(deadline, started).is_err() {
+        return Err(SearchFailure::DeadlineExceeded);
+    }
+    let query_embedding = embedding.map_err(|e| {
+        warn!("Failed to generate embedding: {}", e);
+        SearchFailure::Internal(e.to_string())
+    })?;
+
     // Now do the database search (synchronous, doesn't cross await)
-    let store = match VectorStore::new(&state.config.database.path) {
+    let store = open_read_store(state).map_err(|e| {
+        warn!("Failed to open database: {}", e);
+        SearchFailure::Internal(e.to_string())
+    })?;
+
+    // Interrupt the scan itself once the deadline passes
+    store.set_deadline(deadline.map(|deadline| started + deadline));
+    let search = match &document_ids {
+        Some(ids) => store
+            .search_within_documents(&query_embedding, &model, limit.saturating_add(offset), ids)
+            .map(|mut results| {
+                results.drain(..offset.min(results.len()));
+                results
+            }),
+        None => store.search_similar(&query_embedding, &model, limit, offset),
+    };
+    if check_deadline(deadline, started).is_err() || search.as_ref().is_err_and(is_interrupted) {
+        return Err(SearchFailure::DeadlineExceeded);
+    }
+    let mut results = search.map_err(|e| {
+        warn!("Search failed: {}", e);
+        SearchFailure::Internal(e.to_string())
+    })?;
+
+    // Filter by threshold
+    if params.threshold > 0.0 {
+        results.retain(|r| r.similarity >= params.threshold);
+    }
+
+    Ok(results)
+}
+
+/// Add a search to the history when `server.record_history` is set
+///
+/// A failure to record is logged; it never fails the search itself.
+fn record_search(state: &AppState, query: &str, result_count: usize) {
+    if !state.config.server.record_history {
+        return;
+    }
+
+    let recorded = VectorStore::from_config(&state.config).and_then(|mut store| {
+        store.record_search(query, result_count, state.config.server.history_limit)
+    });
+    if let Err(e) = recorded {
+        warn!("Failed to record search history: {}", e);
+    }
+}
+
+/// Recent searches, newest first
+///
+/// Past queries are only shown to holders of `server.api_token`, so the
+/// endpoint is refused while none is configured.
+async fn history_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HistoryQuery>,
+) -> Response {
+    if state.config.server.api_token.is_none() {
+        return api_token_required("Search history");
+    }
+    if !is_authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    if !state.config.server.record_history {
+        return (
+            StatusCode::FORBIDDEN,
+            "Search history is disabled (set server.record_history = true)",
+        )
+            .into_response();
+    }
+
+    let store = match VectorStore::from_config(&state.config) {
         Ok(s) => s,
         Err(e) => {
             warn!("Failed to open database: {}", e);
@@ -170,27 +647,74 @@ async fn search_handler(
         }
     };
 
-    let mut results = match store.search_similar(&query_embedding, &model, params.top_k) {
-        Ok(r) => r,
+    match store.recent_searches(params.limit) {
+        Ok(entries) => Json(entries).into_response(),
         Err(e) => {
-            warn!("Search failed: {}", e);
+            warn!("Failed to read search history: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Why a search request could not be answered
+enum SearchFailure {
+    BadRequest(String),
+    DeadlineExceeded,
+    Internal(String),
+}
+
+impl IntoResponse for SearchFailure {
+    fn into_response(self) -> Response {
+        match self {
+            SearchFailure::BadRequest(message) => {
+                (StatusCode::BAD_REQUEST, message).into_response()
+            }
+            SearchFailure::DeadlineExceeded => deadline_exceeded_response(),
+            SearchFailure::Internal(message) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, message).into_response()
+            }
+        }
+    }
+}
+
+/// Documents listing endpoint
+async fn documents_handler(
+    State(state): State<AppState>,
+    Query(params): Query<DocumentsQuery>,
+) -> Response {
+    let sort: DocumentSort = match params.sort.parse() {
+        Ok(sort) => sort,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    let order: SortOrder = match params.order.parse() {
+        Ok(order) => order,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let store = match VectorStore::from_config(&state.config) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("Failed to open database: {}", e);
             return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
         }
     };
 
-    // Filter by threshold
-    if params.threshold > 0.0 {
-        results.retain(|r| r.similarity >= params.threshold);
+    match store.list_documents(params.limit, params.offset, sort, order) {
+        Ok(documents) => {
+            let response: Vec<DocumentResponse> =
+                documents.iter().map(DocumentResponse::from).collect();
+            Json(response).into_response()
+        }
+        Err(e) => {
+            warn!("Failed to list documents: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
     }
-
-    let response: Vec<SearchResultResponse> =
-        results.iter().map(SearchResultResponse::from).collect();
-    Json(response).into_response()
 }
 
 /// Models endpoint
 async fn models_handler(State(state): State<AppState>) -> Response {
-    match state.ollama.list_models().await {
+    match state.embedder.list_models().await {
         Ok(models) => {
             let response: Vec<ModelResponse> = models
                 .iter()
@@ -209,6 +733,294 @@ async fn models_handler(State(state): State<AppState>) -> Response {
     }
 }
 
+/// Ingest a file that is readable by the server
+async fn ingest_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<IngestRequest>,
+) -> Response {
+    if !is_authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    if !state.config.server.allow_ingest {
+        return (
+            StatusCode::FORBIDDEN,
+            "Ingestion over HTTP is disabled (set server.allow_ingest = true)",
+        )
+            .into_response();
+    }
+
+    if state.ingestion_paused.load(Ordering::SeqCst) {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Ingestion is paused").into_response();
+    }
+
+    let mut chunking = state.config.chunking.clone();
+    if let Some(size) = request.chunk_size {
+        chunking.max_chunk_size = size;
+    }
+    if let Some(overlap) = request.overlap {
+        chunking.overlap_size = overlap;
+    }
+    if chunking.max_chunk_size == 0 || chunking.overlap_size >= chunking.max_chunk_size {
+        return (
+            StatusCode::BAD_REQUEST,
+            "chunk_size must be positive and larger than overlap",
+        )
+            .into_response();
+    }
+
+    let options = match IngestionOptions::from_config(&state.config) {
+        Ok(options) => options,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let store = match VectorStore::from_config(&state.config) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("Failed to open database: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+        }
+    };
+
+    let model = request
+        .model
+        .unwrap_or_else(|| state.config.default_model().to_string());
+    let strategy = chunking.to_strategy();
+    let mut service = IngestionService::new(store, (*state.embedder).clone()).with_options(options);
+
+    let ingestion = match (request.path, request.source, request.content) {
+        (Some(path), None, None) => {
+            let path = match resolve_ingest_path(&state, &path) {
+                Ok(path) => path,
+                Err(rejection) => return rejection.into_response(),
+            };
+            service.ingest_file(&path, &model, strategy).await
+        }
+        (None, Some(source), Some(content)) => {
+            service
+                .ingest_text(&source, &content, &model, strategy)
+                .await
+        }
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Provide either `path`, or `source` and `content`",
+            )
+                .into_response();
+        }
+    };
+
+    match ingestion {
+        Ok(result) => Json(IngestResponse {
+            document_id: result.document_id,
+            chunks_created: result.chunks_created,
+            embeddings_created: result.embeddings_created,
+            skipped: result.skipped,
+        })
+        .into_response(),
+        Err(e) => {
+            warn!("Ingestion failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Resolve a `path` ingest request to a file under `server.ingest_root`
+///
+/// Reading server files needs both an admin token and an ingest root. The
+/// path is canonicalized, so `..` and symlinks cannot escape the root; a path
+/// that does not resolve is refused like one outside the root, so requests
+/// cannot probe for files elsewhere.
+fn resolve_ingest_path(
+    state: &AppState,
+    path: &Path,
+) -> std::result::Result<PathBuf, (StatusCode, &'static str)> {
+    if state.config.server.api_token.is_none() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Ingesting server files requires server.api_token",
+        ));
+    }
+    let Some(root) = &state.config.server.ingest_root else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Ingesting server files requires server.ingest_root",
+        ));
+    };
+
+    let root = root.canonicalize().map_err(|e| {
+        warn!("Cannot resolve server.ingest_root {:?}: {}", root, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Cannot resolve server.ingest_root",
+        )
+    })?;
+    match root.join(path).canonicalize() {
+        Ok(resolved) if resolved.starts_with(&root) => Ok(resolved),
+        _ => Err((StatusCode::FORBIDDEN, "Path is outside server.ingest_root")),
+    }
+}
+
+/// Pause ingestion, e.g. during maintenance or backups
+async fn pause_handler(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    set_ingestion_paused(&state, &headers, true)
+}
+
+/// Resume ingestion after a pause
+async fn resume_handler(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    set_ingestion_paused(&state, &headers, false)
+}
+
+/// Toggle the pause flag; refused while no `server.api_token` is configured
+fn set_ingestion_paused(state: &AppState, headers: &HeaderMap, paused: bool) -> Response {
+    if state.config.server.api_token.is_none() {
+        return api_token_required("Pausing ingestion");
+    }
+    if !is_authorized(state, headers) {
+        return unauthorized();
+    }
+
+    state.ingestion_paused.store(paused, Ordering::SeqCst);
+    info!(
+        "Ingestion {} via admin endpoint",
+        if paused { "paused" } else { "resumed" }
+    );
+
+    Json(IngestionStatusResponse {
+        ingestion_paused: paused,
+    })
+    .into_response()
+}
+
+/// Stream the whole database as JSONL, one document record per line
+///
+/// Records are read on a blocking thread and handed over through a small
+/// channel, so only a few documents are in memory at a time. The output is
+/// the format read by `vectdb import`. A full dump is only served to holders
+/// of `server.api_token`, so the endpoint is refused while none is configured.
+async fn export_handler(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if state.config.server.api_token.is_none() {
+        return api_token_required("Export");
+    }
+    if !is_authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let store = match VectorStore::from_config(&state.config) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("Failed to open database: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+        }
+    };
+    let ids = match store.document_ids() {
+        Ok(ids) => ids,
+        Err(e) => {
+            warn!("Failed to list documents for export: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+        }
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_BUFFER_RECORDS);
+    tokio::task::spawn_blocking(move || {
+        for id in ids {
+            let line = match store.document_record(id) {
+                // Deleted since the export started
+                Ok(None) => continue,
+                Ok(Some(record)) => serde_json::to_vec(&record)
+                    .map(|mut line| {
+                        line.push(b'\n');
+                        line
+                    })
+                    .map_err(VectDbError::from),
+                Err(e) => Err(e),
+            };
+            let failed = line.is_err();
+            if let Err(e) = &line {
+                warn!("Export failed at document {}: {}", id, e);
+            }
+            // A closed channel means the client went away
+            if tx.blocking_send(line).is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let records = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|line| (line, rx))
+    });
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(records),
+    )
+        .into_response()
+}
+
+fn deadline_exceeded_response() -> Response {
+    warn!("Search deadline exceeded");
+    (StatusCode::GATEWAY_TIMEOUT, "Search deadline exceeded").into_response()
+}
+
+/// Check the bearer token for the privileged endpoints
+///
+/// Without `server.api_token` these endpoints are open only while no
+/// `server.api_key` is set either; the shared read key never unlocks them.
+fn is_authorized(state: &AppState, headers: &HeaderMap) -> bool {
+    match &state.config.server.api_token {
+        Some(expected) => bearer_token(headers).is_some_and(|token| tokens_match(token, expected)),
+        None => state.config.server.api_key.is_none(),
+    }
+}
+
+/// Compare a presented token with a configured one in constant time
+fn tokens_match(token: &str, expected: &str) -> bool {
+    token.as_bytes().ct_eq(expected.as_bytes()).into()
+}
+
+/// Token of an `Authorization: Bearer <token>` header
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Reject `/api/*` requests without the `server.api_key` bearer token
+///
+/// The health probes stay open. The admin `api_token` is accepted as well,
+/// since one `Authorization` header has to satisfy both checks.
+async fn require_api_key(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    let protected = path.starts_with("/api/") && !UNAUTHENTICATED_API_PATHS.contains(&path);
+
+    if let Some(api_key) = &state.config.server.api_key
+        && protected
+    {
+        let api_token = state.config.server.api_token.as_deref();
+        let accepted = bearer_token(request.headers()).is_some_and(|token| {
+            tokens_match(token, api_key)
+                || api_token.is_some_and(|admin| tokens_match(token, admin))
+        });
+        if !accepted {
+            return unauthorized();
+        }
+    }
+
+    next.run(request).await
+}
+
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, "Missing or invalid API token").into_response()
+}
+
+fn api_token_required(action: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        format!("{} over HTTP requires server.api_token", action),
+    )
+        .into_response()
+}
+
 // ============================================================================
 // Request/Response Types
 // ============================================================================
@@ -220,19 +1032,143 @@ struct SearchQuery {
     top_k: usize,
     #[serde(default)]
     threshold: f32,
+    /// Comma-separated document ids to restrict the search to
+    #[serde(default)]
+    doc_ids: Option<String>,
+    /// Number of top results to skip (for "load more" paging)
+    #[serde(default)]
+    offset: usize,
+}
+
+/// JSON body for `POST /api/search`
+#[derive(Debug, Deserialize)]
+struct SearchRequest {
+    query: String,
+    #[serde(default = "default_top_k")]
+    top_k: usize,
+    #[serde(default)]
+    threshold: f32,
+    /// Document metadata that must match exactly (key -> value)
+    #[serde(default)]
+    filter: std::collections::HashMap<String, String>,
 }
 
 fn default_top_k() -> usize {
     10
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct DocumentsQuery {
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default = "default_sort")]
+    sort: String,
+    #[serde(default = "default_order")]
+    order: String,
+}
+
+fn default_limit() -> usize {
+    20
+}
+
+fn default_sort() -> String {
+    "date".to_string()
+}
+
+fn default_order() -> String {
+    "desc".to_string()
+}
+
+/// Body for `POST /api/ingest`: either a server-side `path`, or `source` and `content`
+#[derive(Debug, Deserialize)]
+struct IngestRequest {
+    /// Path of the file to ingest, as seen by the server
+    #[serde(default)]
+    path: Option<PathBuf>,
+    /// Name stored for pushed `content` (e.g. its original path or URL)
+    #[serde(default)]
+    source: Option<String>,
+    /// Text to ingest directly
+    #[serde(default)]
+    content: Option<String>,
+    /// Embedding model (defaults to the configured model)
+    #[serde(default)]
+    model: Option<String>,
+    /// Chunk size override (defaults to `chunking.max_chunk_size`)
+    #[serde(default)]
+    chunk_size: Option<usize>,
+    /// Chunk overlap override (defaults to `chunking.overlap_size`)
+    #[serde(default)]
+    overlap: Option<usize>,
+}
+
+/// Names accepted by [`response_schema`], one per API response body
+pub const SCHEMA_TYPES: [&str; 8] = [
+    "stats",
+    "search",
+    "documents",
+    "models",
+    "health",
+    "readiness",
+    "ingest",
+    "ingestion-status",
+];
+
+/// JSON Schema of an API response body, for client generators
+///
+/// Returns `None` for names not in [`SCHEMA_TYPES`].
+pub fn response_schema(name: &str) -> Option<serde_json::Value> {
+    let schema = match name {
+        "stats" => schemars::schema_for!(StatsResponse),
+        "search" => schemars::schema_for!(Vec<SearchResultResponse>),
+        "documents" => schemars::schema_for!(Vec<DocumentResponse>),
+        "models" => schemars::schema_for!(Vec<ModelResponse>),
+        "health" => schemars::schema_for!(HealthResponse),
+        "readiness" => schemars::schema_for!(ReadinessResponse),
+        "ingest" => schemars::schema_for!(IngestResponse),
+        "ingestion-status" => schemars::schema_for!(IngestionStatusResponse),
+        _ => return None,
+    };
+    Some(schema.to_value())
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct IngestResponse {
+    document_id: i64,
+    chunks_created: usize,
+    embeddings_created: usize,
+    skipped: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct IngestionStatusResponse {
+    ingestion_paused: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
 struct HealthResponse {
     status: String,
     ollama_available: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
+struct ReadinessResponse {
+    status: String,
+    database_available: bool,
+    ollama_available: bool,
+    model_available: bool,
+    embedding_available: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 struct StatsResponse {
     document_count: i64,
     chunk_count: i64,
@@ -240,7 +1176,7 @@ struct StatsResponse {
     db_size_bytes: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct SearchResultResponse {
     source: String,
     chunk_index: usize,
@@ -259,9 +1195,1051 @@ impl From<&SearchResult> for SearchResultResponse {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
+struct DocumentResponse {
+    id: i64,
+    source: String,
+    created_at: i64,
+    chunk_count: i64,
+    metadata: std::collections::HashMap<String, String>,
+}
+
+impl From<&DocumentSummary> for DocumentResponse {
+    fn from(summary: &DocumentSummary) -> Self {
+        Self {
+            id: summary.document.id.unwrap_or(0),
+            source: summary.document.source.clone(),
+            created_at: summary.document.created_at,
+            chunk_count: summary.chunk_count,
+            metadata: summary.document.metadata.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
 struct ModelResponse {
     name: String,
     size: u64,
     modified_at: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::OllamaClient;
+    use crate::domain::{Chunk, Document, Embedding};
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// State pointing at a temporary database and an Ollama address nothing listens on
+    fn offline_state(dir: &tempfile::TempDir) -> AppState {
+        let mut config = Config::default();
+        config.database.path = dir.path().join("vectors.db");
+        config.ollama.base_url = "http://127.0.0.1:9".to_string();
+
+        let ollama = OllamaClient::new(config.ollama.base_url.clone(), 2).unwrap();
+        AppState::new(config, ollama.into())
+    }
+
+    /// Embedder backed by a mock Ollama server that answers every request with `vector`
+    ///
+    /// The server shuts down when dropped, so keep it alive while the embedder is in use.
+    async fn mock_embedder(vector: &[f32]) -> (MockServer, Arc<Embedder>) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": vector
+            })))
+            .mount(&server)
+            .await;
+        let embedder = OllamaClient::new(server.uri(), 5).unwrap().into();
+        (server, Arc::new(embedder))
+    }
+
+    /// Store each document with one chunk per vector, embedded with the default model
+    ///
+    /// Chunk `i` of a document from `a.md` reads "a.md chunk i".
+    fn seed_store(store: &mut VectorStore, docs: Vec<(Document, Vec<Vec<f32>>)>) {
+        let model = Config::default().default_model().to_string();
+        for (document, vectors) in docs {
+            let doc_id = store.insert_document(&document).unwrap();
+            for (idx, vector) in vectors.into_iter().enumerate() {
+                let content = format!("{} chunk {}", document.source, idx);
+                let chunk_id = store
+                    .insert_chunk(&Chunk::new(doc_id, idx, content))
+                    .unwrap();
+                store
+                    .upsert_embedding(&Embedding::new(chunk_id, model.clone(), vector))
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Source document named after its own text
+    fn doc(source: &str) -> Document {
+        Document::new(source.to_string(), source)
+    }
+
+    async fn get_status(state: AppState, uri: &str) -> StatusCode {
+        router(state)
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    async fn post_status(state: AppState, uri: &str, body: &str) -> StatusCode {
+        router(state)
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::AUTHORIZATION, "Bearer secret")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    /// Log sink shared between a test subscriber and the test
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for CapturedLogs {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_echoed_and_logged() {
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(logs.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = offline_state(&dir);
+
+        let response = router(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri("/api/livez")
+                    .header("x-request-id", "test-req-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-request-id"], "test-req-42");
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            output
+                .lines()
+                .any(|line| line.contains("request_id=test-req-42")),
+            "request id missing from logs: {}",
+            output
+        );
+
+        // Without a client id one is generated
+        let response = router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/livez")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let generated = response.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(generated.len(), 16);
+        assert!(generated.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_unavailable_while_paused() {
+        let (_server, embedder) = mock_embedder(&[0.1, 0.2, 0.3]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("doc.txt");
+        std::fs::write(&file, "Some text to ingest").unwrap();
+        let body = serde_json::json!({ "path": file }).to_string();
+
+        let mut state = offline_state(&dir);
+        state.config.server.api_token = Some("secret".to_string());
+        state.config.server.allow_ingest = true;
+        state.config.server.ingest_root = Some(dir.path().to_path_buf());
+        state.embedder = embedder;
+
+        // Admin endpoints require the token
+        assert_eq!(
+            router(state.clone())
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/admin/pause")
+                        .body(Body::empty())
+                        .unwrap()
+                )
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        assert_eq!(
+            post_status(state.clone(), "/api/admin/pause", "").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            post_status(state.clone(), "/api/ingest", &body).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        assert_eq!(
+            post_status(state.clone(), "/api/admin/resume", "").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            post_status(state.clone(), "/api/ingest", &body).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            VectorStore::from_config(&state.config)
+                .unwrap()
+                .count_documents()
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pause_refused_without_api_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = offline_state(&dir);
+
+        for uri in ["/api/admin/pause", "/api/admin/resume"] {
+            assert_eq!(
+                post_status(state.clone(), uri, "").await,
+                StatusCode::FORBIDDEN,
+                "{}",
+                uri
+            );
+        }
+        assert!(!state.ingestion_paused.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_path_ingest_confined_to_ingest_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("inbox");
+        std::fs::create_dir(&root).unwrap();
+        let outside = dir.path().join("secret.txt");
+        std::fs::write(&outside, "Not for ingestion").unwrap();
+
+        let mut state = offline_state(&dir);
+        state.config.server.allow_ingest = true;
+        state.config.server.ingest_root = Some(root.clone());
+        let body = |path: &Path| serde_json::json!({ "path": path }).to_string();
+
+        // Without an admin token, server files are never read
+        assert_eq!(
+            post_status(state.clone(), "/api/ingest", &body(&outside)).await,
+            StatusCode::FORBIDDEN
+        );
+
+        // With one, only paths that resolve under the root are accepted
+        state.config.server.api_token = Some("secret".to_string());
+        for path in [
+            outside.clone(),
+            root.join("../secret.txt"),
+            root.join("missing.txt"),
+        ] {
+            assert_eq!(
+                post_status(state.clone(), "/api/ingest", &body(&path)).await,
+                StatusCode::FORBIDDEN,
+                "{:?}",
+                path
+            );
+        }
+
+        state.config.server.ingest_root = None;
+        assert_eq!(
+            post_status(state, "/api/ingest", &body(&outside)).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingest_pushed_content_dedups_by_hash() {
+        let (_server, embedder) = mock_embedder(&[0.1, 0.2, 0.3]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.config.server.api_token = Some("secret".to_string());
+        state.embedder = embedder;
+
+        let body = serde_json::json!({
+            "source": "remote://notes/1",
+            "content": "Text pushed by a remote indexer. ".repeat(10),
+            "chunk_size": 100,
+            "overlap": 10
+        })
+        .to_string();
+
+        // Disabled unless the config allows it
+        assert_eq!(
+            post_status(state.clone(), "/api/ingest", &body).await,
+            StatusCode::FORBIDDEN
+        );
+        state.config.server.allow_ingest = true;
+
+        let post = |state: AppState| {
+            let body = body.clone();
+            async move {
+                let response = router(state)
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/ingest")
+                            .header(header::CONTENT_TYPE, "application/json")
+                            .header(header::AUTHORIZATION, "Bearer secret")
+                            .body(Body::from(body))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+
+        let first = post(state.clone()).await;
+        assert_eq!(first["skipped"], false);
+        assert!(first["chunks_created"].as_u64().unwrap() > 1);
+
+        let second = post(state.clone()).await;
+        assert_eq!(second["skipped"], true);
+        assert_eq!(second["document_id"], first["document_id"]);
+
+        let store = VectorStore::new(&state.config.database.path).unwrap();
+        assert_eq!(store.count_documents().unwrap(), 1);
+        let id = first["document_id"].as_i64().unwrap();
+        assert_eq!(
+            store.get_document(id).unwrap().unwrap().source,
+            "remote://notes/1"
+        );
+
+        assert_eq!(
+            post_status(state, "/api/ingest", r#"{"source": "no content"}"#).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_deadline_returns_gateway_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "embedding": [0.1, 0.2, 0.3] }))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.config.search.deadline_ms = Some(50);
+        state.embedder = Arc::new(OllamaClient::new(server.uri(), 5).unwrap().into());
+
+        assert_eq!(
+            get_status(state, "/api/search?query=slow").await,
+            StatusCode::GATEWAY_TIMEOUT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_external_ingest_visible_without_restart() {
+        let (_server, embedder) = mock_embedder(&[1.0, 0.0]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.embedder = embedder;
+        state.config.server.cache_stats = true;
+
+        async fn get_json(state: &AppState, uri: &str) -> serde_json::Value {
+            let response = router(state.clone())
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        assert_eq!(get_json(&state, "/api/stats").await["document_count"], 0);
+        assert_eq!(
+            get_json(&state, "/api/search?query=x").await,
+            serde_json::json!([])
+        );
+
+        // A separate connection stands in for `vectdb ingest` running alongside the server
+        let mut store = VectorStore::new(&state.config.database.path).unwrap();
+        seed_store(&mut store, vec![(doc("new.md"), vec![vec![1.0, 0.0]])]);
+
+        assert_eq!(get_json(&state, "/api/stats").await["document_count"], 1);
+        let results = get_json(&state, "/api/search?query=x").await;
+        assert_eq!(results.as_array().unwrap().len(), 1);
+        assert_eq!(results[0]["source"], "new.md");
+    }
+
+    #[tokio::test]
+    async fn test_search_uses_configured_provider() {
+        use crate::config::Provider;
+        use wiremock::matchers::header;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .and(header("authorization", "Bearer sk-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"index": 0, "embedding": [1.0, 0.0]}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = offline_state(&dir).config;
+        config.provider = Provider::OpenAi;
+        config.openai.base_url = server.uri();
+        config.openai.api_key = Some("sk-test".to_string());
+        let state = AppState::new(config.clone(), Embedder::from_config(&config).unwrap());
+
+        let mut store = VectorStore::new(&config.database.path).unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("doc.md".to_string(), "text"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "text".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_id,
+                "text-embedding-3-small".to_string(),
+                vec![1.0, 0.0],
+            ))
+            .unwrap();
+
+        let response = router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/search?query=anything")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["source"], "doc.md");
+    }
+
+    #[tokio::test]
+    async fn test_search_and_stats_read_from_replica() {
+        let (_server, embedder) = mock_embedder(&[1.0, 0.0]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.embedder = embedder;
+        let replica_path = dir.path().join("replica.db");
+        state.config.database.read_path = Some(replica_path.clone());
+
+        for (db_path, sources) in [
+            (&state.config.database.path, vec!["primary.md"]),
+            (&replica_path, vec!["replica-a.md", "replica-b.md"]),
+        ] {
+            let docs = sources
+                .into_iter()
+                .map(|source| (doc(source), vec![vec![1.0, 0.0]]))
+                .collect();
+            seed_store(&mut VectorStore::new(db_path).unwrap(), docs);
+        }
+
+        let response = router(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri("/api/search?query=anything")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let mut sources: Vec<&str> = results
+            .iter()
+            .map(|r| r["source"].as_str().unwrap())
+            .collect();
+        sources.sort();
+        assert_eq!(sources, ["replica-a.md", "replica-b.md"]);
+
+        let response = router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/stats")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let stats: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(stats["document_count"], 2);
+
+        // The replica is opened read-only
+        let mut replica = VectorStore::open_read_only(&replica_path).unwrap();
+        assert!(
+            replica
+                .insert_document(&Document::new("new.md".to_string(), "new"))
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_sources_one_entry_per_document() {
+        let (_server, embedder) = mock_embedder(&[1.0, 0.0]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.embedder = embedder;
+
+        let mut store = VectorStore::new(&state.config.database.path).unwrap();
+        seed_store(
+            &mut store,
+            vec![
+                (doc("a.md"), vec![vec![1.0, 0.0], vec![0.6, 0.8]]),
+                (doc("b.md"), vec![vec![0.8, 0.6]]),
+            ],
+        );
+
+        let response = router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/search/sources?query=anything")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let sources: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0]["source"], "a.md");
+        assert!((sources[0]["best_similarity"].as_f64().unwrap() - 1.0).abs() < 1e-6);
+        assert_eq!(sources[1]["source"], "b.md");
+        assert!(sources.iter().all(|s| s.get("content").is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_post_search_with_filter() {
+        let (_server, embedder) = mock_embedder(&[1.0, 0.0]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.embedder = embedder;
+
+        let mut store = VectorStore::new(&state.config.database.path).unwrap();
+        let docs = [
+            ("en.md", "en", vec![1.0, 0.0]),
+            ("de.md", "de", vec![0.8, 0.6]),
+        ]
+        .into_iter()
+        .map(|(source, lang, vector)| {
+            let document = doc(source).with_metadata("lang".to_string(), lang.to_string());
+            (document, vec![vector])
+        })
+        .collect();
+        seed_store(&mut store, docs);
+
+        let body = serde_json::json!({
+            "query": "a long query that would not fit comfortably in a URL",
+            "top_k": 5,
+            "filter": { "lang": "de" }
+        });
+        let response = router(state)
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/search")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["source"], "de.md");
+        assert_eq!(results[0]["chunk_index"], 0);
+        assert_eq!(results[0]["content"], "de.md chunk 0");
+        assert!(results[0]["similarity"].as_f64().unwrap() > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_search_history_newest_first() {
+        let (_server, embedder) = mock_embedder(&[1.0, 0.0]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.config.server.api_token = Some("secret".to_string());
+        state.config.server.record_history = true;
+        state.embedder = embedder;
+
+        for query in ["first", "second"] {
+            assert_eq!(
+                get_status(state.clone(), &format!("/api/search?query={}", query)).await,
+                StatusCode::OK
+            );
+        }
+
+        // History is behind the API token
+        assert_eq!(
+            get_status(state.clone(), "/api/history").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let response = router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/history")
+                    .header(header::AUTHORIZATION, "Bearer secret")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let history: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["query"], "second");
+        assert_eq!(history[1]["query"], "first");
+        assert_eq!(history[0]["result_count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_history_refused_without_api_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.config.server.record_history = true;
+
+        assert_eq!(
+            get_status(state, "/api/history").await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_streams_jsonl() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+
+        // Never served without an admin token
+        assert_eq!(
+            get_status(state.clone(), "/api/export").await,
+            StatusCode::FORBIDDEN
+        );
+        state.config.server.api_token = Some("secret".to_string());
+
+        let mut store = VectorStore::new(&state.config.database.path).unwrap();
+        for i in 0..3 {
+            let record: crate::domain::DocumentRecord = serde_json::from_value(serde_json::json!({
+                "source": format!("doc{}.txt", i),
+                "content_hash": format!("hash{}", i),
+                "created_at": 0,
+                "chunks": [{
+                    "chunk_index": 0,
+                    "content": format!("content {}", i),
+                    "token_count": 2,
+                    "embedding": {"model": "model", "vector": [1.0, 0.0]}
+                }]
+            }))
+            .unwrap();
+            assert!(store.import_record(&record).unwrap());
+        }
+
+        assert_eq!(
+            get_status(state.clone(), "/api/export").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let response = router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/export")
+                    .header(header::AUTHORIZATION, "Bearer secret")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let lines: Vec<crate::domain::DocumentRecord> = std::str::from_utf8(&body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2].source, "doc2.txt");
+        assert_eq!(lines[2].chunks[0].content, "content 2");
+    }
+
+    #[test]
+    fn test_stats_schema_documents_counts() {
+        let schema = response_schema("stats").unwrap();
+        assert_eq!(
+            schema["properties"]["document_count"]["type"],
+            serde_json::json!("integer")
+        );
+        assert!(
+            schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&serde_json::json!("document_count"))
+        );
+
+        assert_eq!(response_schema("search").unwrap()["type"], "array");
+        assert!(
+            SCHEMA_TYPES
+                .iter()
+                .all(|name| response_schema(name).is_some())
+        );
+        assert!(response_schema("unknown").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_api_key_required_except_for_health() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.config.server.api_key = Some("reader".to_string());
+        state.config.server.api_token = Some("admin".to_string());
+
+        let status = |uri: &str, token: Option<&str>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            router(state.clone()).oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        // Rejected: no header, wrong token, wrong scheme
+        assert_eq!(
+            status("/api/stats", None).await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status("/api/stats", Some("guess")).await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+        let basic = router(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri("/api/documents")
+                    .header(header::AUTHORIZATION, "Basic cmVhZGVy")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(basic.status(), StatusCode::UNAUTHORIZED);
+
+        // Accepted: the API key, or the admin token
+        assert_eq!(
+            status("/api/stats", Some("reader")).await.unwrap().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            status("/api/documents", Some("admin"))
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::OK
+        );
+
+        // Probes and the UI stay open
+        for uri in ["/api/health", "/api/livez", "/"] {
+            assert_eq!(status(uri, None).await.unwrap().status(), StatusCode::OK);
+        }
+
+        // Without an API key the API is open, as before
+        let open = offline_state(&dir);
+        assert_eq!(get_status(open, "/api/stats").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_alone_does_not_unlock_privileged_endpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        state.config.server.api_key = Some("reader".to_string());
+        state.config.server.record_history = true;
+
+        let status = |method: &str, uri: &str| {
+            router(state.clone()).oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(header::AUTHORIZATION, "Bearer reader")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(
+            status("GET", "/api/stats").await.unwrap().status(),
+            StatusCode::OK
+        );
+        for (method, uri, expected) in [
+            ("GET", "/api/history", StatusCode::FORBIDDEN),
+            ("GET", "/api/export", StatusCode::FORBIDDEN),
+            ("POST", "/api/admin/pause", StatusCode::FORBIDDEN),
+        ] {
+            assert_eq!(
+                status(method, uri).await.unwrap().status(),
+                expected,
+                "{} {}",
+                method,
+                uri
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_only_for_configured_origins() {
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method("OPTIONS")
+                .uri("/api/search")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let dir = tempfile::tempdir().unwrap();
+
+        // Same-origin only by default: no CORS headers
+        let response = router(offline_state(&dir))
+            .oneshot(preflight("http://localhost:5173"))
+            .await
+            .unwrap();
+        assert!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+
+        let mut state = offline_state(&dir);
+        state.config.server.cors_origins = vec!["http://localhost:5173".to_string()];
+        let response = router(state.clone())
+            .oneshot(preflight("http://localhost:5173"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://localhost:5173"
+        );
+        let allowed_methods = response.headers()[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(allowed_methods.contains("POST"), "{}", allowed_methods);
+
+        let response = router(state)
+            .oneshot(preflight("http://evil.example"))
+            .await
+            .unwrap();
+        assert!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_requests_up_to_the_grace_period() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        /// Start `serve_until` on a route that sleeps for `handler_delay`,
+        /// signal shutdown once a request arrives, and return the open connection
+        async fn serve_one(
+            handler_delay: Duration,
+            grace: Duration,
+        ) -> (tokio::task::JoinHandle<Result<()>>, tokio::net::TcpStream) {
+            let (started_tx, started_rx) = tokio::sync::oneshot::channel::<()>();
+            let started_tx = Arc::new(std::sync::Mutex::new(Some(started_tx)));
+            let app = Router::new().route(
+                "/slow",
+                get(move || {
+                    let started_tx = started_tx.clone();
+                    async move {
+                        if let Some(tx) = started_tx.lock().unwrap().take() {
+                            let _ = tx.send(());
+                        }
+                        tokio::time::sleep(handler_delay).await;
+                        "done"
+                    }
+                }),
+            );
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+
+            let server = tokio::spawn(serve_until(
+                listener,
+                app,
+                async move {
+                    let _ = started_rx.await;
+                },
+                grace,
+            ));
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(b"GET /slow HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            (server, stream)
+        }
+
+        // A request that finishes within the grace period is answered
+        let (server, mut stream) =
+            serve_one(Duration::from_millis(100), Duration::from_secs(5)).await;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.ends_with("done"), "{}", response);
+        server.await.unwrap().unwrap();
+
+        // One that runs past it no longer holds up the shutdown
+        let (server, _stream) =
+            serve_one(Duration::from_secs(60), Duration::from_millis(200)).await;
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("shutdown waited past the grace period")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_livez_ok_when_ollama_down() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            get_status(offline_state(&dir), "/api/livez").await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_readyz_unavailable_when_ollama_down() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            get_status(offline_state(&dir), "/api/readyz").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_readyz_unavailable_when_model_cannot_embed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = offline_state(&dir);
+        let model = state.config.default_model().to_string();
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{ "name": model, "size": 1, "modified_at": "" }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("model failed to load"))
+            .mount(&server)
+            .await;
+        state.embedder = Arc::new(OllamaClient::new(server.uri(), 5).unwrap().into());
+
+        let response = router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/readyz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let readiness: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(readiness["ollama_available"], true);
+        assert_eq!(readiness["model_available"], true);
+        assert_eq!(readiness["embedding_available"], false);
+    }
+}
@@ -2,17 +2,153 @@
 //!
 //! Provides functions to split text into chunks for embedding generation.
 
-use crate::domain::ChunkStrategy;
+use crate::domain::{ChunkStrategy, OverlapUnit};
+use crate::error::{Result, VectDbError};
+use serde::Serialize;
+use std::io::Write;
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+use unicode_normalization::UnicodeNormalization;
 use unicode_segmentation::UnicodeSegmentation;
 
 /// Chunk text according to the specified strategy
 pub fn chunk_text(text: &str, strategy: ChunkStrategy) -> Vec<String> {
     match strategy {
-        ChunkStrategy::FixedSize { size, overlap } => chunk_fixed_size(text, size, overlap),
+        ChunkStrategy::FixedSize {
+            size,
+            overlap,
+            overlap_unit: OverlapUnit::Chars,
+        } => chunk_fixed_size(text, size, overlap),
+        ChunkStrategy::FixedSize {
+            size,
+            overlap,
+            overlap_unit,
+        } => chunk_fixed_size_by_units(text, size, overlap, overlap_unit),
         ChunkStrategy::Semantic { max_size } => chunk_semantic(text, max_size),
+        ChunkStrategy::TokenBased {
+            max_tokens,
+            overlap_tokens,
+        } => chunk_by_tokens(text, max_tokens, overlap_tokens),
+        ChunkStrategy::Markdown { max_size } => chunk_markdown(text, max_size),
     }
 }
 
+/// Build a chunking strategy from its CLI name ("fixed", "semantic", "token" or "markdown")
+///
+/// For the semantic and markdown strategies `size` is the maximum chunk size
+/// and `overlap` is ignored. For the token strategy both are counted in tokens.
+/// `overlap_unit` only applies to the fixed strategy.
+pub fn strategy_from_name(
+    name: &str,
+    size: usize,
+    overlap: usize,
+    overlap_unit: OverlapUnit,
+) -> Result<ChunkStrategy> {
+    match name {
+        "fixed" => Ok(ChunkStrategy::FixedSize {
+            size,
+            overlap,
+            overlap_unit,
+        }),
+        "semantic" => Ok(ChunkStrategy::Semantic { max_size: size }),
+        "markdown" => Ok(ChunkStrategy::Markdown { max_size: size }),
+        "token" => Ok(ChunkStrategy::TokenBased {
+            max_tokens: size,
+            overlap_tokens: overlap,
+        }),
+        other => Err(VectDbError::InvalidInput(format!(
+            "Unknown chunk strategy: {}. Available: fixed, semantic, token, markdown",
+            other
+        ))),
+    }
+}
+
+/// Shared BPE tokenizer (cl100k_base), loaded on first use
+fn tokenizer() -> &'static CoreBPE {
+    static TOKENIZER: OnceLock<CoreBPE> = OnceLock::new();
+    TOKENIZER.get_or_init(|| {
+        tiktoken_rs::cl100k_base().expect("bundled cl100k_base vocabulary is valid")
+    })
+}
+
+/// Number of BPE tokens in a text
+///
+/// Counted with the cl100k_base vocabulary. Embedding models have their own
+/// tokenizers, but BPE counts track them far more closely than characters do.
+pub fn count_tokens(text: &str) -> usize {
+    tokenizer().encode_ordinary(text).len()
+}
+
+/// A chunk as shown by the chunk preview
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkPreview {
+    pub index: usize,
+    pub chars: usize,
+    pub tokens: usize,
+    pub content: String,
+}
+
+/// Chunk text and describe each chunk
+pub fn preview_chunks(text: &str, strategy: ChunkStrategy) -> Vec<ChunkPreview> {
+    chunk_text(text, strategy)
+        .into_iter()
+        .enumerate()
+        .map(|(index, content)| ChunkPreview {
+            index,
+            chars: content.chars().count(),
+            tokens: count_tokens(&content),
+            content,
+        })
+        .collect()
+}
+
+/// Write a chunk preview as text (one header line per chunk) or JSON
+pub fn write_chunk_preview<W: Write>(
+    writer: &mut W,
+    chunks: &[ChunkPreview],
+    format: &str,
+) -> Result<()> {
+    match format {
+        "json" => {
+            serde_json::to_writer_pretty(&mut *writer, chunks)?;
+            writeln!(writer)?;
+        }
+        "text" => {
+            for chunk in chunks {
+                writeln!(
+                    writer,
+                    "--- chunk {} ({} chars, {} tokens) ---",
+                    chunk.index, chunk.chars, chunk.tokens
+                )?;
+                writeln!(writer, "{}", chunk.content)?;
+            }
+            writeln!(writer, "{} chunk(s)", chunks.len())?;
+        }
+        other => {
+            return Err(VectDbError::InvalidInput(format!(
+                "Unknown output format: {}. Available: text, json",
+                other
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Normalize text before hashing and chunking
+///
+/// Converts CRLF and lone CR line endings to LF when `newlines` is set, and
+/// applies Unicode NFC normalization when `unicode` is set, so identical text
+/// produced on different platforms hashes and chunks identically.
+pub fn normalize_text(text: &str, newlines: bool, unicode: bool) -> String {
+    let text = if newlines {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        text.to_string()
+    };
+
+    if unicode { text.nfc().collect() } else { text }
+}
+
 /// Chunk text using fixed size with overlap
 fn chunk_fixed_size(text: &str, size: usize, overlap: usize) -> Vec<String> {
     if text.is_empty() {
@@ -48,6 +184,116 @@ fn chunk_fixed_size(text: &str, size: usize, overlap: usize) -> Vec<String> {
     chunks
 }
 
+/// Chunk text into runs of whole words or sentences of at most `size` graphemes
+///
+/// Consecutive chunks share their last `overlap` words or sentences. A single
+/// unit longer than `size` becomes a chunk of its own rather than being cut.
+fn chunk_fixed_size_by_units(
+    text: &str,
+    size: usize,
+    overlap: usize,
+    unit: OverlapUnit,
+) -> Vec<String> {
+    let units: Vec<(usize, &str)> = match unit {
+        OverlapUnit::Words => text.split_word_bound_indices().collect(),
+        OverlapUnit::Sentences => text.split_sentence_bound_indices().collect(),
+        OverlapUnit::Chars => return chunk_fixed_size(text, size, overlap),
+    };
+    // Whitespace between words is carried along but never counted as overlap
+    let counts = |unit: &str| !unit.trim().is_empty();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < units.len() {
+        let mut end = start;
+        let mut length = 0;
+        while end < units.len() {
+            let unit_length = units[end].1.graphemes(true).count();
+            if end > start && length + unit_length > size {
+                break;
+            }
+            length += unit_length;
+            end += 1;
+        }
+
+        let end_byte = units.get(end).map_or(text.len(), |(offset, _)| *offset);
+        let chunk = text[units[start].0..end_byte].trim_end();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+        if end == units.len() {
+            break;
+        }
+
+        // Step back over `overlap` counted units, always moving forward overall
+        let mut next = end;
+        let mut overlapped = 0;
+        while overlapped < overlap && next > start + 1 {
+            next -= 1;
+            if counts(units[next].1) {
+                overlapped += 1;
+            }
+        }
+        while next < units.len() && !counts(units[next].1) {
+            next += 1;
+        }
+        start = next;
+    }
+
+    chunks
+}
+
+/// Chunk text into windows of at most `max_tokens` BPE tokens
+///
+/// Consecutive windows share `overlap_tokens` tokens. A character split over
+/// several tokens is never cut: window edges move to the nearest character
+/// boundary.
+fn chunk_by_tokens(text: &str, max_tokens: usize, overlap_tokens: usize) -> Vec<String> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    if max_tokens <= overlap_tokens {
+        // Invalid configuration - just return the whole text
+        return vec![text.to_string()];
+    }
+
+    let bpe = tokenizer();
+    let tokens = bpe.encode_ordinary(text);
+    let token_count = tokens.len();
+    // A window edge inside a character split over several tokens doesn't decode
+    let window = |start: usize, end: usize| bpe.decode(tokens[start..end].to_vec()).ok();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < token_count {
+        // Back the end off a split character, or move it past one if nothing fits
+        let limit = (start + max_tokens).min(token_count);
+        let Some((end, chunk)) = (start + 1..=limit)
+            .rev()
+            .chain(limit + 1..=token_count)
+            .find_map(|end| window(start, end).map(|chunk| (end, chunk)))
+        else {
+            break;
+        };
+
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk);
+        }
+
+        if end == token_count {
+            break;
+        }
+
+        start = end.saturating_sub(overlap_tokens).max(start + 1);
+        while start < end && window(start, end).is_none() {
+            start += 1;
+        }
+    }
+
+    chunks
+}
+
 /// Chunk text semantically by sentences and paragraphs
 fn chunk_semantic(text: &str, max_size: usize) -> Vec<String> {
     if text.is_empty() {
@@ -113,8 +359,168 @@ fn chunk_semantic(text: &str, max_size: usize) -> Vec<String> {
     chunks
 }
 
+/// A structural unit of a markdown document
+#[derive(Debug, PartialEq)]
+enum MarkdownBlock {
+    /// An ATX heading line (`#` to `######`)
+    Heading(String),
+    /// A paragraph or list, which may be split by sentences
+    Text(String),
+    /// A fenced code block or table, never split
+    Atomic(String),
+}
+
+/// Chunk markdown along heading boundaries
+///
+/// Sections are packed into chunks of up to `max_size` graphemes, each
+/// prefixed with the nearest heading for context. Fenced code blocks and
+/// tables are kept whole even if that makes a chunk longer than `max_size`;
+/// oversized paragraphs are split by sentences.
+fn chunk_markdown(text: &str, max_size: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut current: Vec<String> = Vec::new();
+    let mut current_len = 0;
+
+    let flush = |heading: &Option<String>, current: &mut Vec<String>, chunks: &mut Vec<String>| {
+        if current.is_empty() {
+            return;
+        }
+        let body = current.join("\n\n");
+        chunks.push(match heading {
+            Some(heading) => format!("{}\n\n{}", heading, body),
+            None => body,
+        });
+        current.clear();
+    };
+
+    for block in markdown_blocks(text) {
+        let (content, splittable) = match block {
+            MarkdownBlock::Heading(line) => {
+                flush(&heading, &mut current, &mut chunks);
+                current_len = 0;
+                heading = Some(line);
+                continue;
+            }
+            MarkdownBlock::Text(content) => (content, true),
+            MarkdownBlock::Atomic(content) => (content, false),
+        };
+
+        let prefix_len = heading
+            .as_ref()
+            .map_or(0, |h| h.graphemes(true).count() + 2);
+        let budget = max_size.saturating_sub(prefix_len).max(1);
+        let pieces = if splittable && content.graphemes(true).count() > budget {
+            chunk_semantic(&content, budget)
+        } else {
+            vec![content]
+        };
+
+        for piece in pieces {
+            let piece_len = piece.graphemes(true).count();
+            if !current.is_empty() && current_len + 2 + piece_len > budget {
+                flush(&heading, &mut current, &mut chunks);
+                current_len = 0;
+            }
+            current_len += if current.is_empty() { 0 } else { 2 } + piece_len;
+            current.push(piece);
+        }
+    }
+    flush(&heading, &mut current, &mut chunks);
+
+    chunks
+}
+
+/// Split markdown into headings, paragraphs and unsplittable code blocks and tables
+fn markdown_blocks(text: &str) -> Vec<MarkdownBlock> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut lines = text.lines().peekable();
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<MarkdownBlock>| {
+        if !paragraph.is_empty() {
+            blocks.push(MarkdownBlock::Text(paragraph.join("\n")));
+            paragraph.clear();
+        }
+    };
+
+    while let Some(line) = lines.next() {
+        if let Some(fence) = opening_fence(line) {
+            flush(&mut paragraph, &mut blocks);
+            // An unterminated fence runs to the end of the document
+            let mut code = vec![line];
+            for next in lines.by_ref() {
+                code.push(next);
+                if closes_fence(next, fence) {
+                    break;
+                }
+            }
+            blocks.push(MarkdownBlock::Atomic(code.join("\n")));
+        } else if is_heading(line) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(MarkdownBlock::Heading(line.trim().to_string()));
+        } else if is_table_row(line) {
+            flush(&mut paragraph, &mut blocks);
+            let mut rows = vec![line];
+            while let Some(next) = lines.next_if(|next| is_table_row(next)) {
+                rows.push(next);
+            }
+            blocks.push(MarkdownBlock::Atomic(rows.join("\n")));
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+
+    blocks
+}
+
+/// Fence character and length if `line` opens a fenced code block
+fn opening_fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+
+    let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    (len >= 3).then_some((fence_char, len))
+}
+
+/// Whether `line` closes a code block opened by `fence`
+///
+/// The closing fence must use the same character and be at least as long,
+/// so a longer outer fence can contain shorter fences as literal text.
+fn closes_fence(line: &str, (fence_char, fence_len): (char, usize)) -> bool {
+    let trimmed = line.trim();
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    len >= fence_len && trimmed[len * fence_char.len_utf8()..].trim().is_empty()
+}
+
+/// Whether `line` is an ATX heading such as `## Installation`
+fn is_heading(line: &str) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    }
+
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&level)
+        && trimmed[level..]
+            .chars()
+            .next()
+            .is_none_or(|c| c.is_whitespace())
+}
+
+/// Whether `line` is a row of a pipe table
+fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
 /// Split text into sentences (simple implementation)
-fn split_into_sentences(text: &str) -> Vec<&str> {
+pub fn split_into_sentences(text: &str) -> Vec<&str> {
     let mut sentences = Vec::new();
     let mut start = 0;
 
@@ -154,6 +560,152 @@ fn split_into_sentences(text: &str) -> Vec<&str> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_chunk_preview_of_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known.txt");
+        std::fs::write(&path, "a".repeat(25)).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let strategy = strategy_from_name("fixed", 10, 0, OverlapUnit::Chars).unwrap();
+        let chunks = preview_chunks(&text, strategy);
+
+        let mut output = Vec::new();
+        write_chunk_preview(&mut output, &chunks, "text").unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("--- chunk 0 (10 chars, 2 tokens) ---"));
+        assert!(output.contains("--- chunk 1 (10 chars, 2 tokens) ---"));
+        assert!(output.contains("--- chunk 2 (5 chars, 2 tokens) ---"));
+        assert!(output.ends_with("3 chunk(s)\n"));
+
+        let mut json = Vec::new();
+        write_chunk_preview(&mut json, &chunks, "json").unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let sizes: Vec<u64> = parsed
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["chars"].as_u64().unwrap())
+            .collect();
+        assert_eq!(sizes, vec![10, 10, 5]);
+
+        assert!(strategy_from_name("sliding", 10, 0, OverlapUnit::Chars).is_err());
+    }
+
+    #[test]
+    fn test_chunk_by_tokens() {
+        let text = "The borrow checker enforces ownership rules at compile time. \
+                    Émigré café naïveté — 日本語のテキスト 🦀🦀🦀. "
+            .repeat(20);
+        let total = count_tokens(&text);
+        assert!(total > 100);
+
+        let chunks = chunk_text(
+            &text,
+            ChunkStrategy::TokenBased {
+                max_tokens: 32,
+                overlap_tokens: 8,
+            },
+        );
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(count_tokens(chunk) <= 32, "chunk too long: {:?}", chunk);
+        }
+
+        // Without overlap the chunks reassemble into the original text
+        let chunks = chunk_text(
+            &text,
+            ChunkStrategy::TokenBased {
+                max_tokens: 32,
+                overlap_tokens: 0,
+            },
+        );
+        assert_eq!(chunks.concat(), text);
+
+        let strategy = strategy_from_name("token", 32, 8, OverlapUnit::Chars).unwrap();
+        assert!(matches!(
+            strategy,
+            ChunkStrategy::TokenBased {
+                max_tokens: 32,
+                overlap_tokens: 8
+            }
+        ));
+        assert_eq!(count_tokens("Hello, world!"), 4);
+    }
+
+    const MARKDOWN_DOC: &str = "# Guide
+
+Intro paragraph for the guide.
+
+## Install
+
+Run the installer first. Then check the version.
+
+```bash
+# not a heading inside a fence
+cargo install vectdb
+
+vectdb --version
+```
+
+## Options
+
+| Option | Default |
+|--------|---------|
+| --size | 512     |
+| --mode | fixed   |
+
+Closing notes about options.
+";
+
+    #[test]
+    fn test_chunk_markdown_sections() {
+        let chunks = chunk_text(MARKDOWN_DOC, ChunkStrategy::Markdown { max_size: 80 });
+
+        // Every chunk is prefixed with its heading
+        assert_eq!(chunks[0], "# Guide\n\nIntro paragraph for the guide.");
+        assert!(chunks[1..].iter().all(|c| c.starts_with("## ")));
+        assert!(!chunks.iter().any(|c| c.starts_with("# not a heading")));
+
+        // The code block stays whole, blank line and comment included
+        let code = chunks.iter().find(|c| c.contains("cargo install")).unwrap();
+        assert!(code.starts_with("## Install\n\n"));
+        assert!(code.contains(
+            "```bash\n# not a heading inside a fence\ncargo install vectdb\n\nvectdb --version\n```"
+        ));
+
+        // So does the table
+        let table = chunks.iter().find(|c| c.contains("| Option")).unwrap();
+        assert!(table.starts_with("## Options\n\n"));
+        assert!(table.contains("| --size | 512     |\n| --mode | fixed   |"));
+
+        // A large budget keeps each section in one chunk
+        let chunks = chunk_text(MARKDOWN_DOC, ChunkStrategy::Markdown { max_size: 1000 });
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[2].ends_with("Closing notes about options."));
+    }
+
+    #[test]
+    fn test_chunk_markdown_nested_and_unterminated_fences() {
+        let nested = "## Example\n\n````markdown\n```rust\nfn main() {}\n```\n````\n\nAfter.";
+        let chunks = chunk_text(nested, ChunkStrategy::Markdown { max_size: 30 });
+        assert_eq!(
+            chunks[0],
+            "## Example\n\n````markdown\n```rust\nfn main() {}\n```\n````"
+        );
+        assert_eq!(chunks[1], "## Example\n\nAfter.");
+
+        let unterminated = "# Notes\n\n```\ncode without an end\n# still code";
+        let chunks = chunk_text(unterminated, ChunkStrategy::Markdown { max_size: 30 });
+        assert_eq!(
+            chunks,
+            ["# Notes\n\n```\ncode without an end\n# still code"]
+        );
+
+        assert!(chunk_text("", ChunkStrategy::Markdown { max_size: 10 }).is_empty());
+    }
+
     #[test]
     fn test_chunk_fixed_size_simple() {
         let text = "Hello world! This is a test.";
@@ -201,6 +753,74 @@ mod tests {
         assert!(!chunks.is_empty());
     }
 
+    #[test]
+    fn test_chunk_fixed_size_word_overlap() {
+        let text = "The quick brown fox jumps over the lazy dog again";
+        let chunks = chunk_text(
+            text,
+            ChunkStrategy::FixedSize {
+                size: 20,
+                overlap: 2,
+                overlap_unit: OverlapUnit::Words,
+            },
+        );
+
+        assert_eq!(
+            chunks,
+            [
+                "The quick brown fox",
+                "brown fox jumps over",
+                "jumps over the lazy",
+                "the lazy dog again"
+            ]
+        );
+
+        // Every chunk is made of whole words from the text
+        let words: Vec<&str> = text.split(' ').collect();
+        for chunk in &chunks {
+            assert!(chunk.split(' ').all(|word| words.contains(&word)));
+        }
+
+        // Each chunk starts with the last two words of the one before it
+        for pair in chunks.windows(2) {
+            let previous: Vec<&str> = pair[0].split(' ').collect();
+            let overlap = previous[previous.len() - 2..].join(" ");
+            assert!(pair[1].starts_with(&overlap));
+        }
+    }
+
+    #[test]
+    fn test_chunk_fixed_size_sentence_overlap() {
+        let text = "First one. Second one. Third one. Fourth one.";
+        let chunks = chunk_text(
+            text,
+            ChunkStrategy::FixedSize {
+                size: 25,
+                overlap: 1,
+                overlap_unit: OverlapUnit::Sentences,
+            },
+        );
+        assert_eq!(
+            chunks,
+            [
+                "First one. Second one.",
+                "Second one. Third one.",
+                "Third one. Fourth one."
+            ]
+        );
+
+        // A word longer than the chunk size is kept whole
+        let chunks = chunk_text(
+            "tiny enormousword tiny",
+            ChunkStrategy::FixedSize {
+                size: 5,
+                overlap: 0,
+                overlap_unit: OverlapUnit::Words,
+            },
+        );
+        assert_eq!(chunks, ["tiny", "enormousword", "tiny"]);
+    }
+
     #[test]
     fn test_chunk_text_wrapper() {
         let text = "Hello world! This is a test.";
@@ -210,6 +830,7 @@ mod tests {
             ChunkStrategy::FixedSize {
                 size: 10,
                 overlap: 2,
+                overlap_unit: OverlapUnit::Chars,
             },
         );
         assert!(!fixed.is_empty());
@@ -225,4 +846,13 @@ mod tests {
 
         assert!(sentences.len() >= 4);
     }
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("a\r\nb\rc\n", true, false), "a\nb\nc\n");
+        assert_eq!(normalize_text("a\r\nb", false, false), "a\r\nb");
+
+        // "e" followed by a combining acute accent composes to "é"
+        assert_eq!(normalize_text("e\u{0301}", false, true), "\u{00e9}");
+    }
 }
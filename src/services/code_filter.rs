@@ -0,0 +1,348 @@
+//! Comment filtering for source code
+//!
+//! Splits source files into code and comment segments using simple lexical
+//! rules, so code can be indexed without its comments or comments and
+//! docstrings can be indexed on their own.
+
+use crate::error::{Result, VectDbError};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Which parts of a source file to keep before chunking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodeFilter {
+    /// Keep the file unchanged
+    #[default]
+    All,
+    /// Strip comments and docstrings
+    CodeOnly,
+    /// Keep only comments and docstrings
+    CommentsOnly,
+}
+
+impl CodeFilter {
+    /// Name used on the command line and in document metadata
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CodeFilter::All => "all",
+            CodeFilter::CodeOnly => "code-only",
+            CodeFilter::CommentsOnly => "comments-only",
+        }
+    }
+}
+
+impl fmt::Display for CodeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CodeFilter {
+    type Err = VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(CodeFilter::All),
+            "code-only" | "code" => Ok(CodeFilter::CodeOnly),
+            "comments-only" | "comments" => Ok(CodeFilter::CommentsOnly),
+            other => Err(VectDbError::InvalidInput(format!(
+                "Invalid code filter: {}. Use all, code-only or comments-only",
+                other
+            ))),
+        }
+    }
+}
+
+/// Comment syntax family of a recognized language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    /// Rust: `//` and `/* */` comments, `'a` lifetimes
+    Rust,
+    /// C-like languages: `//` and `/* */` comments
+    CLike,
+    /// Python: `#` comments and `"""` docstrings
+    Python,
+    /// Shell-like languages: `#` comments
+    Shell,
+}
+
+/// File extensions recognized as source code
+pub const CODE_EXTENSIONS: [&str; 13] = [
+    "rs", "c", "h", "cpp", "hpp", "go", "java", "js", "ts", "py", "sh", "bash", "rb",
+];
+
+impl CodeLanguage {
+    /// Detect the language from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "rs" => Some(CodeLanguage::Rust),
+            "c" | "h" | "cpp" | "hpp" | "go" | "java" | "js" | "ts" => Some(CodeLanguage::CLike),
+            "py" => Some(CodeLanguage::Python),
+            "sh" | "bash" | "rb" => Some(CodeLanguage::Shell),
+            _ => None,
+        }
+    }
+
+    fn slash_comments(&self) -> bool {
+        matches!(self, CodeLanguage::Rust | CodeLanguage::CLike)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SegmentKind {
+    Code,
+    Comment,
+}
+
+/// Apply a code filter to source text
+pub fn filter_code(text: &str, language: CodeLanguage, filter: CodeFilter) -> String {
+    match filter {
+        CodeFilter::All => text.to_string(),
+        CodeFilter::CodeOnly => {
+            let code: String = segments(text, language)
+                .into_iter()
+                .filter(|(kind, _)| *kind == SegmentKind::Code)
+                .map(|(_, segment)| segment)
+                .collect();
+            tidy_lines(&code)
+        }
+        CodeFilter::CommentsOnly => segments(text, language)
+            .into_iter()
+            .filter(|(kind, _)| *kind == SegmentKind::Comment)
+            .map(|(_, segment)| strip_comment_markers(segment))
+            .filter(|comment| !comment.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Split source text into code and comment segments
+fn segments(text: &str, language: CodeLanguage) -> Vec<(SegmentKind, &str)> {
+    let bytes = text.as_bytes();
+    let mut result = Vec::new();
+    let mut code_start = 0;
+    let mut i = 0;
+
+    let mut push_comment = |result: &mut Vec<_>, start: usize, end: usize| {
+        if code_start < start {
+            result.push((SegmentKind::Code, &text[code_start..start]));
+        }
+        result.push((SegmentKind::Comment, &text[start..end]));
+        code_start = end;
+    };
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+
+        if language.slash_comments() && rest.starts_with(b"//") {
+            let end = find_from(bytes, i, b"\n").unwrap_or(bytes.len());
+            push_comment(&mut result, i, end);
+            i = end;
+        } else if language.slash_comments() && rest.starts_with(b"/*") {
+            let end = find_from(bytes, i + 2, b"*/").map_or(bytes.len(), |pos| pos + 2);
+            push_comment(&mut result, i, end);
+            i = end;
+        } else if !language.slash_comments() && rest[0] == b'#' {
+            let end = find_from(bytes, i, b"\n").unwrap_or(bytes.len());
+            push_comment(&mut result, i, end);
+            i = end;
+        } else if language == CodeLanguage::Python
+            && (rest.starts_with(b"\"\"\"") || rest.starts_with(b"'''"))
+        {
+            let end = find_from(bytes, i + 3, &rest[..3]).map_or(bytes.len(), |pos| pos + 3);
+            // A triple-quoted string that starts a line is a docstring
+            if starts_line(bytes, i) {
+                push_comment(&mut result, i, end);
+            }
+            i = end;
+        } else if rest[0] == b'"' || rest[0] == b'`' {
+            i = skip_string(bytes, i, rest[0]);
+        } else if rest[0] == b'\'' {
+            i = if language == CodeLanguage::Rust && !is_char_literal(rest) {
+                // Lifetime or label, not a quoted literal
+                i + 1
+            } else {
+                skip_string(bytes, i, b'\'')
+            };
+        } else {
+            i += 1;
+        }
+    }
+
+    if code_start < bytes.len() {
+        result.push((SegmentKind::Code, &text[code_start..]));
+    }
+
+    result
+}
+
+/// Find `needle` in `bytes` at or after `from`
+fn find_from(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| from + pos)
+}
+
+/// Whether only whitespace precedes `pos` on its line
+fn starts_line(bytes: &[u8], pos: usize) -> bool {
+    bytes[..pos]
+        .iter()
+        .rev()
+        .take_while(|&&b| b != b'\n')
+        .all(|b| b.is_ascii_whitespace())
+}
+
+/// Skip a quoted string starting at `start`, returning the index after it
+fn skip_string(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            // Unterminated single-line literals end at the newline
+            b'\n' if quote != b'`' && quote != b'"' => return i,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Whether a Rust `'` starts a char literal (`'x'`, `'\n'`) rather than a lifetime
+fn is_char_literal(rest: &[u8]) -> bool {
+    if rest.get(1) == Some(&b'\\') {
+        return true;
+    }
+    let head = &rest[..rest.len().min(8)];
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) => std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or(""),
+    };
+    let mut chars = text.chars().skip(1);
+    chars.next().is_some() && chars.next() == Some('\'')
+}
+
+/// Remove comment delimiters and leading `*` from a comment segment
+fn strip_comment_markers(comment: &str) -> String {
+    let trimmed = comment.trim();
+    let body = if let Some(block) = trimmed.strip_prefix("/*") {
+        block.strip_suffix("*/").unwrap_or(block)
+    } else if trimmed.starts_with("\"\"\"") || trimmed.starts_with("'''") {
+        let quote = &trimmed[..3];
+        let inner = &trimmed[3..];
+        inner.strip_suffix(quote).unwrap_or(inner)
+    } else {
+        trimmed
+    };
+
+    body.lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches('/')
+                .trim_start_matches('#')
+                .trim_start_matches(['*', '!'])
+                .trim()
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Trim trailing whitespace and collapse the blank lines left by removed comments
+fn tidy_lines(text: &str) -> String {
+    let mut output = Vec::new();
+    let mut previous_blank = false;
+
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            if !previous_blank && !output.is_empty() {
+                output.push(line);
+            }
+            previous_blank = true;
+        } else {
+            output.push(line);
+            previous_blank = false;
+        }
+    }
+
+    output.join("\n").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST_SOURCE: &str = r#"//! Crate docs for the parser
+
+/// Adds two numbers together
+fn add(a: i32, b: i32) -> i32 {
+    // inline explanation
+    let url = "http://example.com"; /* block note */
+    let c = '"';
+    a + b
+}
+
+fn longest<'a>(x: &'a str) -> &'a str {
+    x
+}
+"#;
+
+    #[test]
+    fn test_code_only_strips_comments() {
+        let code = filter_code(RUST_SOURCE, CodeLanguage::Rust, CodeFilter::CodeOnly);
+
+        assert!(code.contains("fn add(a: i32, b: i32) -> i32 {"));
+        assert!(code.contains("let url = \"http://example.com\";"));
+        assert!(code.contains("let c = '\"';"));
+        assert!(code.contains("fn longest<'a>(x: &'a str) -> &'a str {"));
+        assert!(!code.contains("Crate docs"));
+        assert!(!code.contains("Adds two numbers"));
+        assert!(!code.contains("inline explanation"));
+        assert!(!code.contains("block note"));
+    }
+
+    #[test]
+    fn test_comments_only_keeps_comments() {
+        let comments = filter_code(RUST_SOURCE, CodeLanguage::Rust, CodeFilter::CommentsOnly);
+
+        assert_eq!(
+            comments,
+            "Crate docs for the parser\nAdds two numbers together\ninline explanation\nblock note"
+        );
+    }
+
+    #[test]
+    fn test_all_keeps_everything() {
+        let all = filter_code(RUST_SOURCE, CodeLanguage::Rust, CodeFilter::All);
+        assert_eq!(all, RUST_SOURCE);
+    }
+
+    #[test]
+    fn test_python_docstrings() {
+        let source = "def f():\n    \"\"\"Return one.\"\"\"\n    x = \"# not a comment\"  # real comment\n    return 1\n";
+
+        let code = filter_code(source, CodeLanguage::Python, CodeFilter::CodeOnly);
+        assert!(code.contains("x = \"# not a comment\""));
+        assert!(!code.contains("Return one."));
+        assert!(!code.contains("real comment"));
+
+        let comments = filter_code(source, CodeLanguage::Python, CodeFilter::CommentsOnly);
+        assert_eq!(comments, "Return one.\nreal comment");
+    }
+
+    #[test]
+    fn test_parse_code_filter() {
+        assert_eq!(
+            "code-only".parse::<CodeFilter>().unwrap(),
+            CodeFilter::CodeOnly
+        );
+        assert_eq!(
+            "comments-only".parse::<CodeFilter>().unwrap(),
+            CodeFilter::CommentsOnly
+        );
+        assert!("everything".parse::<CodeFilter>().is_err());
+    }
+}
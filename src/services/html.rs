@@ -0,0 +1,262 @@
+//! Readable text extraction from HTML pages
+//!
+//! A small lexical pass rather than a full HTML parser: scripts, styles and
+//! comments are dropped, block-level tags become paragraph breaks, other tags
+//! are removed, entities are decoded and whitespace is collapsed. That is
+//! enough to embed saved web pages without their markup noise.
+
+/// Text extracted from an HTML page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlText {
+    /// Readable body text, one paragraph per block element
+    pub text: String,
+    /// Contents of the `<title>` element, if present and non-empty
+    pub title: Option<String>,
+}
+
+/// Elements whose content is never readable text
+const SKIPPED_ELEMENTS: [&str; 6] = ["script", "style", "noscript", "template", "svg", "head"];
+
+/// Elements that start a new paragraph
+const BLOCK_ELEMENTS: [&str; 30] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Extract the readable text and title of an HTML page
+pub fn html_to_text(html: &str) -> HtmlText {
+    // ASCII lowercasing keeps byte offsets identical to the original
+    let lower = html.to_ascii_lowercase();
+    let title = element_content(html, &lower, "title")
+        .map(|title| collapse_whitespace(&decode_entities(&strip_tags(title))))
+        .filter(|title| !title.is_empty());
+
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    let mut pos = 0;
+
+    while let Some(offset) = html[pos..].find('<') {
+        let start = pos + offset;
+        current.push_str(&html[pos..start]);
+
+        if lower[start..].starts_with("<!--") {
+            pos = lower[start..]
+                .find("-->")
+                .map_or(html.len(), |end| start + end + 3);
+            continue;
+        }
+
+        let Some(end) = html[start..].find('>').map(|end| start + end + 1) else {
+            // Unterminated tag: keep the rest as text
+            current.push_str(&html[start..]);
+            pos = html.len();
+            break;
+        };
+        let (name, closing) = tag_name(&lower[start..end]);
+        pos = end;
+
+        if !closing && SKIPPED_ELEMENTS.contains(&name) {
+            let close = format!("</{}", name);
+            pos = lower[pos..]
+                .find(&close)
+                .and_then(|close_start| {
+                    let close_start = pos + close_start;
+                    lower[close_start..]
+                        .find('>')
+                        .map(|gt| close_start + gt + 1)
+                })
+                .unwrap_or(html.len());
+        } else if BLOCK_ELEMENTS.contains(&name) {
+            flush_paragraph(&mut current, &mut paragraphs);
+        }
+    }
+    current.push_str(&html[pos..]);
+    flush_paragraph(&mut current, &mut paragraphs);
+
+    HtmlText {
+        text: paragraphs.join("\n\n"),
+        title,
+    }
+}
+
+/// Lowercased name of a tag such as `<div class="x">` or `</p>`, and whether it closes
+fn tag_name(tag: &str) -> (&str, bool) {
+    let inner = tag.trim_start_matches('<');
+    let (inner, closing) = match inner.strip_prefix('/') {
+        Some(rest) => (rest, true),
+        None => (inner, false),
+    };
+    let end = inner
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(inner.len());
+    (&inner[..end], closing)
+}
+
+/// Raw content of the first `<name>` element
+fn element_content<'a>(html: &'a str, lower: &str, name: &str) -> Option<&'a str> {
+    let open = lower.find(&format!("<{}", name))?;
+    let content_start = open + lower[open..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find(&format!("</{}", name))?;
+    Some(&html[content_start..content_end])
+}
+
+/// Remove any tags from a fragment
+fn strip_tags(fragment: &str) -> String {
+    let mut text = String::with_capacity(fragment.len());
+    let mut in_tag = false;
+    for c in fragment.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Decode and collapse a paragraph's text, keeping it if anything remains
+fn flush_paragraph(current: &mut String, paragraphs: &mut Vec<String>) {
+    let paragraph = collapse_whitespace(&decode_entities(current));
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    current.clear();
+}
+
+/// Replace runs of whitespace with single spaces and trim the ends
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decode numeric character references and the common named entities
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&semi| semi <= 10)
+            .and_then(|semi| decode_entity(&rest[1..=semi]).map(|c| (c, semi + 2)));
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Character for an entity name (without `&` and `;`)
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>Ownership &amp; Borrowing</title>
+  <style>body { color: red; }</style>
+  <script>var tracking = "secret";</script>
+</head>
+<body>
+  <!-- navigation removed -->
+  <h1>Ownership</h1>
+  <p>Each value   has a
+     <b>single</b> owner.</p>
+  <SCRIPT type="text/javascript">alert("hi");</SCRIPT>
+  <p>Use &lt;&amp;T&gt; to borrow &#8212; no copies&#x21;</p>
+</body>
+</html>"#;
+
+    #[test]
+    fn test_html_to_text_strips_scripts_and_markup() {
+        let extracted = html_to_text(PAGE);
+
+        assert_eq!(extracted.title.as_deref(), Some("Ownership & Borrowing"));
+        assert_eq!(
+            extracted.text,
+            "Ownership\n\nEach value has a single owner.\n\nUse <&T> to borrow — no copies!"
+        );
+        assert!(!extracted.text.contains("tracking"));
+        assert!(!extracted.text.contains("alert"));
+        assert!(!extracted.text.contains("color"));
+        assert!(!extracted.text.contains("navigation"));
+    }
+
+    #[test]
+    fn test_html_to_text_without_title_keeps_unknown_entities() {
+        let extracted = html_to_text("<div>Fish &chips; &amp; more</div>");
+
+        assert_eq!(extracted.title, None);
+        assert_eq!(extracted.text, "Fish &chips; & more");
+    }
+}
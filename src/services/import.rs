@@ -0,0 +1,402 @@
+//! Bulk import of JSONL dumps and other databases
+//!
+//! Each line of the input is a [`DocumentRecord`]. Records are committed in
+//! batches together with a checkpoint of the number of lines consumed, so an
+//! interrupted import can be resumed without re-reading committed rows.
+//! Every embedding of a model must have the same dimension, within the dump
+//! and against what the store already holds.
+//!
+//! [`merge_store`] copies another database's documents the same way, record
+//! by record, so row ids are reassigned in the target.
+
+use crate::domain::DocumentRecord;
+use crate::error::{Result, VectDbError};
+use crate::repositories::VectorStore;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Options controlling a bulk import
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Continue from the last checkpoint instead of the start of the file
+    pub resume: bool,
+
+    /// Number of records committed per transaction
+    pub batch_size: usize,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            resume: false,
+            batch_size: 500,
+        }
+    }
+}
+
+/// Outcome of a bulk import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Line the import started from (non-zero when resumed)
+    pub resumed_from: u64,
+
+    /// Documents inserted
+    pub imported: usize,
+
+    /// Documents skipped because their content hash already existed
+    pub skipped: usize,
+}
+
+/// Import a JSONL dump into the store
+pub fn import_jsonl(
+    store: &mut VectorStore,
+    input: &Path,
+    options: &ImportOptions,
+) -> Result<ImportSummary> {
+    let key = checkpoint_key(input)?;
+    let start = if options.resume {
+        store.get_import_checkpoint(&key)?.unwrap_or(0)
+    } else {
+        store.clear_import_checkpoint(&key)?;
+        0
+    };
+
+    if start > 0 {
+        info!("Resuming import of {:?} from line {}", input, start + 1);
+    }
+
+    let mut summary = ImportSummary {
+        resumed_from: start,
+        ..Default::default()
+    };
+    let mut pending = Vec::with_capacity(options.batch_size);
+    let mut next_line = start;
+    let mut dimensions = HashMap::new();
+
+    let reader = BufReader::new(File::open(input)?);
+    for (line_no, line) in reader.lines().enumerate().skip(start as usize) {
+        let line = line?;
+
+        if !line.trim().is_empty() {
+            let record = serde_json::from_str::<DocumentRecord>(&line)
+                .map_err(|e| e.to_string())
+                .and_then(|record| {
+                    check_dimensions(store, &record, &mut dimensions)
+                        .map(|()| record)
+                        .map_err(|e| e.to_string())
+                });
+            match record {
+                Ok(record) => pending.push(record),
+                Err(e) => {
+                    // Keep what was read so far, so a fixed file can be resumed here
+                    commit_batch(store, &key, &pending, line_no as u64, &mut summary)?;
+                    return Err(VectDbError::InvalidInput(format!(
+                        "Invalid record at {:?} line {}: {}",
+                        input,
+                        line_no + 1,
+                        e
+                    )));
+                }
+            }
+        }
+
+        next_line = line_no as u64 + 1;
+        if pending.len() >= options.batch_size.max(1) {
+            commit_batch(store, &key, &pending, next_line, &mut summary)?;
+            pending.clear();
+        }
+    }
+
+    commit_batch(store, &key, &pending, next_line, &mut summary)?;
+    store.clear_import_checkpoint(&key)?;
+
+    info!(
+        "Imported {} document(s), skipped {} duplicate(s)",
+        summary.imported, summary.skipped
+    );
+
+    Ok(summary)
+}
+
+/// Outcome of merging another database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Documents copied into the target
+    pub added: usize,
+
+    /// Documents skipped because their content hash already existed
+    pub skipped: usize,
+}
+
+/// Copy the documents, chunks and embeddings of `other` into `store`
+///
+/// Documents whose content hash is already present are skipped; chunk and
+/// document ids are reassigned by the target. Every record's embeddings are
+/// checked like an import's, and any mismatch fails the merge without writing
+/// anything.
+pub fn merge_store(store: &mut VectorStore, other: &VectorStore) -> Result<MergeSummary> {
+    let ids = other.document_ids()?;
+    info!("Merging {} document(s)", ids.len());
+
+    let summary = store.in_transaction(|store| {
+        let mut summary = MergeSummary::default();
+        let mut dimensions = HashMap::new();
+        for id in ids {
+            let Some(record) = other.document_record(id)? else {
+                continue;
+            };
+            check_dimensions(store, &record, &mut dimensions).map_err(|e| {
+                VectDbError::InvalidInput(format!("Cannot merge {}: {}", record.source, e))
+            })?;
+            if store.import_record(&record)? {
+                summary.added += 1;
+            } else {
+                summary.skipped += 1;
+            }
+        }
+        Ok(summary)
+    })?;
+
+    info!(
+        "Merged {} document(s), skipped {} duplicate(s)",
+        summary.added, summary.skipped
+    );
+
+    Ok(summary)
+}
+
+/// Insert a batch of records and advance the checkpoint in one transaction
+fn commit_batch(
+    store: &mut VectorStore,
+    key: &str,
+    records: &[DocumentRecord],
+    next_line: u64,
+    summary: &mut ImportSummary,
+) -> Result<()> {
+    let (imported, skipped) = store.in_transaction(|store| {
+        let mut imported = 0;
+        for record in records {
+            if store.import_record(record)? {
+                imported += 1;
+            }
+        }
+        store.set_import_checkpoint(key, next_line)?;
+        Ok((imported, records.len() - imported))
+    })?;
+
+    debug!(
+        "Committed {} record(s), checkpoint at line {}",
+        records.len(),
+        next_line
+    );
+
+    summary.imported += imported;
+    summary.skipped += skipped;
+    Ok(())
+}
+
+/// Check that a record's embeddings have one dimension per model, matching the store
+///
+/// `dimensions` remembers the dimension of each model seen so far, so models
+/// first introduced earlier in the same import are checked too.
+fn check_dimensions(
+    store: &VectorStore,
+    record: &DocumentRecord,
+    dimensions: &mut HashMap<String, usize>,
+) -> Result<()> {
+    for chunk in &record.chunks {
+        let Some(embedding) = &chunk.embedding else {
+            continue;
+        };
+        let dimension = embedding.vector.len();
+        if dimension == 0 {
+            return Err(VectDbError::InvalidInput(format!(
+                "chunk {} has an empty embedding",
+                chunk.chunk_index
+            )));
+        }
+
+        let expected = match dimensions.get(&embedding.model) {
+            Some(&expected) => expected,
+            None => {
+                let expected = store
+                    .model_dimension(&embedding.model)?
+                    .unwrap_or(dimension);
+                dimensions.insert(embedding.model.clone(), expected);
+                expected
+            }
+        };
+        if dimension != expected {
+            return Err(VectDbError::InvalidInput(format!(
+                "chunk {} has a {}-dimensional {} embedding, expected {}",
+                chunk.chunk_index, dimension, embedding.model, expected
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checkpoints are keyed by the canonical input path
+fn checkpoint_key(input: &Path) -> Result<String> {
+    Ok(input.canonicalize()?.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ChunkRecord, EmbeddingRecord};
+
+    fn record(idx: usize) -> DocumentRecord {
+        DocumentRecord {
+            source: format!("doc{}.txt", idx),
+            content_hash: format!("hash-{}", idx),
+            metadata: Default::default(),
+            created_at: idx as i64,
+            chunks: (0..2)
+                .map(|chunk_index| ChunkRecord {
+                    chunk_index,
+                    content: format!("Document {} chunk {}", idx, chunk_index),
+                    token_count: Some(4),
+                    embedding: Some(EmbeddingRecord {
+                        model: "test-model".to_string(),
+                        vector: vec![idx as f32, chunk_index as f32],
+                    }),
+                })
+                .collect(),
+        }
+    }
+
+    fn counts(store: &VectorStore) -> (i64, i64, i64) {
+        let stats = store.get_stats().unwrap();
+        (
+            stats.document_count,
+            stats.chunk_count,
+            stats.embedding_count,
+        )
+    }
+
+    #[test]
+    fn test_resume_interrupted_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("dump.jsonl");
+        let lines: Vec<String> = (0..6)
+            .map(|idx| serde_json::to_string(&record(idx)).unwrap())
+            .collect();
+        let options = ImportOptions {
+            resume: true,
+            batch_size: 2,
+        };
+
+        let mut full = VectorStore::in_memory().unwrap();
+        std::fs::write(&input, lines.join("\n")).unwrap();
+        import_jsonl(&mut full, &input, &options).unwrap();
+
+        // Simulate an import interrupted by a partially written line
+        let mut truncated = lines[..3].to_vec();
+        truncated.push(lines[3][..10].to_string());
+        std::fs::write(&input, truncated.join("\n")).unwrap();
+
+        let mut store = VectorStore::in_memory().unwrap();
+        assert!(import_jsonl(&mut store, &input, &options).is_err());
+        assert_eq!(store.count_documents().unwrap(), 3);
+
+        std::fs::write(&input, lines.join("\n")).unwrap();
+        let summary = import_jsonl(&mut store, &input, &options).unwrap();
+
+        assert_eq!(summary.resumed_from, 3);
+        assert_eq!(summary.imported, 3);
+        assert_eq!(summary.skipped, 0);
+        assert_eq!(counts(&store), counts(&full));
+        assert_eq!(counts(&store), (6, 12, 12));
+    }
+
+    #[test]
+    fn test_import_rejects_inconsistent_dimensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("dump.jsonl");
+        let mut store = VectorStore::in_memory().unwrap();
+
+        // The second record's chunks disagree with each other
+        let mut ragged = record(1);
+        ragged.chunks[1]
+            .embedding
+            .as_mut()
+            .unwrap()
+            .vector
+            .push(0.0);
+        let lines = [record(0), ragged, record(2)]
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap())
+            .collect::<Vec<_>>();
+        std::fs::write(&input, lines.join("\n")).unwrap();
+
+        let err = import_jsonl(&mut store, &input, &ImportOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+        assert!(err.to_string().contains("3-dimensional"), "{}", err);
+        assert_eq!(counts(&store), (1, 2, 2));
+
+        // Consistent within the dump, but not with what is already stored
+        let mut wide = record(3);
+        for chunk in &mut wide.chunks {
+            chunk.embedding.as_mut().unwrap().vector.push(0.0);
+        }
+        std::fs::write(&input, serde_json::to_string(&wide).unwrap()).unwrap();
+        assert!(import_jsonl(&mut store, &input, &ImportOptions::default()).is_err());
+        assert_eq!(counts(&store), (1, 2, 2));
+    }
+
+    #[test]
+    fn test_merge_dedups_and_remaps_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = VectorStore::new(dir.path().join("primary.db")).unwrap();
+        let mut other = VectorStore::new(dir.path().join("other.db")).unwrap();
+        for idx in 0..3 {
+            store.import_record(&record(idx)).unwrap();
+        }
+        // Different insertion order, so the other database's ids don't line up
+        for idx in [4, 2, 3] {
+            other.import_record(&record(idx)).unwrap();
+        }
+
+        let summary = merge_store(&mut store, &other).unwrap();
+
+        assert_eq!(
+            summary,
+            MergeSummary {
+                added: 2,
+                skipped: 1
+            }
+        );
+        assert_eq!(counts(&store), (5, 10, 10));
+        let merged = store
+            .get_document_by_hash("hash-4")
+            .unwrap()
+            .and_then(|doc| doc.id)
+            .unwrap();
+        let copied = store.document_record(merged).unwrap().unwrap();
+        assert_eq!(copied.chunks[1].content, "Document 4 chunk 1");
+        assert_eq!(
+            copied.chunks[1].embedding.as_ref().unwrap().vector,
+            [4.0, 1.0]
+        );
+
+        // Embeddings of a different size for the same model are rejected
+        let mut mismatched = VectorStore::new(dir.path().join("mismatched.db")).unwrap();
+        let mut wide = record(9);
+        for chunk in &mut wide.chunks {
+            chunk.embedding.as_mut().unwrap().vector.push(0.0);
+        }
+        mismatched.import_record(&wide).unwrap();
+        drop(mismatched);
+        let mismatched = VectorStore::open_read_only(dir.path().join("mismatched.db")).unwrap();
+        let err = merge_store(&mut store, &mismatched)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(&wide.source), "{}", err);
+        assert_eq!(counts(&store), (5, 10, 10));
+    }
+}
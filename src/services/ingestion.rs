@@ -2,25 +2,142 @@
 //!
 //! Handles loading files, chunking text, generating embeddings, and storing in the database.
 
-use crate::clients::OllamaClient;
-use crate::domain::{Chunk, ChunkStrategy, Document, Embedding};
-use crate::error::{Result, VectDbError};
+use crate::clients::ollama::RetryStats;
+use crate::clients::{EmbeddingProvider, OllamaClient};
+use crate::config::Config;
+use crate::domain::{Chunk, ChunkStrategy, Document, EmbeddedChunk, Embedding, OverlapUnit};
+use crate::error::{Result, ResultExt, VectDbError};
 use crate::repositories::VectorStore;
-use crate::services::chunking::chunk_text;
-use std::fs;
+use crate::services::chunking::{chunk_text, count_tokens, normalize_text};
+use crate::services::code_filter::{CodeFilter, CodeLanguage};
+use crate::services::loaders::{DocumentLoader, LoaderRegistry};
+use encoding_rs::Encoding;
+use std::collections::VecDeque;
 use std::path::Path;
 use tracing::{debug, info, warn};
 
 /// Service for ingesting documents into the vector database
-pub struct IngestionService {
+pub struct IngestionService<P = OllamaClient> {
     store: VectorStore,
-    ollama: OllamaClient,
+    provider: P,
+    options: IngestionOptions,
+    loaders: LoaderRegistry,
+    /// Embedding retries spent on the file currently being ingested
+    retries: RetryStats,
 }
 
-impl IngestionService {
+/// Options controlling how documents are ingested
+#[derive(Debug, Clone)]
+pub struct IngestionOptions {
+    /// Consult and populate the persistent embedding cache
+    pub use_embedding_cache: bool,
+
+    /// Convert CRLF/CR line endings to LF before hashing and chunking
+    pub normalize_newlines: bool,
+
+    /// Normalize text to Unicode NFC before hashing and chunking
+    pub normalize_unicode: bool,
+
+    /// Encoding used for files that are not valid UTF-8 (None = reject them)
+    pub fallback_encoding: Option<&'static Encoding>,
+
+    /// Which parts of recognized source code files to keep
+    pub code_filter: CodeFilter,
+
+    /// Split chunks the model rejects as too long and embed the pieces instead
+    pub split_oversized_chunks: bool,
+
+    /// Rough cap on memory held by chunks and their embeddings before they are stored, in bytes
+    pub max_memory_bytes: usize,
+
+    /// Flag documents whose mean embedding is at least this similar to an existing one
+    pub near_dup_threshold: Option<f32>,
+
+    /// Skip flagged near-duplicates instead of ingesting them with a warning
+    pub skip_near_duplicates: bool,
+
+    /// Number of chunks (with their embeddings) written per transaction
+    pub commit_batch_size: usize,
+}
+
+impl Default for IngestionOptions {
+    fn default() -> Self {
+        Self {
+            use_embedding_cache: true,
+            normalize_newlines: true,
+            normalize_unicode: false,
+            fallback_encoding: None,
+            code_filter: CodeFilter::All,
+            split_oversized_chunks: true,
+            max_memory_bytes: 256 * 1024 * 1024,
+            near_dup_threshold: None,
+            skip_near_duplicates: true,
+            commit_batch_size: 500,
+        }
+    }
+}
+
+impl IngestionOptions {
+    /// Build options from the application configuration
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let fallback_encoding = match &config.ingestion.fallback_encoding {
+            Some(label) => Some(parse_encoding(label)?),
+            None => None,
+        };
+
+        Ok(Self {
+            use_embedding_cache: config.ingestion.embedding_cache,
+            normalize_newlines: config.chunking.normalize_newlines,
+            normalize_unicode: config.chunking.normalize_unicode,
+            fallback_encoding,
+            code_filter: CodeFilter::All,
+            split_oversized_chunks: config.ingestion.split_oversized_chunks,
+            max_memory_bytes: config.ingestion.max_memory_mb * 1024 * 1024,
+            near_dup_threshold: config.ingestion.near_dup_threshold,
+            skip_near_duplicates: config.ingestion.skip_near_duplicates,
+            commit_batch_size: config.ingestion.commit_batch_size,
+        })
+    }
+}
+
+/// Look up a text encoding by its WHATWG label (e.g. "latin1", "windows-1252")
+pub fn parse_encoding(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| VectDbError::InvalidInput(format!("Unknown text encoding: {}", label)))
+}
+
+impl<P: EmbeddingProvider> IngestionService<P> {
     /// Create a new ingestion service
-    pub fn new(store: VectorStore, ollama: OllamaClient) -> Self {
-        Self { store, ollama }
+    pub fn new(store: VectorStore, provider: P) -> Self {
+        let options = IngestionOptions::default();
+        let loaders = LoaderRegistry::new(options.fallback_encoding, options.code_filter);
+
+        Self {
+            store,
+            provider,
+            options,
+            loaders,
+            retries: RetryStats::default(),
+        }
+    }
+
+    /// Set the ingestion options
+    pub fn with_options(mut self, options: IngestionOptions) -> Self {
+        self.loaders
+            .set_builtin(options.fallback_encoding, options.code_filter);
+        self.options = options;
+        self
+    }
+
+    /// The store documents are ingested into
+    pub fn store(&self) -> &VectorStore {
+        &self.store
+    }
+
+    /// Register a loader for an additional file format
+    pub fn with_loader(mut self, loader: impl DocumentLoader + 'static) -> Self {
+        self.loaders.register(Box::new(loader));
+        self
     }
 
     /// Ingest a single file
@@ -29,12 +146,73 @@ impl IngestionService {
         file_path: &Path,
         model: &str,
         strategy: ChunkStrategy,
+    ) -> Result<IngestionResult> {
+        self.ingest_file_inner(file_path, model, strategy)
+            .await
+            .with_context(|| format!("Failed to ingest {}", file_path.display()))
+    }
+
+    async fn ingest_file_inner(
+        &mut self,
+        file_path: &Path,
+        model: &str,
+        strategy: ChunkStrategy,
     ) -> Result<IngestionResult> {
         info!("Ingesting file: {:?}", file_path);
+        self.retries = RetryStats::default();
 
         // Load file content
-        let content = self.load_file(file_path)?;
+        let (content, loader_metadata) = self.load_file_with_metadata(file_path)?;
+        let content = self.prepare_content(&content);
+
+        // Create document
+        let source = file_path.to_string_lossy().to_string();
+        let mut document = Document::new(source, &content);
+        for (key, value) in loader_metadata {
+            document = document.with_metadata(key, value);
+        }
+        if CodeLanguage::from_path(file_path).is_some() {
+            document = document.with_metadata(
+                "code_filter".to_string(),
+                self.options.code_filter.to_string(),
+            );
+        }
 
+        self.ingest_document(file_path, document, &content, model, strategy)
+            .await
+    }
+
+    /// Ingest text supplied directly (e.g. pushed over HTTP) under the given source name
+    ///
+    /// The text goes through the same normalization, duplicate detection and
+    /// chunking as a loaded file.
+    pub async fn ingest_text(
+        &mut self,
+        source: &str,
+        content: &str,
+        model: &str,
+        strategy: ChunkStrategy,
+    ) -> Result<IngestionResult> {
+        info!("Ingesting text for source: {}", source);
+        self.retries = RetryStats::default();
+
+        let content = self.prepare_content(content);
+        let document = Document::new(source.to_string(), &content);
+
+        self.ingest_document(Path::new(source), document, &content, model, strategy)
+            .await
+            .with_context(|| format!("Failed to ingest {}", source))
+    }
+
+    /// Chunk, embed and store a document unless it is empty or a duplicate
+    async fn ingest_document(
+        &mut self,
+        file_path: &Path,
+        document: Document,
+        content: &str,
+        model: &str,
+        strategy: ChunkStrategy,
+    ) -> Result<IngestionResult> {
         if content.trim().is_empty() {
             warn!("File is empty, skipping: {:?}", file_path);
             return Ok(IngestionResult {
@@ -43,13 +221,12 @@ impl IngestionService {
                 chunks_created: 0,
                 embeddings_created: 0,
                 skipped: true,
+                near_duplicate: None,
+                error: None,
+                retries: RetryStats::default(),
             });
         }
 
-        // Create document
-        let source = file_path.to_string_lossy().to_string();
-        let document = Document::new(source, &content);
-
         // Check for duplicates
         if let Some(existing) = self.store.get_document_by_hash(&document.content_hash)? {
             info!(
@@ -62,53 +239,97 @@ impl IngestionService {
                 chunks_created: 0,
                 embeddings_created: 0,
                 skipped: true,
+                near_duplicate: None,
+                error: None,
+                retries: RetryStats::default(),
             });
         }
 
-        // Insert document
-        let document_id = self.store.insert_document(&document)?;
-        info!("Created document with ID: {}", document_id);
-
         // Chunk the text
-        let chunk_texts = chunk_text(&content, strategy);
+        let chunk_texts = chunk_text(content, strategy);
         info!("Created {} chunks", chunk_texts.len());
 
-        // Create and insert chunks
-        let mut chunk_ids = Vec::new();
-        for (idx, chunk_text) in chunk_texts.iter().enumerate() {
-            let chunk = Chunk::new(document_id, idx, chunk_text.clone());
-            let chunk_id = self.store.insert_chunk(&chunk)?;
-            chunk_ids.push(chunk_id);
-        }
-
-        debug!("Inserted {} chunks into database", chunk_ids.len());
-
-        // Generate embeddings
+        // Embed and store the chunks one memory batch at a time; the document
+        // stays flagged incomplete until every batch is stored
         info!("Generating embeddings using model: {}", model);
-        let embeddings = self.ollama.embed_batch(model, &chunk_texts).await?;
+        let document_id = self.store.begin_document(&document)?;
+        let mut mean = VectorMean::default();
+        let commit_batch_size = self.options.commit_batch_size;
+        let stored = self
+            .embed_chunks(model, chunk_texts, |store, first_index, batch| {
+                let batch: Vec<EmbeddedChunk> = batch
+                    .into_iter()
+                    .map(|(content, vector)| {
+                        mean.add(&vector);
+                        EmbeddedChunk {
+                            token_count: count_tokens(&content),
+                            content,
+                            vector,
+                        }
+                    })
+                    .collect();
+                store.append_chunks(
+                    document_id,
+                    first_index,
+                    &batch,
+                    model,
+                    commit_batch_size,
+                    |committed| debug!("Committed {} chunks", first_index + committed),
+                )
+            })
+            .await;
+        let stored = match stored {
+            Ok(stored) => stored,
+            Err(e) => {
+                // Leave no partial document behind when embedding fails
+                self.store.delete_document(document_id)?;
+                return Err(e);
+            }
+        };
 
-        if embeddings.len() != chunk_ids.len() {
-            return Err(VectDbError::EmbeddingFailed(format!(
-                "Expected {} embeddings but got {}",
-                chunk_ids.len(),
-                embeddings.len()
-            )));
+        // Compare against existing documents now that the embeddings are known
+        let near_duplicate = match (self.options.near_dup_threshold, mean.mean()) {
+            (Some(threshold), Some(mean)) => {
+                self.find_near_duplicate(&mean, &document.source, model, threshold)?
+            }
+            _ => None,
+        };
+        if let Some(duplicate) = &near_duplicate {
+            warn!(
+                "{:?} is a near-duplicate of document {} ({}, similarity {:.4})",
+                file_path, duplicate.document_id, duplicate.source, duplicate.similarity
+            );
+            if self.options.skip_near_duplicates {
+                self.store.delete_document(document_id)?;
+                return Ok(IngestionResult {
+                    file_path: file_path.to_path_buf(),
+                    document_id: duplicate.document_id,
+                    chunks_created: 0,
+                    embeddings_created: 0,
+                    skipped: true,
+                    near_duplicate,
+                    error: None,
+                    retries: self.retries,
+                });
+            }
         }
 
-        // Store embeddings
-        for (chunk_id, embedding_vec) in chunk_ids.iter().zip(embeddings.iter()) {
-            let embedding = Embedding::new(*chunk_id, model.to_string(), embedding_vec.clone());
-            self.store.upsert_embedding(&embedding)?;
-        }
+        self.store.finish_document(document_id)?;
+        info!("Created document with ID: {}", document_id);
+
+        debug!("Inserted {} chunks into database", stored);
 
         info!("Successfully ingested {:?}", file_path);
 
         Ok(IngestionResult {
             file_path: file_path.to_path_buf(),
             document_id,
-            chunks_created: chunk_ids.len(),
-            embeddings_created: embeddings.len(),
+            chunks_created: stored,
+            embeddings_created: stored,
             skipped: false,
+            near_duplicate,
+            error: None,
+            retries: self.retries,
         })
     }
 
@@ -125,13 +346,16 @@ impl IngestionService {
             match self.ingest_file(file_path.as_ref(), model, strategy).await {
                 Ok(result) => results.push(result),
                 Err(e) => {
-                    warn!("Failed to ingest {:?}: {}", file_path.as_ref(), e);
+                    warn!("{}", e);
                     results.push(IngestionResult {
                         file_path: file_path.as_ref().to_path_buf(),
                         document_id: 0,
                         chunks_created: 0,
                         embeddings_created: 0,
                         skipped: true,
+                        near_duplicate: None,
+                        error: Some(e.to_string()),
+                        retries: RetryStats::default(),
                     });
                 }
             }
@@ -140,8 +364,380 @@ impl IngestionService {
         Ok(results)
     }
 
-    /// Load file content (supports txt and md for now)
+    /// Re-embed stored chunks with `model`, leaving documents and chunks untouched
+    ///
+    /// Chunks already embedded with `model` are skipped unless `force` is set;
+    /// a forced run also bypasses the embedding cache, so a model whose weights
+    /// changed under the same name gets fresh vectors. `on_chunk` is called
+    /// with (done, total) after each chunk is stored.
+    pub async fn reindex(
+        &mut self,
+        model: &str,
+        force: bool,
+        mut on_chunk: impl FnMut(usize, usize),
+    ) -> Result<ReindexResult> {
+        self.retries = RetryStats::default();
+
+        let chunk_count = self.store.count_chunks()? as usize;
+        let already_embedded = if force {
+            0
+        } else {
+            self.store.count_chunks_for_model(model)? as usize
+        };
+        let total = chunk_count - already_embedded;
+        info!(
+            "Reindexing {} of {} chunks with model {}",
+            total, chunk_count, model
+        );
+
+        // A forced run replaces every embedding, possibly at a new size
+        if force {
+            self.store.reset_model_dimension(model)?;
+        }
+
+        let skip_model = (!force).then_some(model);
+        let mut last_chunk_id = i64::MIN;
+        let mut reindexed = 0;
+
+        loop {
+            let chunks = self
+                .store
+                .chunks_after(last_chunk_id, REINDEX_BATCH_SIZE, skip_model)?;
+            let Some(last) = chunks.last() else {
+                break;
+            };
+            last_chunk_id = last.id.unwrap_or(last_chunk_id);
+
+            self.reembed_chunks(&chunks, model, force).await?;
+
+            for _ in &chunks {
+                reindexed += 1;
+                on_chunk(reindexed, total);
+            }
+        }
+
+        info!("Reindexed {} chunks with model {}", reindexed, model);
+
+        Ok(ReindexResult {
+            reindexed,
+            skipped: already_embedded,
+            retries: self.retries,
+        })
+    }
+
+    /// Re-embed the chunks of a single document with `model`
+    ///
+    /// A cheaper alternative to [`reindex`](Self::reindex) after editing one
+    /// document's chunks. Returns the number of chunks re-embedded.
+    pub async fn reembed_document(&mut self, document_id: i64, model: &str) -> Result<usize> {
+        if self.store.get_document(document_id)?.is_none() {
+            return Err(VectDbError::InvalidInput(format!(
+                "Document {} does not exist",
+                document_id
+            )));
+        }
+        self.retries = RetryStats::default();
+
+        let chunks = self.store.get_chunks_for_document(document_id)?;
+        info!(
+            "Re-embedding {} chunks of document {} with model {}",
+            chunks.len(),
+            document_id,
+            model
+        );
+
+        for batch in chunks.chunks(REINDEX_BATCH_SIZE) {
+            self.reembed_chunks(batch, model, false).await?;
+        }
+
+        Ok(chunks.len())
+    }
+
+    /// Replace a chunk's text and embed it again with `model` right away
+    ///
+    /// Unlike [`VectorStore::update_chunk_content`] alone, the chunk stays
+    /// searchable without waiting for the next reindex.
+    pub async fn update_chunk_content(
+        &mut self,
+        chunk_id: i64,
+        new_content: &str,
+        model: &str,
+    ) -> Result<()> {
+        self.retries = RetryStats::default();
+        // Embed first, so a failure leaves the old text and embedding in place
+        let vector = self.embed_texts(model, &[new_content.to_string()]).await?;
+
+        self.store.in_transaction(|store| {
+            store.update_chunk_content(chunk_id, new_content, count_tokens(new_content))?;
+            for vector in vector {
+                store.upsert_embedding(&Embedding::new(chunk_id, model.to_string(), vector))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Embed stored chunks with `model` and replace their embeddings in one transaction
+    async fn reembed_chunks(
+        &mut self,
+        chunks: &[Chunk],
+        model: &str,
+        bypass_cache: bool,
+    ) -> Result<()> {
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let vectors = if bypass_cache {
+            self.embed_uncached(model, &texts).await?
+        } else {
+            self.embed_texts(model, &texts).await?
+        };
+
+        self.store.in_transaction(|store| {
+            for (chunk, vector) in chunks.iter().zip(vectors) {
+                if let Some(chunk_id) = chunk.id {
+                    store.upsert_embedding(&Embedding::new(chunk_id, model.to_string(), vector))?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Load and chunk a file without touching the database or Ollama
+    pub fn plan_file(&self, file_path: &Path, strategy: ChunkStrategy) -> Result<FilePlan> {
+        let content = self.load_file(file_path)?;
+        Ok(self.plan_text(file_path, &content, strategy))
+    }
+
+    /// Chunk text supplied directly, as `ingest_text` would, without storing it
+    pub fn plan_text(&self, source: &Path, content: &str, strategy: ChunkStrategy) -> FilePlan {
+        let content = self.prepare_content(content);
+        let chunks = if content.trim().is_empty() {
+            Vec::new()
+        } else {
+            chunk_text(&content, strategy)
+        };
+
+        FilePlan {
+            file_path: source.to_path_buf(),
+            chunks,
+        }
+    }
+
+    /// Estimate how long ingesting the given files would take
+    ///
+    /// Files are chunked in memory, then up to `samples` chunks are embedded
+    /// to measure the per-chunk latency, which is extrapolated to all chunks.
+    pub async fn estimate_files(
+        &self,
+        file_paths: &[impl AsRef<Path>],
+        model: &str,
+        strategy: ChunkStrategy,
+        samples: usize,
+    ) -> Result<IngestionEstimate> {
+        let mut plans = Vec::new();
+        for file_path in file_paths {
+            match self.plan_file(file_path.as_ref(), strategy) {
+                Ok(plan) => plans.push(plan),
+                Err(e) => warn!("Skipping {:?} in estimate: {}", file_path.as_ref(), e),
+            }
+        }
+
+        let sample_texts: Vec<&String> = plans
+            .iter()
+            .flat_map(|plan| plan.chunks.iter())
+            .take(samples.max(1))
+            .collect();
+
+        let per_chunk_latency = if sample_texts.is_empty() {
+            std::time::Duration::ZERO
+        } else {
+            let started = std::time::Instant::now();
+            for text in &sample_texts {
+                self.provider.embed(model, text).await?;
+            }
+            started.elapsed() / sample_texts.len() as u32
+        };
+
+        let chunk_count: usize = plans.iter().map(FilePlan::chunk_count).sum();
+        let token_count: usize = plans.iter().map(FilePlan::token_count).sum();
+
+        Ok(IngestionEstimate {
+            file_count: plans.len(),
+            chunk_count,
+            token_count,
+            per_chunk_latency,
+            estimated_duration: per_chunk_latency * chunk_count as u32,
+        })
+    }
+
+    /// Apply the configured text normalization to loaded content
+    fn prepare_content(&self, content: &str) -> String {
+        normalize_text(
+            content,
+            self.options.normalize_newlines,
+            self.options.normalize_unicode,
+        )
+    }
+
+    /// Find an existing document whose mean embedding is close to the new chunks' mean
+    ///
+    /// Documents from the same source are earlier versions of this one, not duplicates.
+    fn find_near_duplicate(
+        &self,
+        mean: &[f32],
+        source: &str,
+        model: &str,
+        threshold: f32,
+    ) -> Result<Option<NearDuplicate>> {
+        Ok(self
+            .store
+            .nearest_duplicate(mean, model, threshold, source)?
+            .map(|(document, similarity)| NearDuplicate {
+                document_id: document.id.unwrap_or(0),
+                source: document.source,
+                similarity,
+            }))
+    }
+
+    /// Embed chunk texts in batches that fit the memory budget
+    ///
+    /// Each embedded batch is handed to `on_batch` together with the store and
+    /// the index of its first chunk, then dropped, so only one batch of vectors
+    /// is held at a time. Returns the number of chunks embedded, which exceeds
+    /// the number of texts when oversized chunks were split.
+    async fn embed_chunks(
+        &mut self,
+        model: &str,
+        texts: Vec<String>,
+        mut on_batch: impl FnMut(&mut VectorStore, usize, Vec<(String, Vec<f32>)>) -> Result<()>,
+    ) -> Result<usize> {
+        let batches = plan_memory_batches(&texts, self.options.max_memory_bytes);
+        if batches.len() > 1 {
+            debug!(
+                "Embedding {} chunks in {} batches to stay within the memory budget",
+                texts.len(),
+                batches.len()
+            );
+        }
+
+        let mut texts = texts.into_iter();
+        let mut embedded = 0;
+        for batch in batches {
+            let batch_texts: Vec<String> = texts.by_ref().take(batch.len()).collect();
+            let batch = self.embed_chunk_batch(model, batch_texts).await?;
+            let count = batch.len();
+            on_batch(&mut self.store, embedded, batch)?;
+            embedded += count;
+        }
+
+        Ok(embedded)
+    }
+
+    /// Embed a batch of chunk texts, splitting any chunk the model rejects as too long
+    ///
+    /// Returns the final chunk texts paired with their embeddings; a rejected
+    /// chunk is replaced in place by its halves, recursively.
+    async fn embed_chunk_batch(
+        &mut self,
+        model: &str,
+        texts: Vec<String>,
+    ) -> Result<Vec<(String, Vec<f32>)>> {
+        match self.embed_texts(model, &texts).await {
+            Ok(embeddings) => return Ok(texts.into_iter().zip(embeddings).collect()),
+            Err(VectDbError::InputTooLong(reason)) if self.options.split_oversized_chunks => {
+                warn!("{}; splitting oversized chunks", reason);
+            }
+            Err(e) => return Err(e),
+        }
+
+        // Chunks embedded before the failure are served from the cache
+        let mut pending: VecDeque<String> = texts.into();
+        let mut embedded = Vec::with_capacity(pending.len());
+
+        while let Some(text) = pending.pop_front() {
+            match self.embed_texts(model, std::slice::from_ref(&text)).await {
+                Ok(embeddings) => {
+                    let vector = embeddings.into_iter().next().ok_or_else(|| {
+                        VectDbError::EmbeddingFailed("No embedding returned".to_string())
+                    })?;
+                    embedded.push((text, vector));
+                }
+                Err(VectDbError::InputTooLong(reason)) => {
+                    let pieces = split_chunk(&text);
+                    if pieces.len() < 2 {
+                        return Err(VectDbError::InputTooLong(reason));
+                    }
+                    debug!(
+                        "Split chunk of {} chars into {} pieces",
+                        text.chars().count(),
+                        pieces.len()
+                    );
+                    for piece in pieces.into_iter().rev() {
+                        pending.push_front(piece);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(embedded)
+    }
+
+    /// Generate embeddings for texts, using the embedding cache when enabled
+    async fn embed_texts(&mut self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if !self.options.use_embedding_cache {
+            return self.embed_uncached(model, texts).await;
+        }
+
+        // Truncated vectors are cached apart from full-size ones
+        let cache_model = match self.provider.truncate_dimension() {
+            Some(dimension) => format!("{}#{}", model, dimension),
+            None => model.to_string(),
+        };
+
+        let mut embeddings: Vec<Option<Vec<f32>>> = Vec::with_capacity(texts.len());
+        let mut missing = Vec::new();
+
+        for text in texts {
+            let cached = self.store.get_cached_embedding(&cache_model, text)?;
+            if cached.is_none() {
+                missing.push(text.clone());
+            }
+            embeddings.push(cached);
+        }
+
+        debug!(
+            "Embedding cache: {} hit(s), {} miss(es)",
+            texts.len() - missing.len(),
+            missing.len()
+        );
+
+        let mut generated = self.embed_uncached(model, &missing).await?.into_iter();
+        for (text, slot) in texts.iter().zip(embeddings.iter_mut()) {
+            if slot.is_none()
+                && let Some(vector) = generated.next()
+            {
+                self.store
+                    .put_cached_embedding(&cache_model, text, &vector)?;
+                *slot = Some(vector);
+            }
+        }
+
+        Ok(embeddings.into_iter().flatten().collect())
+    }
+
+    /// Embed texts with Ollama, recording the retries they needed
+    async fn embed_uncached(&mut self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let (embeddings, retries) = self.provider.embed_batch_with_stats(model, texts).await?;
+        self.retries.merge(&retries);
+        Ok(embeddings)
+    }
+
+    /// Load file content with the first registered loader that accepts it
     fn load_file(&self, file_path: &Path) -> Result<String> {
+        Ok(self.load_file_with_metadata(file_path)?.0)
+    }
+
+    /// Load file content along with any metadata reported by its loader
+    fn load_file_with_metadata(&self, file_path: &Path) -> Result<(String, Vec<(String, String)>)> {
         debug!("Loading file: {:?}", file_path);
 
         if !file_path.exists() {
@@ -158,29 +754,94 @@ impl IngestionService {
             )));
         }
 
-        // Check file extension
-        let extension = file_path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_lowercase();
+        self.loaders.load_with_metadata(file_path)
+    }
+}
 
-        match extension.as_str() {
-            "txt" | "md" | "markdown" => {
-                let content = fs::read_to_string(file_path)?;
-                Ok(content)
-            }
-            "" => {
-                // Try to read as text anyway
-                let content = fs::read_to_string(file_path)?;
-                Ok(content)
+/// Running element-wise mean of equally sized vectors
+#[derive(Default)]
+struct VectorMean {
+    sum: Option<Vec<f32>>,
+    count: usize,
+}
+
+impl VectorMean {
+    /// Add a vector; vectors of a different size than the first are ignored
+    fn add(&mut self, vector: &[f32]) {
+        match &mut self.sum {
+            Some(sum) if sum.len() == vector.len() => {
+                sum.iter_mut().zip(vector).for_each(|(s, v)| *s += v)
             }
-            _ => Err(VectDbError::InvalidInput(format!(
-                "Unsupported file type: .{}. Currently supported: txt, md",
-                extension
-            ))),
+            Some(_) => return,
+            None => self.sum = Some(vector.to_vec()),
+        }
+        self.count += 1;
+    }
+
+    /// The mean of the vectors added so far (None if there are none)
+    fn mean(&self) -> Option<Vec<f32>> {
+        let count = self.count as f32;
+        self.sum
+            .as_ref()
+            .map(|sum| sum.iter().map(|s| s / count).collect())
+    }
+}
+
+/// Chunks re-embedded and stored per transaction when reindexing
+const REINDEX_BATCH_SIZE: usize = 64;
+
+/// Embedding dimension assumed when estimating memory (before any vector is seen)
+const ESTIMATED_EMBEDDING_DIMENSION: usize = 1024;
+
+/// Rough memory held by one in-flight chunk: its text plus an f32 embedding
+fn estimated_chunk_bytes(text: &str) -> usize {
+    text.len() + ESTIMATED_EMBEDDING_DIMENSION * std::mem::size_of::<f32>()
+}
+
+/// Group consecutive chunks into batches whose estimated memory fits the budget
+///
+/// Every batch holds at least one chunk, so a single chunk larger than the
+/// budget is still processed on its own.
+pub fn plan_memory_batches(texts: &[String], budget_bytes: usize) -> Vec<std::ops::Range<usize>> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+
+    for (idx, text) in texts.iter().enumerate() {
+        let bytes = estimated_chunk_bytes(text);
+        if idx > start && used + bytes > budget_bytes {
+            batches.push(start..idx);
+            start = idx;
+            used = 0;
         }
+        used += bytes;
+    }
+
+    if start < texts.len() {
+        batches.push(start..texts.len());
+    }
+
+    batches
+}
+
+/// Smallest chunk (in characters) that is still split further when rejected
+const MIN_SPLIT_CHUNK_SIZE: usize = 16;
+
+/// Split a chunk into two halves without overlap
+fn split_chunk(text: &str) -> Vec<String> {
+    let length = text.chars().count();
+    if length < MIN_SPLIT_CHUNK_SIZE * 2 {
+        return vec![text.to_string()];
     }
+
+    chunk_text(
+        text,
+        ChunkStrategy::FixedSize {
+            size: length.div_ceil(2),
+            overlap: 0,
+            overlap_unit: OverlapUnit::Chars,
+        },
+    )
 }
 
 /// Result of ingesting a file
@@ -191,12 +852,65 @@ pub struct IngestionResult {
     pub chunks_created: usize,
     pub embeddings_created: usize,
     pub skipped: bool,
+    /// Existing document this one was found to nearly duplicate, if any
+    pub near_duplicate: Option<NearDuplicate>,
+    /// Why the file failed, including its path, when ingestion errored
+    pub error: Option<String>,
+    /// Embedding retries the file needed
+    pub retries: RetryStats,
+}
+
+/// Result of re-embedding stored chunks with another model
+#[derive(Debug, Clone)]
+pub struct ReindexResult {
+    /// Chunks whose embedding was regenerated
+    pub reindexed: usize,
+    /// Chunks left alone because they were already embedded with the model
+    pub skipped: usize,
+    /// Embedding retries the run needed
+    pub retries: RetryStats,
+}
+
+/// An existing document whose mean embedding is close to a new one
+#[derive(Debug, Clone)]
+pub struct NearDuplicate {
+    pub document_id: i64,
+    pub source: String,
+    pub similarity: f32,
+}
+
+/// In-memory chunking result for a file (no database writes)
+#[derive(Debug, Clone)]
+pub struct FilePlan {
+    pub file_path: std::path::PathBuf,
+    pub chunks: Vec<String>,
+}
+
+impl FilePlan {
+    /// Number of chunks the file would produce
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Token count across all chunks, in cl100k_base BPE tokens (see `count_tokens`)
+    pub fn token_count(&self) -> usize {
+        self.chunks.iter().map(|c| count_tokens(c)).sum()
+    }
+}
+
+/// Estimated cost of ingesting a set of files
+#[derive(Debug, Clone)]
+pub struct IngestionEstimate {
+    pub file_count: usize,
+    pub chunk_count: usize,
+    pub token_count: usize,
+    pub per_chunk_latency: std::time::Duration,
+    pub estimated_duration: std::time::Duration,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::Config;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -227,4 +941,762 @@ mod tests {
         let result = service.load_file(Path::new("/nonexistent/file.txt"));
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_failed_file_error_includes_path() {
+        let config = Config::default();
+        let store = VectorStore::in_memory().unwrap();
+        let ollama =
+            OllamaClient::new(config.ollama.base_url, config.ollama.timeout_seconds).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.txt");
+        let strategy = ChunkStrategy::default();
+
+        let err = service
+            .ingest_file(&missing, "model", strategy)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(&missing.display().to_string()));
+        assert!(matches!(err, VectDbError::WithContext { .. }));
+        assert!(!matches!(err.root_cause(), VectDbError::WithContext { .. }));
+
+        let results = service
+            .ingest_files(&[&missing], "model", strategy)
+            .await
+            .unwrap();
+        assert!(results[0].skipped);
+        let error = results[0].error.as_deref().unwrap();
+        assert!(error.contains(&missing.display().to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_retries_are_reported() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("busy"))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, 0.2, 0.3]
+            })))
+            .mount(&server)
+            .await;
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "A short document").unwrap();
+
+        let result = service
+            .ingest_file(temp_file.path(), "model", ChunkStrategy::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.chunks_created, 1);
+        assert_eq!(result.retries.retries, 2);
+        assert_eq!(result.retries.retried_inputs, 1);
+        assert!(result.retries.backoff >= std::time::Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_reindex_skips_chunks_already_on_model() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.5, 0.5]
+            })))
+            .expect(2 + 3)
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("doc.txt".to_string(), "doc"))
+            .unwrap();
+        for (idx, model) in ["old-model", "new-model", "old-model"].iter().enumerate() {
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, idx, format!("chunk {}", idx)))
+                .unwrap();
+            store
+                .upsert_embedding(&Embedding::new(chunk_id, model.to_string(), vec![1.0, 0.0]))
+                .unwrap();
+        }
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+
+        let mut progress = Vec::new();
+        let result = service
+            .reindex("new-model", false, |done, total| {
+                progress.push((done, total))
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.reindexed, 2);
+        assert_eq!(result.skipped, 1);
+        assert_eq!(progress, vec![(1, 2), (2, 2)]);
+        assert_eq!(service.store.count_chunks().unwrap(), 3);
+        assert_eq!(
+            service.store.count_chunks_for_model("new-model").unwrap(),
+            3
+        );
+        assert_eq!(
+            service.store.count_chunks_for_model("old-model").unwrap(),
+            0
+        );
+
+        // Nothing left to do without --force; everything is redone with it
+        let result = service
+            .reindex("new-model", false, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(result.reindexed, 0);
+        let result = service.reindex("new-model", true, |_, _| {}).await.unwrap();
+        assert_eq!(result.reindexed, 3);
+        assert_eq!(result.skipped, 0);
+    }
+
+    #[tokio::test]
+    async fn test_reembed_document_only_touches_that_document() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.5, 0.5]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        let mut chunk_ids = Vec::new();
+        for source in ["a.txt", "b.txt", "c.txt"] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            for idx in 0..2 {
+                let chunk_id = store
+                    .insert_chunk(&Chunk::new(doc_id, idx, format!("{} {}", source, idx)))
+                    .unwrap();
+                store
+                    .upsert_embedding(&Embedding::new(
+                        chunk_id,
+                        "model".to_string(),
+                        vec![1.0, 0.0],
+                    ))
+                    .unwrap();
+                chunk_ids.push((doc_id, chunk_id));
+            }
+        }
+        let target = chunk_ids[2].0;
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+        assert_eq!(service.reembed_document(target, "model").await.unwrap(), 2);
+
+        for (doc_id, chunk_id) in chunk_ids {
+            let vector = service
+                .store
+                .get_embedding(chunk_id)
+                .unwrap()
+                .unwrap()
+                .vector;
+            let expected = if doc_id == target {
+                vec![0.5, 0.5]
+            } else {
+                vec![1.0, 0.0]
+            };
+            assert_eq!(vector, expected);
+        }
+
+        let err = service.reembed_document(999, "model").await.unwrap_err();
+        assert!(err.to_string().contains("999"));
+    }
+
+    #[tokio::test]
+    async fn test_update_chunk_content_reembeds_immediately() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.5, 0.5]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("scan.pdf".to_string(), "scan"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "0CR err0rs".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&Embedding::new(
+                chunk_id,
+                "model".to_string(),
+                vec![1.0, 0.0],
+            ))
+            .unwrap();
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+        service
+            .update_chunk_content(chunk_id, "OCR errors", "model")
+            .await
+            .unwrap();
+
+        let chunk = service.store.get_chunk(chunk_id).unwrap().unwrap();
+        assert_eq!(chunk.content, "OCR errors");
+        let embedding = service.store.get_embedding(chunk_id).unwrap().unwrap();
+        assert_eq!(embedding.vector, vec![0.5, 0.5]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_texts_uses_cache() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, 0.2, 0.3]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+
+        let texts = vec!["repeated text".to_string()];
+        let first = service.embed_texts("model", &texts).await.unwrap();
+        let second = service.embed_texts("model", &texts).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(service.store.count_cached_embeddings().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_skips_ollama_for_cached_chunk_text() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        // Two documents of two chunks each, sharing their first chunk
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        std::fs::write(&first, format!("{}{}", "x".repeat(10), "a".repeat(10))).unwrap();
+        std::fs::write(&second, format!("{}{}", "x".repeat(10), "b".repeat(10))).unwrap();
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+        let strategy = ChunkStrategy::FixedSize {
+            size: 10,
+            overlap: 0,
+            overlap_unit: OverlapUnit::Chars,
+        };
+        for file in [&first, &second] {
+            let result = service.ingest_file(file, "model", strategy).await.unwrap();
+            assert_eq!(result.embeddings_created, 2);
+        }
+        server.verify().await;
+
+        // With the cache off, every chunk is embedded again
+        server.reset().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let third = dir.path().join("third.txt");
+        std::fs::write(&third, format!("{}{}", "x".repeat(10), "c".repeat(10))).unwrap();
+        let options = IngestionOptions {
+            use_embedding_cache: false,
+            ..IngestionOptions::default()
+        };
+        let mut service = service.with_options(options);
+        service
+            .ingest_file(&third, "model", strategy)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oversized_chunks_are_split() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+        /// Matches embedding requests whose prompt is longer than the limit
+        struct PromptLongerThan(usize);
+
+        impl wiremock::Match for PromptLongerThan {
+            fn matches(&self, request: &Request) -> bool {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                body["prompt"].as_str().unwrap_or("").chars().count() > self.0
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(PromptLongerThan(40))
+            .respond_with(
+                ResponseTemplate::new(500)
+                    .set_body_string("the input length exceeds the context length"),
+            )
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, 0.2, 0.3]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut temp_file = NamedTempFile::with_suffix(".txt").unwrap();
+        write!(temp_file, "{}", "word ".repeat(20)).unwrap();
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+
+        let strategy = ChunkStrategy::FixedSize {
+            size: 100,
+            overlap: 0,
+            overlap_unit: OverlapUnit::Chars,
+        };
+        let result = service
+            .ingest_file(temp_file.path(), "model", strategy)
+            .await
+            .unwrap();
+
+        // The single 100-char chunk is halved twice to fit the 40-char limit
+        assert_eq!(result.chunks_created, 4);
+        assert_eq!(result.embeddings_created, 4);
+        let chunks = service
+            .store
+            .get_chunks_for_document(result.document_id)
+            .unwrap();
+        assert!(chunks.iter().all(|c| c.content.chars().count() <= 40));
+        assert_eq!(service.store.count_embeddings().unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_custom_loader() {
+        use std::path::Path;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        /// Loads `.rev` files by reversing their content
+        struct ReverseLoader;
+
+        impl DocumentLoader for ReverseLoader {
+            fn can_load(&self, path: &Path) -> bool {
+                path.extension().is_some_and(|ext| ext == "rev")
+            }
+
+            fn load(&self, path: &Path) -> Result<String> {
+                Ok(std::fs::read_to_string(path)?.chars().rev().collect())
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, 0.2, 0.3]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut temp_file = NamedTempFile::with_suffix(".rev").unwrap();
+        write!(temp_file, "olleh").unwrap();
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+        assert!(service.load_file(temp_file.path()).is_err());
+
+        service = service.with_loader(ReverseLoader);
+        let result = service
+            .ingest_file(temp_file.path(), "model", ChunkStrategy::default())
+            .await
+            .unwrap();
+
+        let chunks = service
+            .store
+            .get_chunks_for_document(result.document_id)
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].content, "hello");
+    }
+
+    /// Build a minimal PDF with one Helvetica text line per page (empty for no text)
+    fn minimal_pdf(pages: &[&str]) -> Vec<u8> {
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            String::new(), // page tree, filled in once the page ids are known
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        ];
+        let mut kids = Vec::new();
+        for text in pages {
+            let stream = if text.is_empty() {
+                String::new()
+            } else {
+                format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text)
+            };
+            let page_id = objects.len() + 1;
+            kids.push(format!("{} 0 R", page_id));
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                page_id + 1
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                stream.len(),
+                stream
+            ));
+        }
+        objects[1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        );
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (idx, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", idx + 1, object).bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .bytes(),
+        );
+        pdf
+    }
+
+    #[tokio::test]
+    async fn test_ingest_pdf() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, 0.2, 0.3]
+            })))
+            .mount(&server)
+            .await;
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama);
+
+        let mut text_pdf = NamedTempFile::with_suffix(".pdf").unwrap();
+        text_pdf
+            .write_all(&minimal_pdf(&[
+                "Rust ownership rules",
+                "Borrowing and lifetimes",
+            ]))
+            .unwrap();
+        let result = service
+            .ingest_file(text_pdf.path(), "model", ChunkStrategy::default())
+            .await
+            .unwrap();
+        assert!(!result.skipped);
+
+        let document = service
+            .store
+            .get_document(result.document_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            document.metadata.get("page_count").map(String::as_str),
+            Some("2")
+        );
+        let chunks = service
+            .store
+            .get_chunks_for_document(result.document_id)
+            .unwrap();
+        let content: String = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert!(content.contains("Rust ownership rules"));
+        assert!(content.contains("Borrowing and lifetimes"));
+
+        // A PDF without a text layer is skipped rather than failing the run
+        let mut image_pdf = NamedTempFile::with_suffix(".pdf").unwrap();
+        image_pdf.write_all(&minimal_pdf(&["", ""])).unwrap();
+        let result = service
+            .ingest_file(image_pdf.path(), "model", ChunkStrategy::default())
+            .await
+            .unwrap();
+        assert!(result.skipped);
+    }
+
+    #[test]
+    fn test_plan_memory_batches_bounds_in_flight_chunks() {
+        let texts: Vec<String> = (0..10).map(|_| "x".repeat(100)).collect();
+        let per_chunk = estimated_chunk_bytes(&texts[0]);
+
+        let batches = plan_memory_batches(&texts, per_chunk * 3);
+        assert!(batches.iter().all(|b| !b.is_empty() && b.len() <= 3));
+        assert_eq!(batches.iter().map(|b| b.len()).sum::<usize>(), 10);
+
+        // A budget smaller than one chunk still makes progress one chunk at a time
+        let batches = plan_memory_batches(&texts, 1);
+        assert_eq!(batches.len(), 10);
+
+        assert_eq!(plan_memory_batches(&texts, usize::MAX), vec![0..10]);
+        assert!(plan_memory_batches(&[], 1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_embed_chunks_hands_over_bounded_batches() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, 0.2, 0.3]
+            })))
+            .mount(&server)
+            .await;
+
+        let texts: Vec<String> = (0..10).map(|idx| format!("chunk {:0>95}", idx)).collect();
+        let budget = estimated_chunk_bytes(&texts[0]) * 3;
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama).with_options(IngestionOptions {
+            max_memory_bytes: budget,
+            ..Default::default()
+        });
+
+        let mut batches = Vec::new();
+        let embedded = service
+            .embed_chunks("model", texts.clone(), |_, first_index, batch| {
+                batches.push((first_index, batch.len()));
+                assert_eq!(batch[0].0, texts[first_index]);
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(embedded, 10);
+        assert_eq!(batches, vec![(0, 3), (3, 3), (6, 3), (9, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_near_duplicate_flagged() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(body_string_contains("unrelated"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.0, 0.0, 1.0]
+            })))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.9, 0.1, 0.0]
+            })))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.txt");
+        let edited = dir.path().join("edited.txt");
+        let other = dir.path().join("other.txt");
+        std::fs::write(&original, "The quick brown fox jumps over the lazy dog.").unwrap();
+        std::fs::write(&edited, "The quick brown fox leaps over the lazy dog.").unwrap();
+        std::fs::write(&other, "Something unrelated entirely.").unwrap();
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let mut service = IngestionService::new(store, ollama).with_options(IngestionOptions {
+            near_dup_threshold: Some(0.95),
+            ..Default::default()
+        });
+        let strategy = ChunkStrategy::default();
+
+        let first = service
+            .ingest_file(&original, "model", strategy)
+            .await
+            .unwrap();
+        assert!(first.near_duplicate.is_none());
+
+        let second = service
+            .ingest_file(&edited, "model", strategy)
+            .await
+            .unwrap();
+        let duplicate = second.near_duplicate.expect("flagged as near-duplicate");
+        assert!(second.skipped);
+        assert_eq!(duplicate.document_id, first.document_id);
+
+        let third = service
+            .ingest_file(&other, "model", strategy)
+            .await
+            .unwrap();
+        assert!(third.near_duplicate.is_none());
+        assert_eq!(service.store.count_documents().unwrap(), 2);
+
+        // Re-ingesting an edited file is not flagged against its own earlier version
+        std::fs::write(&original, "The quick brown fox jumped over the lazy dog.").unwrap();
+        let revised = service
+            .ingest_file(&original, "model", strategy)
+            .await
+            .unwrap();
+        assert!(revised.near_duplicate.is_none());
+        assert!(!revised.skipped);
+        assert_eq!(service.store.count_documents().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_crlf_and_lf_files_hash_identically() {
+        let config = Config::default();
+        let store = VectorStore::in_memory().unwrap();
+        let ollama =
+            OllamaClient::new(config.ollama.base_url, config.ollama.timeout_seconds).unwrap();
+        let service = IngestionService::new(store, ollama);
+
+        let mut crlf_file = NamedTempFile::new().unwrap();
+        write!(crlf_file, "Line one\r\nLine two\r\n").unwrap();
+        let mut lf_file = NamedTempFile::new().unwrap();
+        write!(lf_file, "Line one\nLine two\n").unwrap();
+
+        let crlf = service.prepare_content(&service.load_file(crlf_file.path()).unwrap());
+        let lf = service.prepare_content(&service.load_file(lf_file.path()).unwrap());
+
+        assert_eq!(
+            Document::new("a".to_string(), &crlf).content_hash,
+            Document::new("b".to_string(), &lf).content_hash
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimate_files() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "embedding": [0.1, 0.2] }))
+                    .set_delay(std::time::Duration::from_millis(5)),
+            )
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        std::fs::write(&first, "a".repeat(25)).unwrap();
+        std::fs::write(&second, "b".repeat(5)).unwrap();
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = IngestionService::new(store, ollama);
+
+        let strategy = ChunkStrategy::FixedSize {
+            size: 10,
+            overlap: 0,
+            overlap_unit: OverlapUnit::Chars,
+        };
+        let estimate = service
+            .estimate_files(&[first, second], "model", strategy, 2)
+            .await
+            .unwrap();
+
+        assert_eq!(estimate.file_count, 2);
+        assert_eq!(estimate.chunk_count, 4);
+        assert!(estimate.estimated_duration > std::time::Duration::ZERO);
+        assert_eq!(service.store.count_documents().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_load_file_latin1_fallback() {
+        let config = Config::default();
+        let store = VectorStore::in_memory().unwrap();
+        let ollama =
+            OllamaClient::new(config.ollama.base_url, config.ollama.timeout_seconds).unwrap();
+        let service = IngestionService::new(store, ollama);
+
+        // "café naïve" encoded as Latin-1
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file
+            .write_all(&[b'c', b'a', b'f', 0xE9, b' ', b'n', b'a', 0xEF, b'v', b'e'])
+            .unwrap();
+
+        assert!(service.load_file(temp_file.path()).is_err());
+
+        let service = service.with_options(IngestionOptions {
+            fallback_encoding: Some(parse_encoding("latin1").unwrap()),
+            ..Default::default()
+        });
+        let content = service.load_file(temp_file.path()).unwrap();
+        assert_eq!(content, "café naïve");
+    }
 }
@@ -0,0 +1,262 @@
+//! Document loaders for the ingestion pipeline
+//!
+//! A [`DocumentLoader`] turns a file into text. The [`LoaderRegistry`] holds the
+//! built-in loaders (plain text, markdown, source code, HTML and PDF) plus any custom
+//! loaders registered by callers, so new formats can be supported without
+//! touching the ingestion service.
+
+use crate::error::{Result, VectDbError};
+use crate::services::code_filter::{CODE_EXTENSIONS, CodeFilter, CodeLanguage, filter_code};
+use crate::services::html::html_to_text;
+use encoding_rs::Encoding;
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Loads the text content of a file
+pub trait DocumentLoader: Send + Sync {
+    /// Whether this loader handles the given file
+    fn can_load(&self, path: &Path) -> bool;
+
+    /// Read the file and return its text content
+    fn load(&self, path: &Path) -> Result<String>;
+
+    /// Read the file and return its text content plus document metadata
+    ///
+    /// Loaders that learn something about the file while reading it (such as
+    /// a page count) override this; the default adds no metadata.
+    fn load_with_metadata(&self, path: &Path) -> Result<(String, Vec<(String, String)>)> {
+        Ok((self.load(path)?, Vec::new()))
+    }
+}
+
+/// Ordered set of document loaders
+///
+/// Custom loaders are consulted before the built-in ones, most recently
+/// registered first, so they can also override a built-in format.
+#[derive(Default)]
+pub struct LoaderRegistry {
+    custom: Vec<Box<dyn DocumentLoader>>,
+    builtin: Vec<Box<dyn DocumentLoader>>,
+}
+
+impl LoaderRegistry {
+    /// Create a registry with the built-in text and source code loaders
+    pub fn new(fallback_encoding: Option<&'static Encoding>, code_filter: CodeFilter) -> Self {
+        let mut registry = Self::default();
+        registry.set_builtin(fallback_encoding, code_filter);
+        registry
+    }
+
+    /// Replace the built-in loaders, keeping registered custom loaders
+    pub fn set_builtin(
+        &mut self,
+        fallback_encoding: Option<&'static Encoding>,
+        code_filter: CodeFilter,
+    ) {
+        self.builtin = vec![
+            Box::new(TextLoader { fallback_encoding }),
+            Box::new(CodeLoader {
+                fallback_encoding,
+                code_filter,
+            }),
+            Box::new(HtmlLoader { fallback_encoding }),
+            Box::new(PdfLoader),
+        ];
+    }
+
+    /// Register a custom loader
+    pub fn register(&mut self, loader: Box<dyn DocumentLoader>) {
+        self.custom.push(loader);
+    }
+
+    /// Find the loader responsible for a file
+    pub fn find(&self, path: &Path) -> Option<&dyn DocumentLoader> {
+        self.custom
+            .iter()
+            .rev()
+            .chain(self.builtin.iter())
+            .find(|loader| loader.can_load(path))
+            .map(|loader| loader.as_ref())
+    }
+
+    /// Load a file with the first loader that accepts it
+    pub fn load(&self, path: &Path) -> Result<String> {
+        self.find_or_err(path)?.load(path)
+    }
+
+    /// Load a file and the metadata its loader reports
+    pub fn load_with_metadata(&self, path: &Path) -> Result<(String, Vec<(String, String)>)> {
+        self.find_or_err(path)?.load_with_metadata(path)
+    }
+
+    fn find_or_err(&self, path: &Path) -> Result<&dyn DocumentLoader> {
+        self.find(path).ok_or_else(|| {
+            VectDbError::InvalidInput(format!(
+                "Unsupported file type: {:?}. Currently supported: txt, md, html, pdf, source code ({})",
+                path,
+                CODE_EXTENSIONS.join(", ")
+            ))
+        })
+    }
+}
+
+/// Lowercased file extension, or an empty string when there is none
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Built-in loader for plain text and markdown
+///
+/// Files without an extension are read as text as well.
+pub struct TextLoader {
+    fallback_encoding: Option<&'static Encoding>,
+}
+
+impl DocumentLoader for TextLoader {
+    fn can_load(&self, path: &Path) -> bool {
+        matches!(extension_of(path).as_str(), "txt" | "md" | "markdown" | "")
+    }
+
+    fn load(&self, path: &Path) -> Result<String> {
+        decode_text(fs::read(path)?, self.fallback_encoding, path)
+    }
+}
+
+/// Built-in loader for recognized source code, applying the code filter
+pub struct CodeLoader {
+    fallback_encoding: Option<&'static Encoding>,
+    code_filter: CodeFilter,
+}
+
+impl DocumentLoader for CodeLoader {
+    fn can_load(&self, path: &Path) -> bool {
+        CODE_EXTENSIONS.contains(&extension_of(path).as_str())
+    }
+
+    fn load(&self, path: &Path) -> Result<String> {
+        let content = decode_text(fs::read(path)?, self.fallback_encoding, path)?;
+        match CodeLanguage::from_path(path) {
+            Some(language) => Ok(filter_code(&content, language, self.code_filter)),
+            None => Ok(content),
+        }
+    }
+}
+
+/// Built-in loader extracting readable text from HTML pages
+///
+/// The page `<title>` is reported as `title` metadata.
+pub struct HtmlLoader {
+    fallback_encoding: Option<&'static Encoding>,
+}
+
+impl DocumentLoader for HtmlLoader {
+    fn can_load(&self, path: &Path) -> bool {
+        matches!(extension_of(path).as_str(), "html" | "htm")
+    }
+
+    fn load(&self, path: &Path) -> Result<String> {
+        Ok(self.load_with_metadata(path)?.0)
+    }
+
+    fn load_with_metadata(&self, path: &Path) -> Result<(String, Vec<(String, String)>)> {
+        let html = decode_text(fs::read(path)?, self.fallback_encoding, path)?;
+        let extracted = html_to_text(&html);
+        let metadata = extracted
+            .title
+            .map(|title| vec![("title".to_string(), title)])
+            .unwrap_or_default();
+        Ok((extracted.text, metadata))
+    }
+}
+
+/// Built-in loader extracting the text layer of PDF files
+///
+/// Pages are separated by a blank line and the number of pages is reported
+/// as `page_count` metadata. PDFs without a text layer (scanned images) load
+/// as empty text with a warning, so ingestion skips them instead of failing.
+pub struct PdfLoader;
+
+impl PdfLoader {
+    /// Extract the text of each page
+    fn extract_pages(path: &Path) -> Result<Vec<String>> {
+        // pdf-extract panics on some malformed files; report those as errors
+        let extracted = std::panic::catch_unwind(|| pdf_extract::extract_text_by_pages(path))
+            .map_err(|_| VectDbError::InvalidInput(format!("Could not parse PDF: {:?}", path)))?;
+
+        extracted.map_err(|e| {
+            VectDbError::InvalidInput(format!("Could not extract text from {:?}: {}", path, e))
+        })
+    }
+}
+
+impl DocumentLoader for PdfLoader {
+    fn can_load(&self, path: &Path) -> bool {
+        extension_of(path) == "pdf"
+    }
+
+    fn load(&self, path: &Path) -> Result<String> {
+        Ok(self.load_with_metadata(path)?.0)
+    }
+
+    fn load_with_metadata(&self, path: &Path) -> Result<(String, Vec<(String, String)>)> {
+        let pages = Self::extract_pages(path)?;
+        let metadata = vec![("page_count".to_string(), pages.len().to_string())];
+
+        let text = pages
+            .iter()
+            .map(|page| page.trim())
+            .filter(|page| !page.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        if text.is_empty() {
+            warn!(
+                "No extractable text in {:?} ({} pages); it may be image-only",
+                path,
+                pages.len()
+            );
+        }
+
+        Ok((text, metadata))
+    }
+}
+
+/// Decode file bytes as UTF-8, falling back to a BOM-detected or configured encoding
+fn decode_text(
+    bytes: Vec<u8>,
+    fallback: Option<&'static Encoding>,
+    file_path: &Path,
+) -> Result<String> {
+    let bytes = match String::from_utf8(bytes) {
+        Ok(content) => return Ok(content),
+        Err(e) => e.into_bytes(),
+    };
+
+    let Some(fallback) = fallback else {
+        return Err(VectDbError::InvalidInput(format!(
+            "File is not valid UTF-8: {:?}. Use --encoding to decode it",
+            file_path
+        )));
+    };
+
+    let encoding = Encoding::for_bom(&bytes)
+        .map(|(encoding, _)| encoding)
+        .unwrap_or(fallback);
+    let (content, _, had_errors) = encoding.decode(&bytes);
+
+    info!(
+        "Decoded non-UTF-8 file {:?} as {}{}",
+        file_path,
+        encoding.name(),
+        if had_errors {
+            " (with replacement characters)"
+        } else {
+            ""
+        }
+    );
+
+    Ok(content.into_owned())
+}
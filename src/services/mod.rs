@@ -1,7 +1,11 @@
 //! Business logic services
 
 pub mod chunking;
+pub mod code_filter;
+pub mod html;
+pub mod import;
 pub mod ingestion;
+pub mod loaders;
 pub mod search;
 
 pub use chunking::chunk_text;
@@ -2,44 +2,369 @@
 //!
 //! Provides semantic search functionality using embeddings and vector similarity.
 
-use crate::clients::OllamaClient;
+use crate::clients::ollama::EmbeddingProbe;
+use crate::clients::{EmbeddingProvider, OllamaClient};
 use crate::domain::SearchResult;
-use crate::error::Result;
+use crate::error::{Result, VectDbError};
 use crate::repositories::VectorStore;
-use tracing::{debug, info};
+use crate::repositories::vector_store::{compare_results, cosine_similarity, is_interrupted};
+use crate::services::chunking::split_into_sentences;
+use serde::Serialize;
+use serde::ser::{SerializeMap, Serializer};
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Metadata boosts: metadata key -> (value to match, similarity multiplier)
+pub type MetadataBoosts = HashMap<String, (String, f32)>;
+
+/// How many candidates per requested result are fetched for MMR re-ranking
+const MMR_CANDIDATE_MULTIPLIER: usize = 4;
+
+/// Sentences kept on each side of the best-matching one in a smart snippet
+const SNIPPET_NEIGHBORS: usize = 1;
+
+/// Share of a result's similarity to the exclusion query subtracted from its score
+pub const EXCLUSION_WEIGHT: f32 = 0.5;
+
+/// Where the time of one search went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct SearchStats {
+    /// Time spent generating embeddings (query, exclusion and smart snippets)
+    pub embedding_ms: f64,
+
+    /// Time spent scanning the database and ranking candidates
+    pub search_ms: f64,
+
+    /// Stored embeddings compared with the query before the `top_k` cut
+    pub candidates_scanned: usize,
+}
+
+impl SearchStats {
+    /// One-line summary for `--explain`
+    pub fn summary(&self) -> String {
+        format!(
+            "Embedding: {:.1} ms | Search: {:.1} ms | Candidates scanned: {}",
+            self.embedding_ms, self.search_ms, self.candidates_scanned
+        )
+    }
+}
 
 /// Service for performing semantic searches
-pub struct SearchService {
+pub struct SearchService<P = OllamaClient> {
     store: VectorStore,
-    ollama: OllamaClient,
+    provider: P,
+    boosts: MetadataBoosts,
+    mmr_lambda: Option<f32>,
+    hybrid_alpha: Option<f32>,
+    relative_threshold: Option<f32>,
+    deadline: Option<Duration>,
+    smart_snippets: bool,
+    check_embedding_model: bool,
 }
 
-impl SearchService {
+impl<P: EmbeddingProvider> SearchService<P> {
     /// Create a new search service
-    pub fn new(store: VectorStore, ollama: OllamaClient) -> Self {
-        Self { store, ollama }
+    pub fn new(store: VectorStore, provider: P) -> Self {
+        Self {
+            store,
+            provider,
+            boosts: MetadataBoosts::new(),
+            mmr_lambda: None,
+            hybrid_alpha: None,
+            relative_threshold: None,
+            deadline: None,
+            smart_snippets: false,
+            check_embedding_model: false,
+        }
+    }
+
+    /// Boost results whose document metadata matches a preferred value
+    ///
+    /// A matching result's similarity is multiplied by the factor before the
+    /// final ranking. Boosted scores are no longer plain cosine similarities
+    /// and may exceed 1.0; the threshold is still checked against the raw
+    /// similarity, so a boost cannot pull in a result below it.
+    pub fn with_boosts(mut self, boosts: MetadataBoosts) -> Self {
+        self.boosts = boosts;
+        self
+    }
+
+    /// Re-rank results with Maximal Marginal Relevance
+    ///
+    /// `lambda` balances relevance to the query (1.0) against dissimilarity to
+    /// results already selected (0.0). A wider candidate set is fetched so
+    /// there is something to diversify with.
+    pub fn with_mmr(mut self, lambda: f32) -> Self {
+        self.mmr_lambda = Some(lambda);
+        self
+    }
+
+    /// Blend BM25 keyword relevance into the ranking
+    ///
+    /// `alpha` weights the normalized vector score against the normalized
+    /// keyword score (1.0 = vector only). Result similarities become the
+    /// blended score, which is also what the threshold is checked against.
+    pub fn with_hybrid(mut self, alpha: f32) -> Self {
+        self.hybrid_alpha = Some(alpha);
+        self
+    }
+
+    /// Keep only results scoring at least `ratio` times the best result
+    ///
+    /// Unlike an absolute threshold this adapts to each query's score scale,
+    /// which varies between models. Checked against the same score as the
+    /// absolute threshold, before boosts and re-ranking.
+    pub fn with_relative_threshold(mut self, ratio: f32) -> Self {
+        self.relative_threshold = Some(ratio);
+        self
+    }
+
+    /// Preview each result with its sentence closest to the query, plus neighbours
+    ///
+    /// Every sentence of a returned chunk is embedded, so this costs one
+    /// embedding request per sentence of each result.
+    pub fn with_smart_snippets(mut self, enabled: bool) -> Self {
+        self.smart_snippets = enabled;
+        self
+    }
+
+    /// Warn when the query embedding suggests `model` is not an embedding model
+    ///
+    /// Inspects the vector each search already requests instead of sending a
+    /// separate probe.
+    pub fn with_embedding_model_check(mut self, enabled: bool) -> Self {
+        self.check_embedding_model = enabled;
+        self
+    }
+
+    /// Fail searches that take longer than `deadline`
+    ///
+    /// Covers both the query embedding and the database scan. The store's
+    /// progress handler interrupts a scan still running at the deadline, so
+    /// an overrun there fails promptly instead of after the scan finishes.
+    pub fn with_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.deadline = deadline;
+        self
     }
 
     /// Perform a semantic search
+    ///
+    /// Returns `top_k` results after skipping the best `offset`, for paging.
+    /// With an `exclude_query`, results are demoted by how much they resemble
+    /// it (see [`apply_exclusion`]).
     pub async fn search(
         &self,
         query: &str,
         model: &str,
         top_k: usize,
+        offset: usize,
         threshold: f32,
+        exclude_query: Option<&str>,
     ) -> Result<Vec<SearchResult>> {
+        let (results, _) = self
+            .search_with_stats(query, model, top_k, offset, threshold, exclude_query)
+            .await?;
+        Ok(results)
+    }
+
+    /// Perform a semantic search, also reporting where the time went
+    pub async fn search_with_stats(
+        &self,
+        query: &str,
+        model: &str,
+        top_k: usize,
+        offset: usize,
+        threshold: f32,
+        exclude_query: Option<&str>,
+    ) -> Result<(Vec<SearchResult>, SearchStats)> {
+        self.search_scoped(query, model, top_k, offset, threshold, exclude_query, None)
+            .await
+    }
+
+    /// Perform a semantic search restricted to the given document ids
+    pub async fn search_within_documents(
+        &self,
+        query: &str,
+        model: &str,
+        top_k: usize,
+        threshold: f32,
+        exclude_query: Option<&str>,
+        document_ids: &[i64],
+    ) -> Result<Vec<SearchResult>> {
+        let (results, _) = self
+            .search_within_documents_with_stats(
+                query,
+                model,
+                top_k,
+                threshold,
+                exclude_query,
+                document_ids,
+            )
+            .await?;
+        Ok(results)
+    }
+
+    /// Perform a search restricted to the given document ids, also reporting timings
+    pub async fn search_within_documents_with_stats(
+        &self,
+        query: &str,
+        model: &str,
+        top_k: usize,
+        threshold: f32,
+        exclude_query: Option<&str>,
+        document_ids: &[i64],
+    ) -> Result<(Vec<SearchResult>, SearchStats)> {
+        self.search_scoped(
+            query,
+            model,
+            top_k,
+            0,
+            threshold,
+            exclude_query,
+            Some(document_ids),
+        )
+        .await
+    }
+
+    /// Run golden queries and report where each expected source ranked
+    pub async fn run_regression(
+        &self,
+        golden: &[GoldenQuery],
+        model: &str,
+        top_k: usize,
+    ) -> Result<Vec<RegressionOutcome>> {
+        let mut outcomes = Vec::with_capacity(golden.len());
+        for entry in golden {
+            let results = self
+                .search(&entry.query, model, top_k, 0, 0.0, None)
+                .await?;
+            let rank = results
+                .iter()
+                .position(|r| r.document.source == entry.expected_source)
+                .map(|idx| idx + 1);
+            outcomes.push(RegressionOutcome {
+                query: entry.query.clone(),
+                expected_source: entry.expected_source.clone(),
+                rank,
+            });
+        }
+        Ok(outcomes)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn search_scoped(
+        &self,
+        query: &str,
+        model: &str,
+        top_k: usize,
+        offset: usize,
+        threshold: f32,
+        exclude_query: Option<&str>,
+        document_ids: Option<&[i64]>,
+    ) -> Result<(Vec<SearchResult>, SearchStats)> {
+        let started = Instant::now();
+        with_deadline(
+            self.deadline,
+            self.search_unbounded(
+                query,
+                model,
+                top_k,
+                offset,
+                threshold,
+                exclude_query,
+                document_ids,
+                started,
+            ),
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn search_unbounded(
+        &self,
+        query: &str,
+        model: &str,
+        top_k: usize,
+        offset: usize,
+        threshold: f32,
+        exclude_query: Option<&str>,
+        document_ids: Option<&[i64]>,
+        started: Instant,
+    ) -> Result<(Vec<SearchResult>, SearchStats)> {
+        let query = validate_query(query)?;
+
         info!(
-            "Performing semantic search: query='{}', top_k={}, threshold={}",
-            query, top_k, threshold
+            "Performing semantic search: query='{}', top_k={}, offset={}, threshold={}",
+            query, top_k, offset, threshold
         );
 
+        // Rank everything up to the end of the requested page, then cut the page out
+        let needed = offset.saturating_add(top_k);
+
         // Generate embedding for the query
         debug!("Generating query embedding");
-        let query_embedding = self.ollama.embed(model, query).await?;
+        let mut embedding_time = Duration::ZERO;
+        let embedding_started = Instant::now();
+        let query_embedding = self.provider.embed(model, query).await?;
+        if self.check_embedding_model
+            && let Some(warning) = EmbeddingProbe::from_vector(&query_embedding).warning(model)
+        {
+            warn!("{}", warning);
+        }
+        let exclude_embedding = match exclude_query.map(validate_query).transpose()? {
+            Some(exclude) => {
+                debug!("Generating exclusion embedding");
+                Some(self.provider.embed(model, exclude).await?)
+            }
+            None => None,
+        };
+        embedding_time += embedding_started.elapsed();
+        let search_started = Instant::now();
 
-        // Search for similar vectors
+        // Search for similar vectors; boosts and exclusions can reorder results,
+        // so rank all candidates
         debug!("Searching for similar vectors");
-        let mut results = self.store.search_similar(&query_embedding, model, top_k)?;
+        let candidates = if !self.boosts.is_empty() || exclude_embedding.is_some() {
+            usize::MAX
+        } else if self.mmr_lambda.is_some() {
+            needed.saturating_mul(MMR_CANDIDATE_MULTIPLIER)
+        } else {
+            needed
+        };
+        // The scan is synchronous, so the deadline has to interrupt it from within
+        self.store
+            .set_deadline(self.deadline.map(|deadline| started + deadline));
+        let scanned = match (self.hybrid_alpha, document_ids) {
+            (Some(alpha), None) => {
+                self.store
+                    .hybrid_search(query, &query_embedding, model, candidates, alpha)
+            }
+            (Some(alpha), Some(ids)) => self
+                .store
+                .hybrid_search(query, &query_embedding, model, usize::MAX, alpha)
+                .map(|mut results| {
+                    results.retain(|r| r.document.id.is_some_and(|id| ids.contains(&id)));
+                    results
+                }),
+            (None, Some(ids)) => {
+                self.store
+                    .search_within_documents(&query_embedding, model, candidates, ids)
+            }
+            (None, None) => self
+                .store
+                .search_similar(&query_embedding, model, candidates, 0),
+        };
+        self.store.set_deadline(None);
+        let mut results = scanned.map_err(|e| {
+            if is_interrupted(&e) {
+                deadline_exceeded()
+            } else {
+                e
+            }
+        })?;
+        check_deadline(self.deadline, started)?;
 
         // Filter by threshold
         if threshold > 0.0 {
@@ -50,15 +375,281 @@ impl SearchService {
                 threshold
             );
         }
+        if let Some(ratio) = self.relative_threshold {
+            retain_relative(&mut results, ratio);
+            debug!(
+                "Filtered to {} results within {} of the best score",
+                results.len(),
+                ratio
+            );
+        }
+
+        if !self.boosts.is_empty() {
+            apply_boosts(&mut results, &self.boosts);
+        }
+
+        if let Some(exclude_embedding) = &exclude_embedding {
+            let vectors = self.result_vectors(&results)?;
+            apply_exclusion(&mut results, &vectors, exclude_embedding, EXCLUSION_WEIGHT);
+        }
+
+        if let Some(lambda) = self.mmr_lambda {
+            results = self.rerank_mmr(results, lambda, needed)?;
+        }
+        let candidates_scanned = self.store.rows_scanned();
+        results.drain(..offset.min(results.len()));
+        results.truncate(top_k);
+        let search_time = search_started.elapsed();
+
+        if self.smart_snippets {
+            let snippets_started = Instant::now();
+            for result in &mut results {
+                result.snippet = self
+                    .best_sentence_snippet(model, &query_embedding, &result.chunk.content)
+                    .await?;
+            }
+            embedding_time += snippets_started.elapsed();
+            check_deadline(self.deadline, started)?;
+        }
 
         info!("Found {} results", results.len());
 
-        Ok(results)
+        let stats = SearchStats {
+            embedding_ms: embedding_time.as_secs_f64() * 1000.0,
+            search_ms: search_time.as_secs_f64() * 1000.0,
+            candidates_scanned,
+        };
+        Ok((results, stats))
+    }
+
+    /// The sentence of `content` most similar to the query, with its neighbours
+    ///
+    /// Returns `None` for chunks too short to need a snippet.
+    async fn best_sentence_snippet(
+        &self,
+        model: &str,
+        query_embedding: &[f32],
+        content: &str,
+    ) -> Result<Option<String>> {
+        let sentences: Vec<String> = split_into_sentences(content)
+            .into_iter()
+            .map(|sentence| sentence.trim().to_string())
+            .collect();
+        if sentences.len() <= 2 * SNIPPET_NEIGHBORS + 1 {
+            return Ok(None);
+        }
+
+        let vectors = self.provider.embed_batch(model, &sentences).await?;
+        let best = vectors
+            .iter()
+            .map(|vector| cosine_similarity(query_embedding, vector))
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(0, |(idx, _)| idx);
+
+        let start = best.saturating_sub(SNIPPET_NEIGHBORS);
+        let end = (best + SNIPPET_NEIGHBORS + 1).min(sentences.len());
+        Ok(Some(sentences[start..end].join(" ")))
+    }
+
+    /// Stored embedding of each result (empty if it has none), read in one query
+    fn result_vectors(&self, results: &[SearchResult]) -> Result<Vec<Vec<f32>>> {
+        let chunk_ids: Vec<i64> = results.iter().filter_map(|r| r.chunk.id).collect();
+        let mut vectors = self.store.get_embedding_vectors(&chunk_ids)?;
+
+        Ok(results
+            .iter()
+            .map(|result| {
+                result
+                    .chunk
+                    .id
+                    .and_then(|chunk_id| vectors.remove(&chunk_id))
+                    .unwrap_or_default()
+            })
+            .collect())
+    }
+
+    /// Reorder candidates by MMR using their stored embeddings
+    fn rerank_mmr(
+        &self,
+        results: Vec<SearchResult>,
+        lambda: f32,
+        top_k: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let vectors = self.result_vectors(&results)?;
+
+        let relevance: Vec<f32> = results.iter().map(|r| r.similarity).collect();
+        let order = mmr_select(&relevance, &vectors, lambda, top_k);
+        debug!(
+            "MMR selected {} of {} candidates",
+            order.len(),
+            results.len()
+        );
+
+        let mut slots: Vec<Option<SearchResult>> = results.into_iter().map(Some).collect();
+        Ok(order
+            .into_iter()
+            .filter_map(|idx| slots[idx].take())
+            .collect())
+    }
+}
+
+/// Drop results scoring below `ratio` times the best similarity
+///
+/// Does nothing when the best similarity is not positive, since a fraction
+/// of it would no longer be a lower bound.
+pub fn retain_relative(results: &mut Vec<SearchResult>, ratio: f32) {
+    let best = results
+        .iter()
+        .map(|r| r.similarity)
+        .fold(f32::NEG_INFINITY, f32::max);
+    if best > 0.0 {
+        let cutoff = best * ratio;
+        results.retain(|r| r.similarity >= cutoff);
+    }
+}
+
+/// Select up to `k` candidates by Maximal Marginal Relevance
+///
+/// Each step picks the candidate maximizing
+/// `lambda * relevance - (1 - lambda) * max_similarity_to_selected`.
+/// Returns candidate indices in selection order.
+pub fn mmr_select(relevance: &[f32], vectors: &[Vec<f32>], lambda: f32, k: usize) -> Vec<usize> {
+    let mut selected: Vec<usize> = Vec::with_capacity(k.min(relevance.len()));
+    let mut remaining: Vec<usize> = (0..relevance.len()).collect();
+
+    while selected.len() < k && !remaining.is_empty() {
+        let (best_pos, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(pos, &idx)| {
+                let redundancy = selected
+                    .iter()
+                    .map(|&chosen| cosine_similarity(&vectors[idx], &vectors[chosen]))
+                    .fold(0.0f32, f32::max);
+                (pos, lambda * relevance[idx] - (1.0 - lambda) * redundancy)
+            })
+            .fold((0, f32::NEG_INFINITY), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+        selected.push(remaining.remove(best_pos));
+    }
+
+    selected
+}
+
+/// Error returned when a search runs past its deadline
+pub fn deadline_exceeded() -> VectDbError {
+    VectDbError::SearchFailed("deadline exceeded".to_string())
+}
+
+/// Run a future, failing with [`deadline_exceeded`] if it outlives the deadline
+pub async fn with_deadline<T>(
+    deadline: Option<Duration>,
+    future: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout(deadline, future)
+            .await
+            .map_err(|_| deadline_exceeded())?,
+        None => future.await,
+    }
+}
+
+/// Fail if more than `deadline` has passed since `started`
+pub fn check_deadline(deadline: Option<Duration>, started: Instant) -> Result<()> {
+    match deadline {
+        Some(deadline) if started.elapsed() > deadline => Err(deadline_exceeded()),
+        _ => Ok(()),
+    }
+}
+
+/// Trim a search query, rejecting empty or whitespace-only input
+pub fn validate_query(query: &str) -> Result<&str> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(VectDbError::InvalidInput(
+            "Search query must not be empty".to_string(),
+        ));
+    }
+
+    Ok(query)
+}
+
+/// Multiply the similarity of results matching a boost and re-sort by score
+pub fn apply_boosts(results: &mut [SearchResult], boosts: &MetadataBoosts) {
+    for result in results.iter_mut() {
+        for (key, (value, factor)) in boosts {
+            if result.document.metadata.get(key) == Some(value) {
+                result.similarity *= factor;
+            }
+        }
+    }
+
+    results.sort_by(compare_results);
+}
+
+/// Demote results that resemble an exclusion query, then re-rank
+///
+/// Each score becomes `score - weight * max(0, cosine(result, exclusion))`,
+/// where `vectors` holds the results' stored embeddings in the same order.
+/// Results unrelated to (or opposite from) the exclusion keep their score.
+pub fn apply_exclusion(
+    results: &mut [SearchResult],
+    vectors: &[Vec<f32>],
+    exclude_embedding: &[f32],
+    weight: f32,
+) {
+    for (result, vector) in results.iter_mut().zip(vectors) {
+        let resemblance = cosine_similarity(vector, exclude_embedding).max(0.0);
+        result.similarity -= weight * resemblance;
     }
+
+    results.sort_by(compare_results);
+}
+
+/// Parse a boost specification of the form `key=value:factor` (e.g. `priority=high:1.5`)
+pub fn parse_boost(spec: &str) -> Result<(String, (String, f32))> {
+    let invalid = || {
+        VectDbError::InvalidInput(format!(
+            "Invalid boost: {}. Expected key=value:factor",
+            spec
+        ))
+    };
+
+    let (key, rest) = spec.split_once('=').ok_or_else(invalid)?;
+    let (value, factor) = rest.rsplit_once(':').ok_or_else(invalid)?;
+    let factor: f32 = factor.trim().parse().map_err(|_| invalid())?;
+
+    if key.trim().is_empty() || !factor.is_finite() || factor < 0.0 {
+        return Err(invalid());
+    }
+
+    Ok((key.trim().to_string(), (value.to_string(), factor)))
+}
+
+/// Parse a comma-separated list of document ids (e.g. "1,2,3")
+pub fn parse_document_ids(input: &str) -> Result<Vec<i64>> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<i64>()
+                .map_err(|_| VectDbError::InvalidInput(format!("Invalid document id: {}", id)))
+        })
+        .collect()
 }
 
 /// Format search results as text
-pub fn format_results_text(results: &[SearchResult], explain: bool) -> String {
+///
+/// With `explain` set to the query, each result also shows its similarity and
+/// how many of the query's words occur in the chunk (see [`term_overlap`]).
+pub fn format_results_text(results: &[SearchResult], explain: Option<&str>) -> String {
     if results.is_empty() {
         return "No results found.".to_string();
     }
@@ -70,15 +661,31 @@ pub fn format_results_text(results: &[SearchResult], explain: bool) -> String {
     for (idx, result) in results.iter().enumerate() {
         output.push_str(&format!("=== Result {} ===\n", idx + 1));
 
-        if explain {
+        if let Some(query) = explain {
             output.push_str(&format!("Similarity: {:.4}\n", result.similarity));
+
+            let overlap = term_overlap(query, &result.chunk.content);
+            let matched = if overlap.matched.is_empty() {
+                "none, a purely semantic match".to_string()
+            } else {
+                overlap.matched.join(", ")
+            };
+            output.push_str(&format!(
+                "Term overlap: {:.0}% ({}/{} query words: {})\n",
+                overlap.fraction() * 100.0,
+                overlap.matched.len(),
+                overlap.query_terms,
+                matched
+            ));
         }
 
         output.push_str(&format!("Source: {}\n", result.document.source));
         output.push_str(&format!("Chunk {}\n\n", result.chunk.chunk_index + 1));
 
-        // Truncate long content for display
-        let content = if result.chunk.content.len() > 500 {
+        // Show the smart snippet if there is one, otherwise truncate long content
+        let content = if let Some(snippet) = &result.snippet {
+            snippet.clone()
+        } else if result.chunk.content.len() > 500 {
             format!("{}...", &result.chunk.content[..500])
         } else {
             result.chunk.content.clone()
@@ -90,31 +697,422 @@ pub fn format_results_text(results: &[SearchResult], explain: bool) -> String {
     output
 }
 
+/// Query words found in a chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermOverlap {
+    /// Distinct query words present in the chunk, in query order
+    pub matched: Vec<String>,
+    /// Distinct words in the query
+    pub query_terms: usize,
+}
+
+impl TermOverlap {
+    /// Fraction of the query's words present in the chunk (0.0 for an empty query)
+    pub fn fraction(&self) -> f32 {
+        if self.query_terms == 0 {
+            0.0
+        } else {
+            self.matched.len() as f32 / self.query_terms as f32
+        }
+    }
+}
+
+/// Lexical overlap between a query and chunk content
+///
+/// Both are split into lowercase alphanumeric words; the overlap is the set
+/// intersection of the query's words with the chunk's.
+pub fn term_overlap(query: &str, content: &str) -> TermOverlap {
+    let content_words: std::collections::HashSet<String> = words(content).collect();
+
+    let mut query_words: Vec<String> = Vec::new();
+    for word in words(query) {
+        if !query_words.contains(&word) {
+            query_words.push(word);
+        }
+    }
+
+    TermOverlap {
+        query_terms: query_words.len(),
+        matched: query_words
+            .into_iter()
+            .filter(|word| content_words.contains(word))
+            .collect(),
+    }
+}
+
+/// Lowercase alphanumeric words of a text
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
 /// Format search results as JSON
 pub fn format_results_json(results: &[SearchResult]) -> Result<String> {
     let json = serde_json::to_string_pretty(results)?;
     Ok(json)
 }
 
+/// Write search results as JSON to a writer without buffering the whole output
+pub fn write_results_json<W: Write>(writer: &mut W, results: &[SearchResult]) -> Result<()> {
+    serde_json::to_writer_pretty(writer, results)?;
+    Ok(())
+}
+
+/// A field of a search result that can be selected for JSON output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultField {
+    Source,
+    Similarity,
+    Content,
+    ChunkIndex,
+    DocumentId,
+    ChunkId,
+    Metadata,
+    CreatedAt,
+}
+
+impl ResultField {
+    /// Key used for this field in the JSON output
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResultField::Source => "source",
+            ResultField::Similarity => "similarity",
+            ResultField::Content => "content",
+            ResultField::ChunkIndex => "chunk_index",
+            ResultField::DocumentId => "document_id",
+            ResultField::ChunkId => "chunk_id",
+            ResultField::Metadata => "metadata",
+            ResultField::CreatedAt => "created_at",
+        }
+    }
+}
+
+impl std::str::FromStr for ResultField {
+    type Err = VectDbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "source" => Ok(ResultField::Source),
+            "similarity" => Ok(ResultField::Similarity),
+            "content" => Ok(ResultField::Content),
+            "chunk_index" => Ok(ResultField::ChunkIndex),
+            "document_id" => Ok(ResultField::DocumentId),
+            "chunk_id" => Ok(ResultField::ChunkId),
+            "metadata" => Ok(ResultField::Metadata),
+            "created_at" => Ok(ResultField::CreatedAt),
+            other => Err(VectDbError::InvalidInput(format!(
+                "Unknown result field: {}. Available: source, similarity, content, chunk_index, \
+                 document_id, chunk_id, metadata, created_at",
+                other
+            ))),
+        }
+    }
+}
+
+/// Parse a comma-separated list of result fields (e.g. "source,similarity,content")
+pub fn parse_fields(input: &str) -> Result<Vec<ResultField>> {
+    input
+        .split(',')
+        .filter(|field| !field.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Serializes only the selected fields of a search result, in the given order
+pub struct ProjectedResult<'a> {
+    pub result: &'a SearchResult,
+    pub fields: &'a [ResultField],
+}
+
+impl Serialize for ProjectedResult<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for field in self.fields {
+            let key = field.name();
+            match field {
+                ResultField::Source => map.serialize_entry(key, &self.result.document.source)?,
+                ResultField::Similarity => map.serialize_entry(key, &self.result.similarity)?,
+                ResultField::Content => map.serialize_entry(key, &self.result.chunk.content)?,
+                ResultField::ChunkIndex => {
+                    map.serialize_entry(key, &self.result.chunk.chunk_index)?
+                }
+                ResultField::DocumentId => map.serialize_entry(key, &self.result.document.id)?,
+                ResultField::ChunkId => map.serialize_entry(key, &self.result.chunk.id)?,
+                ResultField::Metadata => {
+                    map.serialize_entry(key, &self.result.document.metadata)?
+                }
+                ResultField::CreatedAt => {
+                    map.serialize_entry(key, &self.result.document.created_at)?
+                }
+            }
+        }
+        map.end()
+    }
+}
+
+/// Write search results as JSON containing only the selected fields
+pub fn write_projected_json<W: Write>(
+    writer: &mut W,
+    results: &[SearchResult],
+    fields: &[ResultField],
+) -> Result<()> {
+    let projected: Vec<ProjectedResult> = results
+        .iter()
+        .map(|result| ProjectedResult { result, fields })
+        .collect();
+    serde_json::to_writer_pretty(writer, &projected)?;
+    Ok(())
+}
+
+/// Write search results as a JSON object together with the search's stats
+///
+/// The results are projected to `fields` when given, as in [`write_projected_json`].
+pub fn write_results_json_with_stats<W: Write>(
+    writer: &mut W,
+    results: &[SearchResult],
+    fields: Option<&[ResultField]>,
+    stats: &SearchStats,
+) -> Result<()> {
+    #[derive(Serialize)]
+    struct WithStats<'a, T: Serialize> {
+        stats: &'a SearchStats,
+        results: T,
+    }
+
+    match fields {
+        Some(fields) => {
+            let projected: Vec<ProjectedResult> = results
+                .iter()
+                .map(|result| ProjectedResult { result, fields })
+                .collect();
+            serde_json::to_writer_pretty(
+                writer,
+                &WithStats {
+                    stats,
+                    results: projected,
+                },
+            )?;
+        }
+        None => serde_json::to_writer_pretty(writer, &WithStats { stats, results })?,
+    }
+    Ok(())
+}
+
 /// Format search results as CSV
 pub fn format_results_csv(results: &[SearchResult]) -> String {
-    let mut output = String::new();
+    let mut output = Vec::new();
+    write_results_csv(&mut output, results).expect("writing to a Vec cannot fail");
+    String::from_utf8(output).expect("CSV output is valid UTF-8")
+}
 
+/// Write search results as CSV to a writer, one row at a time
+pub fn write_results_csv<W: Write>(writer: &mut W, results: &[SearchResult]) -> Result<()> {
     // Header
-    output.push_str("rank,similarity,source,chunk_index,content\n");
+    writer.write_all(b"rank,similarity,source,chunk_index,content\n")?;
 
     // Rows
     for (idx, result) in results.iter().enumerate() {
         let content = result.chunk.content.replace('"', "\"\""); // Escape quotes
         let content = content.replace('\n', " "); // Remove newlines
 
-        output.push_str(&format!(
-            "{},{:.4},\"{}\",{},\"{}\"\n",
+        writeln!(
+            writer,
+            "{},{:.4},\"{}\",{},\"{}\"",
             idx + 1,
             result.similarity,
             result.document.source,
             result.chunk.chunk_index + 1,
             content
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A single query from a batch query file
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchQuery {
+    /// Query identifier (used as the TREC `query_id`)
+    pub id: String,
+
+    /// Query text
+    pub text: String,
+}
+
+/// Parse a batch query file
+///
+/// Each non-empty line is either `query_id<TAB>query text` or just the query
+/// text, in which case the 1-based line number is used as the id. Lines
+/// starting with `#` are treated as comments.
+pub fn parse_batch_queries(contents: &str) -> Vec<BatchQuery> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+
+            let (id, text) = match line.split_once('\t') {
+                Some((id, text)) => (id.trim().to_string(), text.trim().to_string()),
+                None => ((idx + 1).to_string(), line.to_string()),
+            };
+
+            Some(BatchQuery { id, text })
+        })
+        .collect()
+}
+
+/// A matched document source with the score of its best chunk
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourceMatch {
+    pub source: String,
+    pub best_similarity: f32,
+}
+
+/// Collapse ranked chunk results to one entry per source, best score first
+pub fn group_by_source(results: &[SearchResult]) -> Vec<SourceMatch> {
+    let mut sources: Vec<SourceMatch> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+
+    for result in results {
+        match index.get(result.document.source.as_str()) {
+            Some(&idx) => {
+                let entry = &mut sources[idx];
+                entry.best_similarity = entry.best_similarity.max(result.similarity);
+            }
+            None => {
+                index.insert(&result.document.source, sources.len());
+                sources.push(SourceMatch {
+                    source: result.document.source.clone(),
+                    best_similarity: result.similarity,
+                });
+            }
+        }
+    }
+
+    sources.sort_by(|a, b| {
+        b.best_similarity
+            .partial_cmp(&a.best_similarity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    sources
+}
+
+/// A query paired with the document source expected in its top results
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenQuery {
+    pub query: String,
+    pub expected_source: String,
+}
+
+/// Parse a golden query file
+///
+/// Each non-empty line is `query -> expected_source`; lines starting with `#`
+/// are comments.
+pub fn parse_golden_queries(contents: &str) -> Result<Vec<GoldenQuery>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(idx, line)| {
+            let (query, source) = line
+                .rsplit_once("->")
+                .map(|(q, s)| (q.trim(), s.trim()))
+                .filter(|(q, s)| !q.is_empty() && !s.is_empty())
+                .ok_or_else(|| {
+                    VectDbError::InvalidInput(format!(
+                        "Line {}: expected 'query -> expected_source', got '{}'",
+                        idx + 1,
+                        line.trim()
+                    ))
+                })?;
+            Ok(GoldenQuery {
+                query: query.to_string(),
+                expected_source: source.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Result of one golden query
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegressionOutcome {
+    pub query: String,
+    pub expected_source: String,
+    /// 1-based rank of the expected source, if it appeared in the top-k
+    pub rank: Option<usize>,
+}
+
+impl RegressionOutcome {
+    pub fn passed(&self) -> bool {
+        self.rank.is_some()
+    }
+}
+
+/// One query's results as a line of NDJSON batch output
+#[derive(Debug, Serialize)]
+pub struct BatchQueryResults<'a> {
+    pub query_id: &'a str,
+    pub query: &'a str,
+    pub results: &'a [SearchResult],
+}
+
+/// Write one query's results as a single NDJSON line and flush
+///
+/// Flushing per query lets downstream tools consume a long batch run
+/// incrementally instead of waiting for it to finish.
+pub fn write_batch_ndjson<W: Write>(
+    writer: &mut W,
+    query: &BatchQuery,
+    results: &[SearchResult],
+) -> Result<()> {
+    let line = BatchQueryResults {
+        query_id: &query.id,
+        query: &query.text,
+        results,
+    };
+    serde_json::to_writer(&mut *writer, &line)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Format search results for one query as TREC run lines
+///
+/// Each line follows the `query_id Q0 doc_id rank score run_name` layout
+/// expected by `trec_eval`. The document source is used as the doc_id (with
+/// whitespace replaced, since columns are whitespace-separated), and only the
+/// best-ranked chunk of each document is emitted because `trec_eval` rejects
+/// duplicate doc_ids within a query.
+pub fn format_results_trec(query_id: &str, results: &[SearchResult], run_name: &str) -> String {
+    let mut output = String::new();
+    let mut seen = std::collections::HashSet::new();
+
+    for result in results {
+        let doc_id: String = result
+            .document
+            .source
+            .chars()
+            .map(|c| if c.is_whitespace() { '_' } else { c })
+            .collect();
+
+        if !seen.insert(doc_id.clone()) {
+            continue;
+        }
+
+        output.push_str(&format!(
+            "{} Q0 {} {} {:.6} {}\n",
+            query_id,
+            doc_id,
+            seen.len(),
+            result.similarity,
+            run_name
         ));
     }
 
@@ -129,7 +1127,7 @@ mod tests {
     #[test]
     fn test_format_results_text_empty() {
         let results = vec![];
-        let output = format_results_text(&results, false);
+        let output = format_results_text(&results, None);
         assert!(output.contains("No results found"));
     }
 
@@ -141,13 +1139,35 @@ mod tests {
             chunk,
             document: doc,
             similarity: 0.95,
+            snippet: None,
         };
 
-        let output = format_results_text(&[result], true);
+        let output = format_results_text(&[result], Some("chunk"));
         assert!(output.contains("Result 1"));
         assert!(output.contains("0.95"));
         assert!(output.contains("test.txt"));
         assert!(output.contains("Test chunk content"));
+        assert!(output.contains("Term overlap: 100% (1/1 query words: chunk)"));
+    }
+
+    #[test]
+    fn test_term_overlap_fraction() {
+        let overlap = term_overlap(
+            "How does the borrow checker work?",
+            "The borrow checker enforces ownership rules at compile time.",
+        );
+        // how, does, the, borrow, checker, work -> the, borrow, checker
+        assert_eq!(overlap.query_terms, 6);
+        assert_eq!(overlap.matched, vec!["the", "borrow", "checker"]);
+        assert!((overlap.fraction() - 0.5).abs() < 1e-6);
+
+        // Case-insensitive, repeated query words count once
+        let overlap = term_overlap("Rust rust RUST", "rustaceans love rust");
+        assert_eq!(overlap.query_terms, 1);
+        assert_eq!(overlap.fraction(), 1.0);
+
+        assert_eq!(term_overlap("", "anything").fraction(), 0.0);
+        assert_eq!(term_overlap("lifetimes", "ownership").fraction(), 0.0);
     }
 
     #[test]
@@ -158,6 +1178,7 @@ mod tests {
             chunk,
             document: doc,
             similarity: 0.85,
+            snippet: None,
         };
 
         let output = format_results_json(&[result]).unwrap();
@@ -173,6 +1194,7 @@ mod tests {
             chunk,
             document: doc,
             similarity: 0.75,
+            snippet: None,
         };
 
         let output = format_results_csv(&[result]);
@@ -189,9 +1211,667 @@ mod tests {
             chunk,
             document: doc,
             similarity: 0.5,
+            snippet: None,
         };
 
         let output = format_results_csv(&[result]);
         assert!(output.contains("\"\""));
     }
+
+    #[test]
+    fn test_parse_batch_queries() {
+        let contents = "q1\tfirst query\n\n# comment\nsecond query\n";
+        let queries = parse_batch_queries(contents);
+
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].id, "q1");
+        assert_eq!(queries[0].text, "first query");
+        assert_eq!(queries[1].id, "4");
+        assert_eq!(queries[1].text, "second query");
+    }
+
+    #[test]
+    fn test_format_results_trec() {
+        let make = |source: &str, index: usize, similarity: f32| SearchResult {
+            chunk: Chunk::new(1, index, "content".to_string()),
+            document: Document::new(source.to_string(), source),
+            similarity,
+            snippet: None,
+        };
+
+        let mut output = format_results_trec(
+            "q1",
+            &[
+                make("a.txt", 0, 0.9),
+                make("a.txt", 1, 0.8),
+                make("b c.txt", 0, 0.7),
+            ],
+            "run1",
+        );
+        output.push_str(&format_results_trec(
+            "q2",
+            &[make("b c.txt", 0, 0.6)],
+            "run1",
+        ));
+
+        let lines: Vec<Vec<&str>> = output
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+
+        assert_eq!(lines.len(), 3);
+        for columns in &lines {
+            assert_eq!(columns.len(), 6);
+            assert_eq!(columns[1], "Q0");
+            assert_eq!(columns[5], "run1");
+        }
+
+        assert_eq!(lines[0], vec!["q1", "Q0", "a.txt", "1", "0.900000", "run1"]);
+        assert_eq!(
+            lines[1],
+            vec!["q1", "Q0", "b_c.txt", "2", "0.700000", "run1"]
+        );
+        assert_eq!(
+            lines[2],
+            vec!["q2", "Q0", "b_c.txt", "1", "0.600000", "run1"]
+        );
+    }
+
+    #[test]
+    fn test_write_results_matches_string_formatters() {
+        let results: Vec<SearchResult> = (0..200)
+            .map(|i| SearchResult {
+                chunk: Chunk::new(1, i, format!("Chunk \"{}\"\nwith a newline", i)),
+                document: Document::new(format!("doc{}.txt", i), "content"),
+                similarity: 1.0 / (i as f32 + 1.0),
+                snippet: None,
+            })
+            .collect();
+
+        let mut csv = Vec::new();
+        write_results_csv(&mut csv, &results).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            format_results_csv(&results)
+        );
+
+        let mut json = Vec::new();
+        write_results_json(&mut json, &results).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            format_results_json(&results).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_document_ids() {
+        assert_eq!(parse_document_ids("1, 2,3").unwrap(), vec![1, 2, 3]);
+        assert!(parse_document_ids("1,two").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_boost_overtakes_higher_similarity() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        for (source, priority, vector) in [
+            ("close.txt", "low", vec![1.0, 0.1]),
+            ("far.txt", "high", vec![1.0, 0.6]),
+        ] {
+            let doc = Document::new(source.to_string(), source)
+                .with_metadata("priority".to_string(), priority.to_string());
+            let doc_id = store.insert_document(&doc).unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, source.to_string()))
+                .unwrap();
+            store
+                .upsert_embedding(&crate::domain::Embedding::new(
+                    chunk_id,
+                    "model".to_string(),
+                    vector,
+                ))
+                .unwrap();
+        }
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = SearchService::new(store, ollama);
+        let results = service
+            .search("query", "model", 1, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results[0].document.source, "close.txt");
+
+        let boosts = MetadataBoosts::from([parse_boost("priority=high:1.5").unwrap()]);
+        let service = service.with_boosts(boosts);
+        let results = service
+            .search("query", "model", 1, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.source, "far.txt");
+        assert!(results[0].similarity > 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_smart_snippet_picks_best_sentence() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        // The query and the one relevant sentence embed close together
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(body_string_contains("borrow"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.0, 1.0]
+            })))
+            .mount(&server)
+            .await;
+
+        let content = "Rust has a package manager. It is called cargo. \
+                       The borrow checker rejects dangling references. \
+                       Macros generate code. Traits define shared behavior.";
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("rust.md".to_string(), content))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, content.to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&crate::domain::Embedding::new(
+                chunk_id,
+                "model".to_string(),
+                vec![1.0, 0.0],
+            ))
+            .unwrap();
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = SearchService::new(store, ollama);
+        let results = service
+            .search("how does borrowing work", "model", 1, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results[0].snippet, None);
+
+        let service = service.with_smart_snippets(true);
+        let results = service
+            .search("how does borrowing work", "model", 1, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            results[0].snippet.as_deref(),
+            Some(
+                "It is called cargo. The borrow checker rejects dangling references. \
+                 Macros generate code."
+            )
+        );
+        assert!(format_results_text(&results, None).contains("\n\nIt is called cargo."));
+    }
+
+    #[tokio::test]
+    async fn test_embedding_model_check_reuses_query_embedding() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("a.txt".to_string(), "a"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "a".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&crate::domain::Embedding::new(
+                chunk_id,
+                "model".to_string(),
+                vec![1.0, 0.0],
+            ))
+            .unwrap();
+
+        // The 2-dimensional query vector is suspicious, but no separate probe is sent
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = SearchService::new(store, ollama).with_embedding_model_check(true);
+        let results = service
+            .search("query", "model", 1, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_search_stats_count_candidates() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        let mut doc_ids = Vec::new();
+        for (source, vector) in [
+            ("a.txt", vec![1.0, 0.0]),
+            ("b.txt", vec![0.5, 0.5]),
+            ("c.txt", vec![0.0, 1.0]),
+        ] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, source.to_string()))
+                .unwrap();
+            store
+                .upsert_embedding(&crate::domain::Embedding::new(
+                    chunk_id,
+                    "model".to_string(),
+                    vector,
+                ))
+                .unwrap();
+            doc_ids.push(doc_id);
+        }
+
+        // Without the sqlite-vec index, every embedding of the model is scored
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = SearchService::new(store.with_ann(false), ollama);
+        let (results, stats) = service
+            .search_with_stats("query", "model", 1, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(stats.candidates_scanned, 3);
+        assert!(stats.embedding_ms > 0.0);
+        assert!(stats.search_ms >= 0.0);
+
+        let (_, stats) = service
+            .search_within_documents_with_stats("query", "model", 1, 0.0, None, &doc_ids[..2])
+            .await
+            .unwrap();
+        assert_eq!(stats.candidates_scanned, 2);
+
+        let mut json = Vec::new();
+        let fields = parse_fields("source").unwrap();
+        write_results_json_with_stats(&mut json, &results, Some(&fields), &stats).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(parsed["stats"]["candidates_scanned"], 2);
+        assert!(parsed["stats"]["embedding_ms"].is_number());
+        assert_eq!(parsed["results"][0]["source"], "a.txt");
+        assert!(stats.summary().contains("Candidates scanned: 2"));
+    }
+
+    #[tokio::test]
+    async fn test_relative_threshold_keeps_near_top_results() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        for (source, vector) in [
+            ("best.txt", vec![1.0, 0.0]),
+            ("near.txt", vec![1.0, 0.1]),
+            ("gap.txt", vec![1.0, 1.0]),
+            ("far.txt", vec![0.1, 1.0]),
+        ] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, source.to_string()))
+                .unwrap();
+            store
+                .upsert_embedding(&crate::domain::Embedding::new(
+                    chunk_id,
+                    "model".to_string(),
+                    vector,
+                ))
+                .unwrap();
+        }
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = SearchService::new(store, ollama);
+        let results = service
+            .search("query", "model", 10, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 4);
+
+        // best = 1.0, near ~ 0.995, gap ~ 0.707, far ~ 0.0995
+        let service = service.with_relative_threshold(0.9);
+        let results = service
+            .search("query", "model", 10, 0, 0.0, None)
+            .await
+            .unwrap();
+        let sources: Vec<&str> = results.iter().map(|r| r.document.source.as_str()).collect();
+        assert_eq!(sources, ["best.txt", "near.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_exclusion_demotes_results_matching_both() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for (prompt, embedding) in [("rust", [1.0, 0.0, 0.0]), ("async", [0.0, 1.0, 0.0])] {
+            Mock::given(method("POST"))
+                .and(path("/api/embeddings"))
+                .and(body_partial_json(serde_json::json!({ "prompt": prompt })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "embedding": embedding
+                })))
+                .mount(&server)
+                .await;
+        }
+
+        let mut store = VectorStore::in_memory().unwrap();
+        for (source, vector) in [
+            // Closest to the query, but also close to the exclusion
+            ("async-rust.md", vec![0.9, 0.4, 0.0]),
+            ("rust-basics.md", vec![0.8, 0.0, 0.6]),
+        ] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, source.to_string()))
+                .unwrap();
+            store
+                .upsert_embedding(&crate::domain::Embedding::new(
+                    chunk_id,
+                    "model".to_string(),
+                    vector,
+                ))
+                .unwrap();
+        }
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = SearchService::new(store, ollama);
+
+        let results = service
+            .search("rust", "model", 2, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results[0].document.source, "async-rust.md");
+
+        let results = service
+            .search("rust", "model", 2, 0, 0.0, Some("async"))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].document.source, "rust-basics.md");
+        assert_eq!(results[1].document.source, "async-rust.md");
+        assert!(results[1].similarity < results[0].similarity);
+    }
+
+    #[tokio::test]
+    async fn test_regression_reports_pass_and_fail() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        for (source, vector) in [("rust.md", vec![1.0, 0.1]), ("python.md", vec![0.1, 1.0])] {
+            let doc_id = store
+                .insert_document(&Document::new(source.to_string(), source))
+                .unwrap();
+            let chunk_id = store
+                .insert_chunk(&Chunk::new(doc_id, 0, source.to_string()))
+                .unwrap();
+            store
+                .upsert_embedding(&crate::domain::Embedding::new(
+                    chunk_id,
+                    "model".to_string(),
+                    vector,
+                ))
+                .unwrap();
+        }
+
+        let golden = parse_golden_queries(
+            "# golden queries\nborrow checker -> rust.md\n\nlist comprehension -> python.md\n",
+        )
+        .unwrap();
+        assert_eq!(golden.len(), 2);
+
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = SearchService::new(store, ollama);
+        let outcomes = service.run_regression(&golden, "model", 1).await.unwrap();
+        assert_eq!(outcomes[0].rank, Some(1));
+        assert!(outcomes[0].passed());
+        assert!(!outcomes[1].passed());
+
+        assert!(parse_golden_queries("no arrow here").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_with_openai_provider() {
+        use crate::clients::OpenAiClient;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"index": 0, "embedding": [1.0, 0.0]}]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut store = VectorStore::in_memory().unwrap();
+        let doc_id = store
+            .insert_document(&Document::new("a.txt".to_string(), "a"))
+            .unwrap();
+        let chunk_id = store
+            .insert_chunk(&Chunk::new(doc_id, 0, "a".to_string()))
+            .unwrap();
+        store
+            .upsert_embedding(&crate::domain::Embedding::new(
+                chunk_id,
+                "model".to_string(),
+                vec![1.0, 0.0],
+            ))
+            .unwrap();
+
+        let openai = OpenAiClient::new(server.uri(), Some("sk-test".to_string()), 5).unwrap();
+        let service = SearchService::new(store, openai);
+        let results = service
+            .search("query", "model", 5, 0, 0.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.source, "a.txt");
+    }
+
+    #[tokio::test]
+    async fn test_empty_query_rejected_before_embedding() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service = SearchService::new(store, ollama);
+
+        for query in ["", "   \t\n"] {
+            let result = service.search(query, "model", 10, 0, 0.0, None).await;
+            assert!(matches!(result, Err(VectDbError::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn test_projected_json_contains_only_requested_fields() {
+        let doc = Document::new("test.txt".to_string(), "test content")
+            .with_metadata("secret".to_string(), "hidden".to_string());
+        let result = SearchResult {
+            chunk: Chunk::new(1, 0, "Test chunk".to_string()),
+            document: doc,
+            similarity: 0.5,
+            snippet: None,
+        };
+
+        let fields = parse_fields("source,similarity,content").unwrap();
+        let mut output = Vec::new();
+        write_projected_json(&mut output, &[result], &fields).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let object = parsed[0].as_object().unwrap();
+        let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["content", "similarity", "source"]);
+        assert_eq!(object["source"], "test.txt");
+        assert!(!String::from_utf8(output).unwrap().contains("hidden"));
+
+        assert!(parse_fields("source,vector").is_err());
+    }
+
+    #[test]
+    fn test_mmr_prefers_distinct_over_duplicate() {
+        // Three near-identical chunks and one distinct, less relevant chunk
+        let relevance = [0.95, 0.94, 0.93, 0.80];
+        let vectors = vec![
+            vec![1.0, 0.0],
+            vec![1.0, 0.01],
+            vec![1.0, 0.02],
+            vec![0.0, 1.0],
+        ];
+
+        // Pure relevance keeps the duplicates
+        assert_eq!(mmr_select(&relevance, &vectors, 1.0, 2), vec![0, 1]);
+
+        let selected = mmr_select(&relevance, &vectors, 0.5, 2);
+        assert_eq!(selected, vec![0, 3]);
+
+        assert_eq!(mmr_select(&relevance, &vectors, 0.5, 10).len(), 4);
+    }
+
+    #[test]
+    fn test_batch_ndjson_one_line_per_query() {
+        let queries = parse_batch_queries("q1\tfirst query\nq2\tsecond query\nq3\tthird query\n");
+        let result = SearchResult {
+            chunk: Chunk::new(1, 0, "Test chunk".to_string()),
+            document: Document::new("test.txt".to_string(), "test content"),
+            similarity: 0.5,
+            snippet: None,
+        };
+
+        let mut output = Vec::new();
+        for (idx, query) in queries.iter().enumerate() {
+            let results = vec![result.clone(); idx];
+            write_batch_ndjson(&mut output, query, &results).unwrap();
+        }
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        for (idx, line) in lines.iter().enumerate() {
+            assert_eq!(line["query_id"], format!("q{}", idx + 1));
+            assert_eq!(line["results"].as_array().unwrap().len(), idx);
+        }
+        assert_eq!(lines[1]["query"], "second query");
+    }
+
+    #[tokio::test]
+    async fn test_search_deadline_exceeded() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "embedding": [0.1, 0.2, 0.3] }))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&server)
+            .await;
+
+        let store = VectorStore::in_memory().unwrap();
+        let ollama = OllamaClient::new(server.uri(), 5).unwrap();
+        let service =
+            SearchService::new(store, ollama).with_deadline(Some(Duration::from_millis(50)));
+
+        let err = service
+            .search("slow query", "model", 5, 0, 0.0, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VectDbError::SearchFailed(ref msg) if msg == "deadline exceeded"));
+
+        assert!(
+            check_deadline(
+                Some(Duration::ZERO),
+                Instant::now() - Duration::from_millis(1)
+            )
+            .is_err()
+        );
+        assert!(check_deadline(None, Instant::now() - Duration::from_secs(60)).is_ok());
+    }
+
+    #[test]
+    fn test_parse_boost() {
+        assert_eq!(
+            parse_boost("lang=en:2").unwrap(),
+            ("lang".to_string(), ("en".to_string(), 2.0))
+        );
+        assert!(parse_boost("lang=en").is_err());
+        assert!(parse_boost("lang=en:fast").is_err());
+    }
 }
@@ -0,0 +1,28 @@
+//! Formatting helpers shared by the clients, the CLI and the server
+
+use chrono::DateTime;
+
+/// Format Unix seconds as `YYYY-MM-DD HH:MM UTC`
+pub fn format_utc(timestamp: i64) -> String {
+    match DateTime::from_timestamp(timestamp, 0) {
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M UTC").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Describe an age in seconds as "5 minutes ago", "2 days ago", etc.
+pub fn format_relative(seconds_ago: i64) -> String {
+    if seconds_ago < 0 {
+        return "in the future".to_string();
+    }
+    let (count, unit) = match seconds_ago {
+        0..60 => return "just now".to_string(),
+        60..3600 => (seconds_ago / 60, "minute"),
+        3600..86_400 => (seconds_ago / 3600, "hour"),
+        86_400..2_592_000 => (seconds_ago / 86_400, "day"),
+        2_592_000..31_536_000 => (seconds_ago / 2_592_000, "month"),
+        _ => (seconds_ago / 31_536_000, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}